@@ -25,15 +25,15 @@ use rand::Rng;
 #[derive(Debug, Default)]
 pub struct Ban {
     /// Ban identifier.
-    ban_id: i64,
+    pub ban_id: i64,
     /// Channel identifier where the ban occurred.
-    channel_id: i64,
+    pub channel_id: i64,
     /// User identifier of the banned user.
-    user_id: i64,
+    pub user_id: i64,
     /// Date when the user was banned.
-    banned_at: Option<NaiveDate>,
+    pub banned_at: Option<NaiveDate>,
     /// Reason for the ban.
-    reason: String,
+    pub reason: String,
 }
 
 impl CrudOps for Ban {
@@ -25,17 +25,17 @@ use rand::Rng;
 #[derive(Debug, Default)]
 pub struct Channel {
     /// Channel identifier.
-    channel_id: i64,
+    pub channel_id: i64,
     /// Name of the channel.
-    channel_name: String,
+    pub channel_name: String,
     /// Topic identifier.
-    topic: i32,
+    pub topic: i32,
     /// User identifier of the creator.
-    created_by: i64,
+    pub created_by: i64,
     /// Channel creation date.
-    creator: Option<NaiveDate>,
+    pub creator: Option<NaiveDate>,
     /// Is the channel private?
-    is_private: bool,
+    pub is_private: bool,
 }
 
 impl CrudOps for Channel {
@@ -25,17 +25,17 @@ use rand::Rng;
 #[derive(Debug, Default)]
 pub struct Log {
     /// Log identifier.
-    log_id: i64,
+    pub log_id: i64,
     /// Type of the event logged.
-    event_type: String,
+    pub event_type: String,
     /// User identifier associated with the event.
-    user_id: i64,
+    pub user_id: i64,
     /// Channel identifier associated with the event.
-    channel_id: i64,
+    pub channel_id: i64,
     /// Timestamp of when the event occurred.
-    timestamp: Option<NaiveDateTime>,
+    pub timestamp: Option<NaiveDateTime>,
     /// Additional details about the event.
-    details: String,
+    pub details: String,
 }
 
 impl CrudOps for Log {
@@ -76,7 +76,7 @@ impl CrudOps for Log {
         self.event_type = format!("event_type_{}", rng.gen_range(1..100));
         self.user_id = rng.gen_range(1..10000);
         self.channel_id = rng.gen_range(1..10000);
-        self.timestamp = Some(NaiveDateTime::from_timestamp(chrono::Utc::now().timestamp(), 0));
+        self.timestamp = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp(), 0).map(|time| time.naive_utc());
         self.details = format!("Details for event {}", rng.gen_range(1..10000)); // Random details.
 
         // Insert the new log into the database.
@@ -26,17 +26,17 @@ use serde_json::json;
 #[derive(Debug, Default)]
 pub struct Message {
     /// Message identifier.
-    message_id: i64,
+    pub message_id: i64,
     /// Channel identifier where the message was sent.
-    channel_id: i64,
+    pub channel_id: i64,
     /// User identifier of the sender.
-    user_id: i64,
+    pub user_id: i64,
     /// The text of the message.
-    message_text: String,
+    pub message_text: String,
     /// Timestamp of when the message was sent.
-    timestamp: Option<NaiveDateTime>,
+    pub timestamp: Option<NaiveDateTime>,
     /// JSON data containing flexible profile information.
-    message_data: serde_json::Value,
+    pub message_data: serde_json::Value,
 }
 
 impl CrudOps for Message {
@@ -77,7 +77,7 @@ impl CrudOps for Message {
         self.channel_id = rng.gen_range(1..10000);
         self.user_id = rng.gen_range(1..10000);
         self.message_text = format!("This is a random message {}", rng.gen_range(1..10000));
-        self.timestamp = Some(NaiveDateTime::from_timestamp(chrono::Utc::now().timestamp(), 0));
+        self.timestamp = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp(), 0).map(|time| time.naive_utc());
 
         // Create a JSON object for message_data.
         self.message_data = json!({
@@ -111,15 +111,15 @@ impl CrudOps for Message {
 #[derive(Debug, Default)]
 pub struct Reaction {
     /// Reaction identifier.
-    reaction_id: i64,
+    pub reaction_id: i64,
     /// Message identifier associated with the reaction.
-    message_id: i64,
+    pub message_id: i64,
     /// User identifier who made the reaction.
-    user_id: i64,
+    pub user_id: i64,
     /// Timestamp of when the reaction was made.
-    timestamp: Option<NaiveDateTime>,
+    pub timestamp: Option<NaiveDateTime>,
     /// Type of the reaction (e.g., "like", "dislike").
-    reaction_type: String,
+    pub reaction_type: String,
 }
 
 impl CrudOps for Reaction {
@@ -158,7 +158,7 @@ impl CrudOps for Reaction {
         // Generate random values.
         self.message_id = rng.gen_range(1..10000);
         self.user_id = rng.gen_range(1..10000);
-        self.timestamp = Some(NaiveDateTime::from_timestamp(chrono::Utc::now().timestamp(), 0));
+        self.timestamp = chrono::DateTime::from_timestamp(chrono::Utc::now().timestamp(), 0).map(|time| time.naive_utc());
         self.reaction_type = format!("reaction_type_{}", rng.gen_range(1..5)); // Random reaction type.
 
         // Insert the new reaction into the database.
@@ -23,7 +23,7 @@ mod bans;
 mod settings;
 mod logs;
 
-pub use user::{User, ChannelUser, UserProfile};
+pub use user::{User, ChannelUser, UserProfile, ProfileData};
 pub use channel::Channel;
 pub use message::{Message, Reaction};
 pub use bans::Ban;
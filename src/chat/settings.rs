@@ -24,11 +24,11 @@ use rand::Rng;
 #[derive(Debug, Default)]
 pub struct ServerSetting {
     /// Setting identifier.
-    setting_id: i64,
+    pub setting_id: i64,
     /// Name of the setting.
-    settings_name: String,
+    pub settings_name: String,
     /// Value of the setting.
-    settings_value: i32,
+    pub settings_value: i32,
 }
 
 impl CrudOps for ServerSetting {
@@ -87,13 +87,13 @@ impl CrudOps for ServerSetting {
 #[derive(Debug, Default)]
 pub struct ChannelSetting {
     /// Setting identifier.
-    setting_id: i64,
+    pub setting_id: i64,
     /// Channel identifier associated with the setting.
-    channel_id: i64,
+    pub channel_id: i64,
     /// Name of the setting.
-    setting_name: String,
+    pub setting_name: String,
     /// Value of the setting.
-    setting_value: String,
+    pub setting_value: String,
 }
 
 impl CrudOps for ChannelSetting {
@@ -155,13 +155,13 @@ impl CrudOps for ChannelSetting {
 #[derive(Debug, Default)]
 pub struct UserSetting {
     /// Setting identifier.
-    settings_id: i64,
+    pub settings_id: i64,
     /// User identifier associated with the setting.
-    user_id: i64,
+    pub user_id: i64,
     /// Name of the setting.
-    settings_name: String,
+    pub settings_name: String,
     /// Value of the setting.
-    settings_value: String,
+    pub settings_value: String,
 }
 
 impl CrudOps for UserSetting {
@@ -227,9 +227,9 @@ impl CrudOps for UserSetting {
 #[derive(Debug, Default)]
 pub struct ServerSettingKV {
     /// Name of the setting.
-    settings_name: String,
+    pub settings_name: String,
     /// Value of the setting.
-    settings_value: String,
+    pub settings_value: String,
 }
 
 impl CrudOps for ServerSettingKV {
@@ -238,7 +238,8 @@ impl CrudOps for ServerSettingKV {
         let content = String::from(
             r#"
             setting_name VARCHAR(255),
-            setting_value VARCHAR(255),
+            setting_value TEXT,
+            expires_at DATETIME(6),
             PRIMARY KEY(setting_name)
             "#
         );
@@ -287,11 +288,11 @@ impl CrudOps for ServerSettingKV {
 #[derive(Debug, Default)]
 pub struct ChannelSettingKV {
     /// Channel identifier.
-    channel_id: i64,
+    pub channel_id: i64,
     /// Name of the setting.
-    settings_name: String,
+    pub settings_name: String,
     /// Value of the setting.
-    settings_value: String,
+    pub settings_value: String,
 }
 
 impl CrudOps for ChannelSettingKV {
@@ -299,9 +300,10 @@ impl CrudOps for ChannelSettingKV {
         let name = "Channel_Settings_KV".to_string();
         let content = String::from(
             r#"
-            channel_id BIGINT AUTO_INCREMENT UNIQUE,
-            setting_name VARCHAR(255),
-            setting_value VARCHAR(255),
+            channel_id BIGINT NOT NULL,
+            setting_name VARCHAR(255) NOT NULL,
+            setting_value TEXT,
+            expires_at DATETIME(6),
             PRIMARY KEY(channel_id, setting_name)
             "#
         );
@@ -336,7 +338,7 @@ impl CrudOps for ChannelSettingKV {
             VALUES (?, ?, ?)
             "#,
         )
-            .bind(&self.channel_id)
+            .bind(self.channel_id)
             .bind(&self.settings_name)
             .bind(&self.settings_value)
             .execute(pool)
@@ -362,9 +364,10 @@ impl CrudOps for UserSettingKV {
         let name = "User_Settings_KV".to_string();
         let content = String::from(
             r#"
-            user_id BIGINT AUTO_INCREMENT UNIQUE,
-            setting_name VARCHAR(255),
-            setting_value VARCHAR(255),
+            user_id BIGINT NOT NULL,
+            setting_name VARCHAR(255) NOT NULL,
+            setting_value TEXT,
+            expires_at DATETIME(6),
             PRIMARY KEY(user_id, setting_name)
             "#
         );
@@ -399,7 +402,7 @@ impl CrudOps for UserSettingKV {
             VALUES (?, ?, ?)
             "#,
         )
-            .bind(&self.user_id)
+            .bind(self.user_id)
             .bind(&self.settings_name)
             .bind(&self.settings_value)
             .execute(pool)
@@ -17,13 +17,14 @@
 //! User related structs.
 
 use crate::db::{create_table, CrudOps};
+use serde::{Deserialize, Serialize};
 use chrono::NaiveDate;
-use serde_json::json;
+use serde_json::{json, Map, Value};
 use sqlx::MySqlPool;
 use rand::Rng;
 
 /// User table.
-#[derive(Debug, Default, sqlx::FromRow)]
+#[derive(Debug, Default, Clone, PartialEq, sqlx::FromRow, Serialize, Deserialize)]
 pub struct User {
     /// User identifier.
     pub user_id: i64,
@@ -104,15 +105,15 @@ impl CrudOps for User {
 #[derive(Debug, Default)]
 pub struct ChannelUser {
     /// Channel user identifier.
-    channel_user_id: i64,
+    pub channel_user_id: i64,
     /// Channel identifier.
-    channel_id: i64,
+    pub channel_id: i64,
     /// User identifier.
-    user_id: i64,
+    pub user_id: i64,
     /// Date when the user joined the channel.
-    joined_at: Option<NaiveDate>,
+    pub joined_at: Option<NaiveDate>,
     /// Role of the user in the channel.
-    role: String,
+    pub role: String,
 }
 
 impl CrudOps for ChannelUser {
@@ -120,8 +121,8 @@ impl CrudOps for ChannelUser {
         let name = "Channel_Users".to_string();
         let content = String::from(
             r#"
-            channel_user_id BIGINT,
-            channel_id BIGINT AUTO_INCREMENT UNIQUE,
+            channel_user_id BIGINT AUTO_INCREMENT,
+            channel_id BIGINT,
             user_id BIGINT,
             joined_at DATE,
             role TEXT,
@@ -175,8 +176,28 @@ impl CrudOps for ChannelUser {
     }
 }
 
+/// Typed user profile data stored in `User_Profiles.profile_data`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileData {
+    /// Biography of the user.
+    #[serde(default)]
+    pub bio: String,
+    /// URL of the user's profile picture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_picture_url: Option<String>,
+    /// Location of the user.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Other flexible profile fields.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
 /// UserProfile table.
-#[derive(Debug, Default)]
+///
+/// `profile_data` is the source of truth, profile columns are kept
+/// in sync with it by triggers.
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct UserProfile {
     /// Profile identifier.
     pub profile_id: i64,
@@ -192,6 +213,42 @@ pub struct UserProfile {
     pub profile_data: serde_json::Value,
 }
 
+impl UserProfile {
+    /// Construct UserProfile object from stored profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id`   - given profile identifier.
+    /// - `user_id`      - given user identifier.
+    /// - `profile_data` - given profile data.
+    ///
+    /// # Returns
+    /// - New `UserProfile` object - in case of success.
+    /// - `serde_json::Error` - if profile data is not a valid `ProfileData`.
+    pub fn from_data(profile_id: i64, user_id: i64, profile_data: Value)
+        -> Result<Self, serde_json::Error>
+    {
+        let data = serde_json::from_value::<ProfileData>(profile_data.clone())?;
+
+        Ok(Self {
+            profile_id,
+            user_id,
+            bio:                 data.bio,
+            profile_picture_url: data.profile_picture_url.unwrap_or_default(),
+            location:            data.location.unwrap_or_default(),
+            profile_data,
+        })
+    }
+
+    /// Get typed profile data.
+    ///
+    /// # Returns
+    /// - Profile data - in case of success.
+    /// - `serde_json::Error` - otherwise.
+    pub fn data(&self) -> Result<ProfileData, serde_json::Error> {
+        serde_json::from_value(self.profile_data.clone())
+    }
+}
+
 impl CrudOps for UserProfile {
     async fn create(pool: &MySqlPool) -> Result<(), sqlx::Error> {
         let name = "User_Profiles".to_string();
@@ -203,6 +260,7 @@ impl CrudOps for UserProfile {
             profile_picture_url TEXT,
             location TEXT,
             profile_data JSON,
+            version BIGINT NOT NULL DEFAULT 1,
             PRIMARY KEY(profile_id)
             "#
         );
@@ -213,12 +271,27 @@ impl CrudOps for UserProfile {
         Ok(())
     }
 
-    async fn update(&self, _pool: &MySqlPool) -> Result<(), sqlx::Error> {
-        todo!()
+    async fn update(&self, pool: &MySqlPool) -> Result<(), sqlx::Error> {
+        // Profile columns are updated from profile_data by trigger.
+        let query = "UPDATE User_Profiles SET user_id = ?, profile_data = ? WHERE profile_id = ?;";
+
+        sqlx::query(query)
+            .bind(self.user_id)
+            .bind(&self.profile_data)
+            .bind(self.profile_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
     }
 
-    async fn delete(&self, _pool: &MySqlPool) -> Result<(), sqlx::Error> {
-        todo!()
+    async fn delete(&self, pool: &MySqlPool) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM User_Profiles WHERE profile_id = ?;")
+            .bind(self.profile_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
     }
 
     async fn fill_random(&mut self, pool: &MySqlPool)
@@ -0,0 +1,372 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Document collection aggregation pipeline related declarations.
+
+use crate::db::collection::{validate_name, validate_path, Collection, Filter};
+use crate::db::error::DbError;
+
+/// Sort order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Get SQL representation of the order.
+    ///
+    /// # Returns
+    /// - SQL sort order.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc  => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Group accumulator.
+///
+/// Numeric accumulators convert values at path to `DOUBLE`, documents
+/// without the path are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Accumulator {
+    /// Number of grouped rows.
+    Count,
+    /// Sum of values at JSON path.
+    Sum(String),
+    /// Average of values at JSON path.
+    Avg(String),
+    /// Minimum of values at JSON path.
+    Min(String),
+    /// Maximum of values at JSON path.
+    Max(String),
+}
+
+/// Aggregation pipeline over document collection.
+///
+/// Stages are applied in fixed order: unwind, match, group, sort & limit.
+/// Every result row is JSON object: grouped rows contain group keys and
+/// accumulators by their names, ungrouped rows are documents with unwound
+/// arrays replaced by their elements.
+///
+/// After `unwind("$.items")` path `$.items` (and paths inside it, e.g.
+/// `$.items.price`) refers to single array element.
+///
+/// # Example
+/// ```ignore
+/// // Top 10 channels by number of messages.
+/// let pipeline = Pipeline::new()
+///     .group("channel_id", "$.channel_id")
+///     .count("messages")
+///     .sort("$.messages", SortOrder::Desc)
+///     .limit(10);
+///
+/// let rows = doc_db.messages()?.aggregate(&pipeline).await?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pipeline {
+    /// Unwound array paths.
+    unwinds: Vec<String>,
+    /// Document filters.
+    filters: Vec<Filter>,
+    /// Group keys (output name & JSON path).
+    keys: Vec<(String, String)>,
+    /// Group accumulators (output name & accumulator).
+    accumulators: Vec<(String, Accumulator)>,
+    /// Sort keys (JSON path in result row & order).
+    sort: Vec<(String, SortOrder)>,
+    /// Maximum number of result rows.
+    limit: Option<u64>,
+}
+
+impl Pipeline {
+    /// Construct new empty Pipeline object.
+    ///
+    /// # Returns
+    /// - New `Pipeline` object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add unwind stage producing row for every element of JSON array.
+    ///
+    /// # Parameters
+    /// - `path` - given JSON path of array.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn unwind(mut self, path: &str) -> Self {
+        self.unwinds.push(path.to_string());
+        self
+    }
+
+    /// Add match stage keeping only rows matching filter.
+    ///
+    /// # Parameters
+    /// - `filter` - given document filter.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Add group key.
+    ///
+    /// # Parameters
+    /// - `name` - given output name.
+    /// - `path` - given grouped JSON path.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn group(mut self, name: &str, path: &str) -> Self {
+        self.keys.push((name.to_string(), path.to_string()));
+        self
+    }
+
+    /// Add group accumulator.
+    ///
+    /// # Parameters
+    /// - `name`        - given output name.
+    /// - `accumulator` - given accumulator.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn accumulate(mut self, name: &str, accumulator: Accumulator) -> Self {
+        self.accumulators.push((name.to_string(), accumulator));
+        self
+    }
+
+    /// Add number of grouped rows.
+    pub fn count(self, name: &str) -> Self {
+        self.accumulate(name, Accumulator::Count)
+    }
+
+    /// Add sum of values at JSON path.
+    pub fn sum(self, name: &str, path: &str) -> Self {
+        self.accumulate(name, Accumulator::Sum(path.to_string()))
+    }
+
+    /// Add average of values at JSON path.
+    pub fn avg(self, name: &str, path: &str) -> Self {
+        self.accumulate(name, Accumulator::Avg(path.to_string()))
+    }
+
+    /// Add minimum of values at JSON path.
+    pub fn min(self, name: &str, path: &str) -> Self {
+        self.accumulate(name, Accumulator::Min(path.to_string()))
+    }
+
+    /// Add maximum of values at JSON path.
+    pub fn max(self, name: &str, path: &str) -> Self {
+        self.accumulate(name, Accumulator::Max(path.to_string()))
+    }
+
+    /// Add sort key.
+    ///
+    /// # Parameters
+    /// - `path`  - given JSON path in result row (e.g. `$.messages`).
+    /// - `order` - given sort order.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn sort(mut self, path: &str, order: SortOrder) -> Self {
+        self.sort.push((path.to_string(), order));
+        self
+    }
+
+    /// Set maximum number of result rows.
+    ///
+    /// # Parameters
+    /// - `limit` - given maximum number of rows.
+    ///
+    /// # Returns
+    /// - Updated pipeline.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Compile pipeline to SQL query.
+    ///
+    /// Names & paths are validated, so they are inlined into query,
+    /// while filter values are passed as query arguments.
+    ///
+    /// # Parameters
+    /// - `collection` - given collection to aggregate.
+    ///
+    /// # Returns
+    /// - Query returning single JSON column & its arguments - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn compile(&self, collection: &Collection) -> Result<(String, Vec<String>), DbError> {
+        let doc         = format!("d.`{}`", collection.doc_column());
+        let mut sources = format!("`{}` AS d", collection.table());
+        let mut unwound = Vec::new();
+
+        // Unwind stage.
+        for (i, path) in self.unwinds.iter().enumerate() {
+            validate_path(path)?;
+
+            let (source, relative) = Self::resolve(&doc, &unwound, path);
+            let alias              = format!("u{i}");
+
+            sources.push_str(&format!(
+                " CROSS JOIN JSON_TABLE({source}, '{relative}[*]' \
+                 COLUMNS (value JSON PATH '$')) AS {alias}"
+            ));
+
+            unwound.push((path.clone(), format!("{alias}.value")));
+        }
+
+        // Match stage.
+        let mut conditions = vec![format!("{doc} IS NOT NULL")];
+        let mut args       = Vec::new();
+
+        for filter in &self.filters {
+            validate_path(&filter.path)?;
+
+            conditions.push(format!(
+                "{} {} CAST(? AS JSON)",
+                Self::extract(&doc, &unwound, &filter.path),
+                filter.op.as_sql()
+            ));
+
+            args.push(filter.value.to_string());
+        }
+
+        // Group stage.
+        let is_grouped = !self.keys.is_empty() || !self.accumulators.is_empty();
+        let mut fields = Vec::new();
+        let mut groups = Vec::new();
+
+        for (name, path) in &self.keys {
+            validate_name(name)?;
+            validate_path(path)?;
+
+            let key = Self::extract(&doc, &unwound, path);
+            fields.push(format!("'{name}', {key}"));
+            groups.push(key);
+        }
+
+        for (name, accumulator) in &self.accumulators {
+            validate_name(name)?;
+
+            let (function, path) = match accumulator {
+                Accumulator::Count     => {
+                    fields.push(format!("'{name}', COUNT(*)"));
+                    continue;
+                }
+                Accumulator::Sum(path) => ("SUM", path),
+                Accumulator::Avg(path) => ("AVG", path),
+                Accumulator::Min(path) => ("MIN", path),
+                Accumulator::Max(path) => ("MAX", path),
+            };
+
+            validate_path(path)?;
+
+            fields.push(format!(
+                "'{name}', {function}(CAST({} AS DOUBLE))",
+                Self::extract(&doc, &unwound, path)
+            ));
+        }
+
+        let result = if is_grouped {
+            format!("JSON_OBJECT({})", fields.join(", "))
+        }
+        else if unwound.is_empty() {
+            doc.clone()
+        }
+        else {
+            // Replace unwound arrays with their elements.
+            let pairs: Vec<String> = unwound
+                .iter()
+                .map(|(path, value)| format!("'{path}', {value}"))
+                .collect();
+
+            format!("JSON_SET({doc}, {})", pairs.join(", "))
+        };
+
+        let mut query = format!(
+            "SELECT {result} AS result FROM {sources} WHERE {}",
+            conditions.join(" AND ")
+        );
+
+        if !groups.is_empty() {
+            query.push_str(&format!(" GROUP BY {}", groups.join(", ")));
+        }
+
+        // Sort & limit stages are applied to result rows.
+        let mut query = format!("SELECT t.result FROM ({query}) AS t");
+
+        if !self.sort.is_empty() {
+            let mut keys = Vec::new();
+
+            for (path, order) in &self.sort {
+                validate_path(path)?;
+                keys.push(format!("JSON_EXTRACT(t.result, '{path}') {}", order.as_sql()));
+            }
+
+            query.push_str(&format!(" ORDER BY {}", keys.join(", ")));
+        }
+
+        if let Some(limit) = self.limit {
+            query.push_str(&format!(" LIMIT {limit}"));
+        }
+
+        Ok((query, args))
+    }
+
+    /// Resolve JSON path against unwound arrays.
+    ///
+    /// # Parameters
+    /// - `doc`     - given document expression.
+    /// - `unwound` - given unwound paths & their element expressions.
+    /// - `path`    - given JSON path.
+    ///
+    /// # Returns
+    /// - Source JSON expression & path relative to it.
+    fn resolve(doc: &str, unwound: &[(String, String)], path: &str) -> (String, String) {
+        // Latest unwind is the innermost one.
+        for (prefix, value) in unwound.iter().rev() {
+            if let Some(rest) = path.strip_prefix(prefix.as_str())
+                && (rest.is_empty() || rest.starts_with(['.', '[']))
+            {
+                return (value.clone(), format!("${rest}"));
+            }
+        }
+
+        (doc.to_string(), path.to_string())
+    }
+
+    /// Get expression extracting value at JSON path.
+    ///
+    /// # Parameters
+    /// - `doc`     - given document expression.
+    /// - `unwound` - given unwound paths & their element expressions.
+    /// - `path`    - given JSON path.
+    ///
+    /// # Returns
+    /// - SQL expression.
+    fn extract(doc: &str, unwound: &[(String, String)], path: &str) -> String {
+        match Self::resolve(doc, unwound, path) {
+            (source, relative) if relative == "$" => source,
+            (source, relative)                    => format!("JSON_EXTRACT({source}, '{relative}')"),
+        }
+    }
+}
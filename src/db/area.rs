@@ -16,29 +16,79 @@
 
 //! Area-specific database manager related declarations.
 
-use crate::{db::ConnectionConfig, chat::create_db_tables};
+use crate::db::search::{self, MessageHit, MessageQuery, ProfileHit, ProfileQuery};
+use crate::{db::{drop_index, ConnectionConfig}, chat::create_db_tables};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 use sqlx::MySqlPool;
 use crate::chat::fill_db_tables;
 
 /// Area enumeration.
-#[derive(Debug, Default, Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Default, Hash, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum Area {
     #[default]
     Unknown,
     Russia,
+    #[serde(rename = "USA")]
     Usa,
 }
 
-impl Area {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Area {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Area::Russia  => "Russia".to_string(),
-            Area::Usa     => "USA".to_string(),
-            Area::Unknown => "".to_string(),
+            Area::Russia  => write!(f, "Russia"),
+            Area::Usa     => write!(f, "USA"),
+            Area::Unknown => write!(f, ""),
         }
     }
 }
 
+impl FromStr for Area {
+    type Err = String;
+
+    /// Parse area from its region name (e.g. "Russia" or "USA").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Russia" => Ok(Area::Russia),
+            "USA"    => Ok(Area::Usa),
+            _        => Err(format!("unknown area: {s}")),
+        }
+    }
+}
+
+
+/// Make membership identifier surrogate key of `Channel_Users`.
+///
+/// Old schema declared `channel_id` as `AUTO_INCREMENT UNIQUE`, so channel
+/// could have single member per area, while membership identifier had to be
+/// chosen by writer. Existing rows are kept as is.
+///
+/// # Parameters
+/// - `pool` - given MySQL connection pool.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+async fn migrate_channel_users(pool: &MySqlPool) -> Result<(), sqlx::Error> {
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'Channel_Users'
+        AND COLUMN_NAME = 'channel_id' AND EXTRA LIKE '%auto_increment%';
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query).fetch_one(pool).await?;
+
+    if count > 0 {
+        let query = "ALTER TABLE `Channel_Users` MODIFY `channel_id` BIGINT, \
+                     MODIFY `channel_user_id` BIGINT NOT NULL AUTO_INCREMENT;";
+        sqlx::query(query).execute(pool).await?;
+    }
+
+    // Unique index is named after its column.
+    drop_index(pool, "Channel_Users", "channel_id").await?;
+    Ok(())
+}
 
 /// Area-specific database manager.
 #[derive(Debug, Default)]
@@ -61,11 +111,11 @@ impl AreaDB {
     /// # Returns
     /// - New `AreaDB` object.
     pub fn new(config: ConnectionConfig, area: Area) -> Self {
-        let mut area_db = AreaDB::default();
-
-        area_db.config = config;
-        area_db.area   = area;
-        area_db
+        AreaDB {
+            pool: None,
+            config,
+            area,
+        }
     }
 
     /// Connect database.
@@ -79,12 +129,23 @@ impl AreaDB {
     pub async fn connect(&mut self, url: &str) -> Result<(), sqlx::Error> {
         let pool = MySqlPool::connect(url).await?;
         create_db_tables(&pool).await?;
+        migrate_channel_users(&pool).await?;
+        search::create_search_indexes(&pool).await?;
         fill_db_tables(&pool, 1).await?;
         self.pool = Some(pool);
 
         Ok(())
     }
 
+    /// Get manager connection pool.
+    ///
+    /// # Returns
+    /// - Manager MySQL connection pool if connected.
+    #[inline(always)]
+    pub fn pool(&self) -> Option<&MySqlPool> {
+        self.pool.as_ref()
+    }
+
     /// Get manager config.
     ///
     /// # Returns
@@ -111,4 +172,40 @@ impl AreaDB {
     pub fn name(&self) -> &String {
         &self.config.database
     }
+
+    /// Search messages of the area.
+    ///
+    /// # Parameters
+    /// - `query` - given search query.
+    ///
+    /// # Returns
+    /// - Vector of hits ordered by relevance - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn search_messages(&self, query: &MessageQuery)
+        -> Result<Vec<MessageHit>, sqlx::Error>
+    {
+        if let Some(pool) = &self.pool {
+            return search::search_messages(pool, self.area, query).await;
+        }
+
+        Err(sqlx::Error::PoolClosed)
+    }
+
+    /// Search user profiles of the area by bio & location.
+    ///
+    /// # Parameters
+    /// - `query` - given search query.
+    ///
+    /// # Returns
+    /// - Vector of hits ordered by relevance - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn search_profiles(&self, query: &ProfileQuery)
+        -> Result<Vec<ProfileHit>, sqlx::Error>
+    {
+        if let Some(pool) = &self.pool {
+            return search::search_profiles(pool, self.area, query).await;
+        }
+
+        Err(sqlx::Error::PoolClosed)
+    }
 }
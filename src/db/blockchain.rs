@@ -14,10 +14,224 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use serde_json::Value;
+use std::ops::RangeInclusive;
+use chrono::{NaiveDateTime, Timelike, Utc};
+use sha2::{Digest, Sha256};
 use sqlx::MySqlPool;
-use crate::chat::{create_db_tables, fill_db_tables, UserSettingKV};
-use crate::db::{create_db, create_table, ConnectionConfig};
+use crate::chat::{create_db_tables, fill_db_tables};
+use crate::db::{add_column, create_db, create_table, ConnectionConfig};
+
+/// Number of blocks read at once during chain verification.
+const VERIFY_BATCH_SIZE: u32 = 1000;
+
+/// Table whose single row is locked by block appenders.
+pub const CHAIN_HEAD_TABLE: &str = "Chain_Head";
+
+/// Identifier of chain head row.
+const CHAIN_ID: i64 = 1;
+
+/// Columns of Message_Block table read into `Block`.
+const BLOCK_COLUMNS: &str = "block_id, previous_block_hash, message_id, user_id, channel_id, \
+                             message_text, timestamp, block_hash, hash_version";
+
+/// Domain separator of canonical block encoding.
+const CANONICAL_DOMAIN: &[u8] = b"Message_Block/v1";
+
+/// Format of block timestamp as stored in `DATETIME` column.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Scheme of block hashing, stored with every block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashVersion {
+    /// `SHA2(CONCAT(previous_block_hash, message_text, timestamp), 256)`
+    /// computed by `SendMessage` procedure before versioned hashing.
+    Legacy = 0,
+    /// SHA-256 of canonical encoding of all block fields
+    /// (see `Block::canonical_bytes`).
+    Canonical = 1,
+}
+
+impl HashVersion {
+    /// Scheme used for new blocks.
+    pub const CURRENT: Self = HashVersion::Canonical;
+}
+
+impl TryFrom<i64> for HashVersion {
+    type Error = i64;
+
+    fn try_from(version: i64) -> Result<Self, Self::Error> {
+        match version {
+            0 => Ok(HashVersion::Legacy),
+            1 => Ok(HashVersion::Canonical),
+            _ => Err(version),
+        }
+    }
+}
+
+/// Message_Block table entry.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Block {
+    /// Block identifier, position of the block in chain.
+    pub block_id: i64,
+    /// Hash of preceding block (empty for the first block).
+    pub previous_block_hash: Option<String>,
+    /// Message identifier.
+    pub message_id: Option<i64>,
+    /// Identifier of message author.
+    pub user_id: Option<i64>,
+    /// Channel identifier.
+    pub channel_id: Option<i64>,
+    /// Message text.
+    pub message_text: Option<String>,
+    /// Time of the message.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Stored block hash.
+    pub block_hash: Option<String>,
+    /// Hashing scheme of the block (see `HashVersion`).
+    pub hash_version: i64,
+}
+
+impl Block {
+    /// Encode block fields canonically.
+    ///
+    /// Encoding starts with domain separator followed by block identifier,
+    /// previous block hash, message identifier, user identifier, channel
+    /// identifier, message text & timestamp. Every field is encoded as `0`
+    /// byte if it is `NULL`, otherwise as `1` byte followed by 8 bytes of
+    /// big-endian integer or by 8 bytes of big-endian length & UTF-8 bytes
+    /// of string. Timestamp is encoded as `YYYY-MM-DD hh:mm:ss` string.
+    ///
+    /// # Returns
+    /// - Canonical block encoding.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        fn put_int(bytes: &mut Vec<u8>, value: Option<i64>) {
+            match value {
+                Some(value) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+
+        fn put_str(bytes: &mut Vec<u8>, value: Option<&str>) {
+            match value {
+                Some(value) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(value.len() as u64).to_be_bytes());
+                    bytes.extend_from_slice(value.as_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+
+        let timestamp = self.timestamp.map(|timestamp| timestamp.format(TIMESTAMP_FORMAT).to_string());
+        let mut bytes = CANONICAL_DOMAIN.to_vec();
+
+        put_int(&mut bytes, Some(self.block_id));
+        put_str(&mut bytes, self.previous_block_hash.as_deref());
+        put_int(&mut bytes, self.message_id);
+        put_int(&mut bytes, self.user_id);
+        put_int(&mut bytes, self.channel_id);
+        put_str(&mut bytes, self.message_text.as_deref());
+        put_str(&mut bytes, timestamp.as_deref());
+
+        bytes
+    }
+
+    /// Compute block hash with its hashing scheme.
+    ///
+    /// # Returns
+    /// - Lowercase hex SHA-256 hash or `None` if legacy hash is `NULL` -
+    ///   in case of success.
+    /// - Unsupported hashing scheme version - otherwise.
+    pub fn compute_hash(&self) -> Result<Option<String>, i64> {
+        let bytes = match HashVersion::try_from(self.hash_version)? {
+            // Any `NULL` argument makes `CONCAT` & thus the hash `NULL`.
+            HashVersion::Legacy => {
+                let (Some(previous), Some(text), Some(timestamp)) =
+                    (&self.previous_block_hash, &self.message_text, self.timestamp)
+                else {
+                    return Ok(None);
+                };
+
+                format!("{previous}{text}{}", timestamp.format(TIMESTAMP_FORMAT)).into_bytes()
+            }
+            HashVersion::Canonical => self.canonical_bytes(),
+        };
+
+        Ok(Some(hex::encode(Sha256::digest(bytes))))
+    }
+}
+
+/// Transaction_Log table entry.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Transaction {
+    /// Transaction identifier.
+    pub transaction_id: i64,
+    /// Type of the action (e.g. "SEND_MESSAGE").
+    pub action_type: String,
+    /// Channel identifier associated with the action.
+    pub channel_id: i64,
+    /// Time of the transaction.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Hash of the block created by the transaction.
+    pub block_hash: String,
+}
+
+/// Reason why block fails verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockFault {
+    /// Stored block hash differs from hash recomputed from block content.
+    HashMismatch {
+        /// Recomputed block hash.
+        expected: Option<String>,
+        /// Stored block hash.
+        actual: Option<String>,
+    },
+    /// Stored previous block hash differs from hash of preceding block
+    /// (or from the greatest hash of preceding blocks for legacy block).
+    BrokenLink {
+        /// Expected previous block hash (empty for the first block).
+        expected: Option<String>,
+        /// Stored previous block hash.
+        actual: Option<String>,
+    },
+    /// Block is hashed with unknown scheme.
+    UnsupportedVersion(i64),
+}
+
+/// Block failing verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenBlock {
+    /// Block identifier.
+    pub block_id: i64,
+    /// Reason of failure.
+    pub fault: BlockFault,
+}
+
+/// Result of chain verification.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainReport {
+    /// Number of verified blocks.
+    pub checked: u64,
+    /// The first block failing verification.
+    pub first_broken: Option<BrokenBlock>,
+    /// Ranges of block identifiers missing from the chain.
+    pub missing_blocks: Vec<RangeInclusive<i64>>,
+    /// Transactions whose block hash matches no block.
+    pub orphan_transactions: Vec<Transaction>,
+}
+
+impl ChainReport {
+    /// Check whether chain is intact.
+    ///
+    /// # Returns
+    /// - `true` if no problems were found, `false` otherwise.
+    pub fn is_valid(&self) -> bool {
+        self.first_broken.is_none() && self.missing_blocks.is_empty() && self.orphan_transactions.is_empty()
+    }
+}
 
 /// Blockchain database Manager.
 #[derive(Debug, Default)]
@@ -80,6 +294,9 @@ impl BlockchainDBManager {
 
             create_table(pool, &"Message_Block".to_string(), &content).await?;
 
+            // Blocks created before versioned hashing use legacy scheme.
+            add_column(pool, "Message_Block", "hash_version", "TINYINT NOT NULL DEFAULT 0").await?;
+
             let content = String::from(
                 r#"
                 transaction_id BIGINT PRIMARY KEY  AUTO_INCREMENT UNIQUE,
@@ -92,6 +309,12 @@ impl BlockchainDBManager {
             );
 
             create_table(pool, &"Transaction_Log".to_string(), &content).await?;
+
+            // Appenders lock the single row of chain head before reading last block.
+            create_table(pool, &CHAIN_HEAD_TABLE.to_string(), &"chain_id TINYINT PRIMARY KEY".to_string()).await?;
+
+            let query = format!("INSERT IGNORE INTO {CHAIN_HEAD_TABLE} (chain_id) VALUES (?);");
+            sqlx::query(&query).bind(CHAIN_ID).execute(pool).await?;
         }
 
         Ok(())
@@ -99,46 +322,76 @@ impl BlockchainDBManager {
 
     pub async fn set_procedures(&self) -> Result<(), sqlx::Error> {
         if let Some(pool) = &self.pool {
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS SendMessage;").execute(pool).await?;
+
+            // Procedure is kept for SQL clients & hashes blocks the same way
+            // as `send_message` (see `Block::canonical_bytes`).
             let query =
                 r#"
-                CREATE PROCEDURE IF NOT EXISTS SendMessage(
+                CREATE PROCEDURE SendMessage(
                     IN p_user_id BIGINT,
                     IN p_channel_id BIGINT,
-                    IN p_message_text TEXT
+                    IN p_message_text TEXT CHARACTER SET utf8mb4
                 )
                 BEGIN
-                    DECLARE v_block_id BIGINT;
-                    DECLARE v_previous_block_hash VARCHAR(64);
+                    DECLARE v_block_id BIGINT DEFAULT 1;
+                    DECLARE v_previous_block_hash VARCHAR(64) DEFAULT '';
                     DECLARE v_block_hash VARCHAR(64);
-
-                    -- Получаем идентификатор последнего блока
-                    SELECT COALESCE(MAX(block_id), 0) + 1
-                    INTO v_block_id
-                    FROM Message_Block;
-
-                    -- Получение хэша предыдущего блока.
-                    SELECT COALESCE(MAX(block_hash), '')
-                    INTO v_previous_block_hash
-                    FROM Message_Block;
-
-                    -- Вычисление хэша текущего блока.
-                    SET v_block_hash = SHA2(
-                        CONCAT(v_previous_block_hash, p_message_text, NOW()),
-                        256
-                    );
+                    DECLARE v_timestamp DATETIME;
+                    DECLARE v_chain_id TINYINT;
+
+                    DECLARE EXIT HANDLER FOR SQLEXCEPTION
+                    BEGIN
+                        ROLLBACK;
+                        RESIGNAL;
+                    END;
+
+                    START TRANSACTION;
+
+                    -- Блокировка головы цепочки для последовательного добавления блоков.
+                    SELECT chain_id INTO v_chain_id
+                    FROM Chain_Head WHERE chain_id = 1 FOR UPDATE;
+
+                    -- Получение идентификатора и хэша последнего блока.
+                    SELECT block_id + 1, COALESCE(block_hash, '')
+                    INTO v_block_id, v_previous_block_hash
+                    FROM Message_Block ORDER BY block_id DESC LIMIT 1 FOR UPDATE;
+
+                    -- Время хэшируется с точностью DATETIME, как и хранится.
+                    SET v_timestamp = UTC_TIMESTAMP();
+
+                    -- Вычисление хэша каноничного представления блока.
+                    SET v_block_hash = SHA2(CONCAT(
+                        'Message_Block/v1',
+                        X'01', UNHEX(LPAD(HEX(v_block_id), 16, '0')),
+                        X'01', UNHEX(LPAD(HEX(LENGTH(v_previous_block_hash)), 16, '0')),
+                        v_previous_block_hash,
+                        X'00',
+                        IF(p_user_id IS NULL, X'00',
+                            CONCAT(X'01', UNHEX(LPAD(HEX(p_user_id), 16, '0')))),
+                        IF(p_channel_id IS NULL, X'00',
+                            CONCAT(X'01', UNHEX(LPAD(HEX(p_channel_id), 16, '0')))),
+                        IF(p_message_text IS NULL, X'00',
+                            CONCAT(X'01', UNHEX(LPAD(HEX(LENGTH(p_message_text)), 16, '0')),
+                            CAST(p_message_text AS BINARY))),
+                        X'01', UNHEX(LPAD(HEX(19), 16, '0')),
+                        DATE_FORMAT(v_timestamp, '%Y-%m-%d %H:%i:%s')
+                    ), 256);
 
                     -- Вставка сообщения в блокчейн.
                     INSERT INTO Message_Block (block_id, previous_block_hash,
                     message_id, user_id, channel_id, message_text, timestamp,
-                    block_hash)
+                    block_hash, hash_version)
                     VALUES (v_block_id, v_previous_block_hash, NULL, p_user_id,
-                    p_channel_id, p_message_text, NOW(), v_block_hash);
+                    p_channel_id, p_message_text, v_timestamp, v_block_hash, 1);
 
                     -- Сохранение транзакции.
                     INSERT INTO Transaction_Log (transaction_id, user_id,
                     action_type, channel_id, timestamp, block_hash)
                     VALUES (NULL, p_user_id, 'SEND_MESSAGE', p_channel_id,
-                    NOW(), v_block_hash);
+                    v_timestamp, v_block_hash);
+
+                    COMMIT;
                 END;
                 "#;
 
@@ -183,36 +436,270 @@ impl BlockchainDBManager {
         Ok(())
     }
 
-    pub async fn send_message(&self, user_id: i64, channel_id: i64, msg: &String)
+    /// Append message block to chain & log its transaction.
+    ///
+    /// # Parameters
+    /// - `user_id`    - given identifier of message author.
+    /// - `channel_id` - given channel identifier.
+    /// - `msg`        - given message text.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn send_message(&self, user_id: i64, channel_id: i64, msg: &str)
         -> Result<(), sqlx::Error>
     {
         if let Some(pool) = &self.pool {
-            let query = "CALL SendMessage(?, ?, ?);";
+            let mut transaction = pool.begin().await?;
+
+            // Serialize appends: concurrent senders wait here until the previous one commits.
+            let query = format!("SELECT chain_id FROM {CHAIN_HEAD_TABLE} WHERE chain_id = ? FOR UPDATE;");
+            sqlx::query(&query)
+                .bind(CHAIN_ID)
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            // Locking read sees the latest committed block rather than transaction snapshot.
+            let query = "SELECT block_id, block_hash FROM Message_Block ORDER BY block_id DESC LIMIT 1 FOR UPDATE;";
+            let head: Option<(i64, Option<String>)> = sqlx::query_as(query)
+                .fetch_optional(&mut *transaction)
+                .await?;
+
+            let (block_id, previous_block_hash) = match head {
+                Some((block_id, hash)) => (block_id + 1, hash.unwrap_or_default()),
+                None                   => (1, String::new()),
+            };
+
+            // Timestamp is hashed as stored, so it is truncated to `DATETIME` precision.
+            let timestamp = Utc::now().naive_utc().with_nanosecond(0).unwrap_or_default();
+
+            let mut block = Block {
+                block_id,
+                previous_block_hash: Some(previous_block_hash),
+                message_id:          None,
+                user_id:             Some(user_id),
+                channel_id:          Some(channel_id),
+                message_text:        Some(msg.to_string()),
+                timestamp:           Some(timestamp),
+                block_hash:          None,
+                hash_version:        HashVersion::CURRENT as i64,
+            };
+
+            block.block_hash = block.compute_hash()
+                .map_err(|version| sqlx::Error::Protocol(format!("unsupported hash version {version}")))?;
+
+            let query =
+                r#"
+                INSERT INTO Message_Block (block_id, previous_block_hash, message_id,
+                user_id, channel_id, message_text, timestamp, block_hash, hash_version)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);
+                "#;
 
             sqlx::query(query)
-                .bind(&user_id)
-                .bind(&channel_id)
-                .bind(&msg)
-                .execute(pool)
+                .bind(block.block_id)
+                .bind(&block.previous_block_hash)
+                .bind(block.message_id)
+                .bind(block.user_id)
+                .bind(block.channel_id)
+                .bind(&block.message_text)
+                .bind(block.timestamp)
+                .bind(&block.block_hash)
+                .bind(block.hash_version)
+                .execute(&mut *transaction)
                 .await?;
+
+            let query =
+                r#"
+                INSERT INTO Transaction_Log (user_id, action_type, channel_id, timestamp, block_hash)
+                VALUES (?, 'SEND_MESSAGE', ?, ?, ?);
+                "#;
+
+            sqlx::query(query)
+                .bind(user_id)
+                .bind(channel_id)
+                .bind(block.timestamp)
+                .bind(&block.block_hash)
+                .execute(&mut *transaction)
+                .await?;
+
+            transaction.commit().await?;
         }
 
         Ok(())
     }
 
-    pub async fn get_user_transactions(&self, user_id: i64) -> Result<(), sqlx::Error>
+    /// Get message block.
+    ///
+    /// # Parameters
+    /// - `block_id` - given block identifier.
+    ///
+    /// # Returns
+    /// - Block or `None` if it does not exist - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn get_block(&self, block_id: i64) -> Result<Option<Block>, sqlx::Error> {
+        if let Some(pool) = &self.pool {
+            let query = format!("SELECT {BLOCK_COLUMNS} FROM Message_Block WHERE block_id = ?;");
+
+            let block = sqlx::query_as(query.as_str())
+                .bind(block_id)
+                .fetch_optional(pool)
+                .await?;
+
+            return Ok(block);
+        }
+
+        Err(sqlx::Error::PoolClosed)
+    }
+
+    /// Get transactions of specific user ordered by time.
+    ///
+    /// # Parameters
+    /// - `user_id` - given user identifier.
+    ///
+    /// # Returns
+    /// - Vector of user transactions - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn get_user_transactions(&self, user_id: i64)
+        -> Result<Vec<Transaction>, sqlx::Error>
     {
         if let Some(pool) = &self.pool {
             let query = "CALL GetUserTransactions(?);";
 
-            let rows = sqlx::query(query)
-                .bind(&user_id)
+            let transactions = sqlx::query_as(query)
+                .bind(user_id)
                 .fetch_all(pool)
                 .await?;
 
-            println!("{:#?}", rows);
+            return Ok(transactions);
         }
 
-        Ok(())
+        Err(sqlx::Error::PoolClosed)
+    }
+
+    /// Get number of message blocks in specific channel.
+    ///
+    /// # Parameters
+    /// - `channel_id` - given channel identifier.
+    ///
+    /// # Returns
+    /// - Number of message blocks - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn get_message_count(&self, channel_id: i64) -> Result<i64, sqlx::Error> {
+        if let Some(pool) = &self.pool {
+            let query = "SELECT GetMessageCount(?);";
+
+            let row: (i64,) = sqlx::query_as(query)
+                .bind(channel_id)
+                .fetch_one(pool)
+                .await?;
+
+            return Ok(row.0);
+        }
+
+        Err(sqlx::Error::PoolClosed)
+    }
+
+    /// Verify integrity of message chain.
+    ///
+    /// Blocks are walked in order of identifiers. Hash of every block is
+    /// recomputed with its hashing scheme, and its previous block hash must
+    /// equal hash of preceding block. Legacy blocks were linked to the
+    /// greatest hash of preceding blocks instead (`MAX(block_hash)`), so
+    /// their links are checked by that rule. Gaps in block identifiers are
+    /// reported for the whole chain.
+    ///
+    /// # Parameters
+    /// - `channel_id` - given channel identifier to verify only its blocks &
+    ///   transactions (`None` for the whole chain).
+    ///
+    /// # Returns
+    /// - Chain verification report - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn verify_chain(&self, channel_id: Option<i64>) -> Result<ChainReport, sqlx::Error> {
+        let Some(pool) = &self.pool else {
+            return Err(sqlx::Error::PoolClosed);
+        };
+
+        let query = format!(
+            "SELECT {BLOCK_COLUMNS} FROM Message_Block WHERE block_id > ? ORDER BY block_id LIMIT ?;"
+        );
+
+        let mut report        = ChainReport::default();
+        let mut cursor        = 0;
+        let mut previous_hash = Some(String::new());
+        let mut max_hash      = String::new();
+
+        loop {
+            let blocks: Vec<Block> = sqlx::query_as(query.as_str())
+                .bind(cursor)
+                .bind(VERIFY_BATCH_SIZE)
+                .fetch_all(pool)
+                .await?;
+
+            for block in &blocks {
+                if block.block_id > cursor + 1 {
+                    report.missing_blocks.push(cursor + 1..=block.block_id - 1);
+                }
+
+                let is_checked = channel_id.is_none() || block.channel_id == channel_id;
+
+                if is_checked {
+                    report.checked += 1;
+                }
+
+                if is_checked && report.first_broken.is_none() {
+                    let expected = match HashVersion::try_from(block.hash_version) {
+                        Ok(HashVersion::Legacy) => Some(max_hash.clone()),
+                        _                       => previous_hash.clone(),
+                    };
+
+                    let fault = match block.compute_hash() {
+                        _ if block.previous_block_hash != expected => Some(BlockFault::BrokenLink {
+                            expected,
+                            actual: block.previous_block_hash.clone(),
+                        }),
+                        Err(version) => Some(BlockFault::UnsupportedVersion(version)),
+                        Ok(hash) if hash != block.block_hash => Some(BlockFault::HashMismatch {
+                            expected: hash,
+                            actual:   block.block_hash.clone(),
+                        }),
+                        Ok(_) => None,
+                    };
+
+                    report.first_broken = fault.map(|fault| BrokenBlock { block_id: block.block_id, fault });
+                }
+
+                cursor        = block.block_id;
+                previous_hash = block.block_hash.clone();
+
+                // `MAX` ignores `NULL` hashes.
+                if let Some(hash) = &block.block_hash && *hash > max_hash {
+                    max_hash = hash.clone();
+                }
+            }
+
+            if blocks.len() < VERIFY_BATCH_SIZE as usize {
+                break;
+            }
+        }
+
+        let query =
+            r#"
+            SELECT t.transaction_id, COALESCE(t.action_type, '') AS action_type,
+            COALESCE(t.channel_id, 0) AS channel_id, t.timestamp,
+            COALESCE(t.block_hash, '') AS block_hash
+            FROM Transaction_Log t
+            LEFT JOIN Message_Block b ON b.block_hash = t.block_hash
+            WHERE b.block_id IS NULL AND (? IS NULL OR t.channel_id = ?)
+            ORDER BY t.transaction_id;
+            "#;
+
+        report.orphan_transactions = sqlx::query_as(query)
+            .bind(channel_id)
+            .bind(channel_id)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(report)
     }
 }
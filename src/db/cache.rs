@@ -0,0 +1,387 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! In-process key-value cache related declarations.
+//!
+//! Writes of the same process invalidate cached keys immediately. Writes of
+//! other processes bump versions of namespaces (see `VERSIONS_TABLE`), which
+//! are checked at most once per version check interval. Values are cached
+//! with namespace version read by the same statement, so version bumped
+//! later invalidates them.
+
+use std::{collections::{HashMap, HashSet}, num::NonZeroUsize, sync::Mutex, time::{Duration, Instant}};
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::db::kvdb::Namespace;
+use lru::LruCache;
+
+/// Table storing versions of key-value namespaces by table & scope.
+pub const VERSIONS_TABLE: &str = "KV_Versions";
+
+/// Default maximum number of cached keys.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Default time to keep cached key.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Default interval between checks of table versions.
+pub const DEFAULT_VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Key-value cache config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Maximum number of cached keys.
+    pub capacity: NonZeroUsize,
+    /// Time to keep cached key.
+    pub ttl: Duration,
+    /// Interval between checks of table versions (zero to check on every read).
+    pub version_check_interval: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity:               NonZeroUsize::new(DEFAULT_CAPACITY).unwrap(),
+            ttl:                    DEFAULT_TTL,
+            version_check_interval: DEFAULT_VERSION_CHECK_INTERVAL,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Construct new CacheConfig object.
+    ///
+    /// # Parameters
+    /// - `capacity` - given maximum number of cached keys (at least 1).
+    /// - `ttl`      - given time to keep cached key.
+    ///
+    /// # Returns
+    /// - New `CacheConfig` object.
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity: NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            ttl,
+            ..Default::default()
+        }
+    }
+
+    /// Set interval between checks of table versions.
+    pub fn version_check_interval(mut self, interval: Duration) -> Self {
+        self.version_check_interval = interval;
+        self
+    }
+}
+
+/// Snapshot of cache metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of reads answered from cache.
+    pub hits: u64,
+    /// Number of reads sent to database.
+    pub misses: u64,
+    /// Number of keys evicted due to capacity limit.
+    pub evictions: u64,
+    /// Number of keys invalidated by writes.
+    pub invalidations: u64,
+    /// Number of currently cached keys.
+    pub size: usize,
+}
+
+impl CacheStats {
+    /// Get ratio of reads answered from cache.
+    ///
+    /// # Returns
+    /// - Hit ratio in `[0, 1]` (0 if there were no reads).
+    pub fn hit_ratio(&self) -> f64 {
+        let reads = self.hits + self.misses;
+
+        if reads == 0 {
+            return 0.0;
+        }
+
+        self.hits as f64 / reads as f64
+    }
+}
+
+/// Cached key value.
+#[derive(Debug)]
+struct Entry {
+    /// Value or `None` for missing key.
+    value: Option<String>,
+    /// Time when entry becomes stale.
+    expires_at: Instant,
+}
+
+/// Namespace table & scope (see `Namespace::scope`).
+pub(crate) type Scope = (String, String);
+
+/// Versions of key-value namespaces.
+#[derive(Debug, Default)]
+struct Versions {
+    /// Version of cached values by namespace scope.
+    known: HashMap<Scope, i64>,
+    /// Time of the last check.
+    checked_at: Option<Instant>,
+}
+
+/// Get scope of namespace.
+///
+/// # Parameters
+/// - `namespace` - given key namespace.
+///
+/// # Returns
+/// - Namespace table & scope.
+fn scope(namespace: &Namespace) -> Scope {
+    (namespace.table().to_string(), namespace.scope())
+}
+
+/// LRU cache of key-value store reads.
+#[derive(Debug)]
+pub(crate) struct KvCache {
+    /// Cached values by namespace & key.
+    entries: Mutex<LruCache<(Namespace, String), Entry>>,
+    /// Cache config.
+    config: CacheConfig,
+    /// Table versions.
+    versions: Mutex<Versions>,
+    /// Number of invalidations, used to drop values read before them.
+    epoch: AtomicU64,
+    /// Number of reads answered from cache.
+    hits: AtomicU64,
+    /// Number of reads sent to database.
+    misses: AtomicU64,
+    /// Number of keys evicted due to capacity limit.
+    evictions: AtomicU64,
+    /// Number of keys invalidated by writes.
+    invalidations: AtomicU64,
+}
+
+impl KvCache {
+    /// Construct new KvCache object.
+    ///
+    /// # Parameters
+    /// - `config` - given cache config.
+    ///
+    /// # Returns
+    /// - New `KvCache` object.
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            entries:       Mutex::new(LruCache::new(config.capacity)),
+            config,
+            versions:      Mutex::new(Versions::default()),
+            epoch:         AtomicU64::new(0),
+            hits:          AtomicU64::new(0),
+            misses:        AtomicU64::new(0),
+            evictions:     AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    /// Get cached value of key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - `Some(value)` on hit (value is `None` for cached missing key).
+    /// - `None` on miss.
+    pub(crate) fn get(&self, namespace: &Namespace, key: &str) -> Option<Option<String>> {
+        let mut entries = self.entries.lock().unwrap();
+        let cache_key   = (namespace.clone(), key.to_string());
+
+        let value = match entries.get(&cache_key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.pop(&cache_key);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        value
+    }
+
+    /// Get current invalidation epoch.
+    ///
+    /// Capture it before database read & pass it to `KvCache::put`.
+    ///
+    /// # Returns
+    /// - Invalidation epoch.
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    /// Cache value read from database.
+    ///
+    /// Value is dropped if any key was invalidated since read started or
+    /// if namespace version differs from version of cached values.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given value (`None` for missing key).
+    /// - `ttl`       - given remaining time to live of key (`None` if persistent).
+    /// - `epoch`     - given invalidation epoch captured before read.
+    /// - `version`   - given namespace version read together with value.
+    pub(crate) fn put(
+        &self,
+        namespace: &Namespace,
+        key: &str,
+        value: Option<String>,
+        ttl: Option<Duration>,
+        epoch: u64,
+        version: i64
+    ) {
+        let mut versions = self.versions.lock().unwrap();
+        let mut entries  = self.entries.lock().unwrap();
+
+        if self.epoch() != epoch || *versions.known.entry(scope(namespace)).or_insert(version) != version {
+            return;
+        }
+
+        let ttl       = ttl.map_or(self.config.ttl, |ttl| ttl.min(self.config.ttl));
+        let cache_key = (namespace.clone(), key.to_string());
+        let entry     = Entry { value, expires_at: Instant::now() + ttl };
+
+        if let Some((evicted, _)) = entries.push(cache_key.clone(), entry)
+            && evicted != cache_key
+        {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Invalidate cached key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    pub(crate) fn invalidate(&self, namespace: &Namespace, key: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+
+        if entries.pop(&(namespace.clone(), key.to_string())).is_some() {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Invalidate all cached keys of namespace scopes.
+    ///
+    /// # Parameters
+    /// - `scopes` - given namespace scopes.
+    fn invalidate_scopes(&self, scopes: &HashSet<Scope>) {
+        let mut entries = self.entries.lock().unwrap();
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+
+        let stale: Vec<(Namespace, String)> = entries
+            .iter()
+            .filter(|((namespace, _), _)| scopes.contains(&scope(namespace)))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in stale {
+            entries.pop(&key);
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Check whether table versions should be checked.
+    ///
+    /// # Returns
+    /// - `true` if version check interval elapsed.
+    pub(crate) fn needs_version_check(&self) -> bool {
+        let versions = self.versions.lock().unwrap();
+
+        versions.checked_at.is_none_or(|checked_at| {
+            checked_at.elapsed() >= self.config.version_check_interval
+        })
+    }
+
+    /// Get namespace scopes of cached keys.
+    ///
+    /// Versions of scopes without cached keys are forgotten.
+    ///
+    /// # Returns
+    /// - Namespace scopes whose versions should be checked.
+    pub(crate) fn scopes(&self) -> Vec<Scope> {
+        let mut versions = self.versions.lock().unwrap();
+        let entries      = self.entries.lock().unwrap();
+
+        let cached: HashSet<Scope> = entries
+            .iter()
+            .map(|((namespace, _), _)| scope(namespace))
+            .collect();
+
+        versions.known.retain(|scope, _| cached.contains(scope));
+        versions.known.keys().cloned().collect()
+    }
+
+    /// Apply namespace versions read from database.
+    ///
+    /// Keys of scopes whose version changed are invalidated.
+    ///
+    /// # Parameters
+    /// - `checked` - given checked namespace scopes.
+    /// - `current` - given current versions of checked scopes (missing
+    ///   scopes have version 0).
+    pub(crate) fn apply_versions(&self, checked: &[Scope], current: &HashMap<Scope, i64>) {
+        let mut versions = self.versions.lock().unwrap();
+        let mut changed  = HashSet::new();
+
+        for scope in checked {
+            let current = current.get(scope).copied().unwrap_or(0);
+
+            if let Some(version) = versions.known.get_mut(scope)
+                && *version != current
+            {
+                *version = current;
+                changed.insert(scope.clone());
+            }
+        }
+
+        versions.checked_at = Some(Instant::now());
+        drop(versions);
+
+        if !changed.is_empty() {
+            self.invalidate_scopes(&changed);
+        }
+    }
+
+    /// Invalidate all cached keys.
+    pub(crate) fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+
+        self.invalidations.fetch_add(entries.len() as u64, Ordering::Relaxed);
+        entries.clear();
+    }
+
+    /// Get snapshot of cache metrics.
+    ///
+    /// # Returns
+    /// - Cache metrics.
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits:          self.hits.load(Ordering::Relaxed),
+            misses:        self.misses.load(Ordering::Relaxed),
+            evictions:     self.evictions.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+            size:          self.entries.lock().unwrap().len(),
+        }
+    }
+}
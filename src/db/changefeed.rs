@@ -0,0 +1,499 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Change data capture related declarations.
+//!
+//! Changes of watched tables are written into outbox table by triggers
+//! in the same transaction, so committed changes are never lost. Feed polls
+//! outbox in order of change identifiers, which serve as resumable cursor.
+//! Holes in identifiers are waited for while their transactions are running,
+//! holes of rolled back transactions are skipped after short gap timeout.
+
+use std::{collections::VecDeque, fmt, str::FromStr, time::{Duration, Instant}};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use crate::db::create_table;
+use futures_util::Stream;
+use chrono::NaiveDateTime;
+use sqlx::{mysql::MySqlDatabaseError, MySqlPool};
+use serde_json::Value;
+
+/// Table storing captured changes.
+pub const OUTBOX_TABLE: &str = "Change_Outbox";
+
+/// Default maximum number of changes fetched by single poll.
+pub const DEFAULT_BATCH_SIZE: u64 = 100;
+
+/// Default delay between polls of empty outbox.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Default time to wait for missing changes not locked by any transaction.
+pub const DEFAULT_GAP_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// MySQL error number of lock which can not be acquired with `NOWAIT`.
+const ER_LOCK_NOWAIT: u16 = 3572;
+
+/// Change operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChangeOp {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl ChangeOp {
+    /// Get all operations.
+    const ALL: [ChangeOp; 3] = [ChangeOp::Insert, ChangeOp::Update, ChangeOp::Delete];
+
+    /// Get SQL representation of the operation.
+    ///
+    /// # Returns
+    /// - Operation name stored in outbox.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeOp::Insert => "INSERT",
+            ChangeOp::Update => "UPDATE",
+            ChangeOp::Delete => "DELETE",
+        }
+    }
+}
+
+impl fmt::Display for ChangeOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ChangeOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "INSERT" => Ok(ChangeOp::Insert),
+            "UPDATE" => Ok(ChangeOp::Update),
+            "DELETE" => Ok(ChangeOp::Delete),
+            _        => Err(format!("unknown change operation: {s}")),
+        }
+    }
+}
+
+/// Captured row change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Change identifier, used as feed cursor.
+    pub id: i64,
+    /// Changed table name.
+    pub table: String,
+    /// Change operation.
+    pub op: ChangeOp,
+    /// Primary key of changed row (e.g. `{"message_id": 1}`).
+    pub key: Value,
+    /// Row before change (updates & deletes).
+    pub old: Option<Value>,
+    /// Row after change (inserts & updates).
+    pub new: Option<Value>,
+    /// Time of change.
+    pub created_at: NaiveDateTime,
+}
+
+impl ChangeEvent {
+    /// Decode row before change.
+    ///
+    /// # Returns
+    /// - Row or `None` for inserts - in case of success.
+    /// - `serde_json::Error` - otherwise.
+    pub fn old_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+        self.old.clone().map(serde_json::from_value).transpose()
+    }
+
+    /// Decode row after change.
+    ///
+    /// # Returns
+    /// - Row or `None` for deletes - in case of success.
+    /// - `serde_json::Error` - otherwise.
+    pub fn new_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+        self.new.clone().map(serde_json::from_value).transpose()
+    }
+}
+
+/// Escape string to be used inside single-quoted SQL literal.
+///
+/// # Parameters
+/// - `value` - given string.
+///
+/// # Returns
+/// - Escaped string.
+fn escape_literal(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "''")
+}
+
+/// Generate `JSON_OBJECT` of row columns.
+///
+/// # Parameters
+/// - `row`     - given row alias (`NEW` or `OLD`).
+/// - `columns` - given column names.
+///
+/// # Returns
+/// - SQL expression.
+fn json_row(row: &str, columns: &[String]) -> String {
+    let pairs: Vec<String> = columns
+        .iter()
+        .map(|column| format!(
+            "'{}', {row}.`{}`",
+            escape_literal(column),
+            column.replace('`', "``")
+        ))
+        .collect();
+
+    format!("JSON_OBJECT({})", pairs.join(", "))
+}
+
+/// Create outbox table & change capturing triggers of given tables.
+///
+/// Triggers are recreated, so call it again after table columns change.
+///
+/// # Parameters
+/// - `pool`   - given MySQL connection pool.
+/// - `tables` - given watched table names.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+pub async fn install(pool: &MySqlPool, tables: &[&str]) -> Result<(), sqlx::Error> {
+    let content = String::from(
+        r#"
+        change_id BIGINT AUTO_INCREMENT,
+        table_name VARCHAR(64) NOT NULL,
+        operation VARCHAR(8) NOT NULL,
+        row_key JSON NOT NULL,
+        old_row JSON,
+        new_row JSON,
+        created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+        PRIMARY KEY(change_id)
+        "#
+    );
+
+    create_table(pool, &OUTBOX_TABLE.to_string(), &content).await?;
+
+    for table in tables {
+        // Virtual generated columns (e.g. JSON indexes) are derived data.
+        let query =
+            r#"
+            SELECT CAST(COLUMN_NAME AS CHAR) FROM information_schema.COLUMNS
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+                AND EXTRA NOT LIKE '%VIRTUAL GENERATED%'
+            ORDER BY ORDINAL_POSITION;
+            "#;
+
+        let columns: Vec<String> = sqlx::query_scalar(query)
+            .bind(table)
+            .fetch_all(pool)
+            .await?;
+
+        let query =
+            r#"
+            SELECT CAST(COLUMN_NAME AS CHAR) FROM information_schema.KEY_COLUMN_USAGE
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND CONSTRAINT_NAME = 'PRIMARY'
+            ORDER BY ORDINAL_POSITION;
+            "#;
+
+        let keys: Vec<String> = sqlx::query_scalar(query)
+            .bind(table)
+            .fetch_all(pool)
+            .await?;
+
+        if columns.is_empty() || keys.is_empty() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        for op in ChangeOp::ALL {
+            let (row, old, new) = match op {
+                ChangeOp::Insert => ("NEW", "NULL".to_string(), json_row("NEW", &columns)),
+                ChangeOp::Update => ("NEW", json_row("OLD", &columns), json_row("NEW", &columns)),
+                ChangeOp::Delete => ("OLD", json_row("OLD", &columns), "NULL".to_string()),
+            };
+
+            let trigger = format!("Capture{}_{}", op.as_str(), table);
+            let query   = format!(
+                r#"
+                CREATE TRIGGER `{trigger}`
+                AFTER {op} ON `{table}`
+                FOR EACH ROW
+                BEGIN
+                    DECLARE v_old JSON;
+                    DECLARE v_new JSON;
+
+                    SET v_old = {old};
+                    SET v_new = {new};
+
+                    -- Rows updated without changes are not captured.
+                    IF NOT (v_old <=> v_new) THEN
+                        INSERT INTO {OUTBOX_TABLE} (table_name, operation, row_key, old_row, new_row)
+                        VALUES ('{name}', '{op}', {key}, v_old, v_new);
+                    END IF;
+                END;
+                "#,
+                name = escape_literal(table),
+                key  = json_row(row, &keys),
+            );
+
+            let drop = format!("DROP TRIGGER IF EXISTS `{trigger}`;");
+            sqlx::raw_sql(drop.as_str()).execute(pool).await?;
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete delivered changes from outbox.
+///
+/// # Parameters
+/// - `pool`   - given MySQL connection pool.
+/// - `cursor` - given cursor of the last change delivered to all consumers.
+///
+/// # Returns
+/// - Number of deleted changes - in case of success.
+/// - `sqlx::Error` - otherwise.
+pub async fn prune(pool: &MySqlPool, cursor: i64) -> Result<u64, sqlx::Error> {
+    let query  = format!("DELETE FROM {OUTBOX_TABLE} WHERE change_id <= ?;");
+    let result = sqlx::query(query.as_str()).bind(cursor).execute(pool).await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Change feed polling outbox table.
+///
+/// Change identifiers are allocated before commit, so transaction committed
+/// later may still fill a hole below the last delivered change. Rows of such
+/// transactions are locked, so feed waits for them until they are committed
+/// or rolled back. Holes which are not locked (rolled back transactions,
+/// `auto_increment_increment` above 1) are skipped once they stay unlocked
+/// for gap timeout, which absorbs the moment between identifier allocation
+/// & row insertion. Feed reading from the beginning treats identifiers below
+/// the first change (e.g. pruned ones) as such hole too.
+#[derive(Debug, Clone)]
+pub struct ChangeFeed {
+    /// Manager MySQL connection pool.
+    pool: MySqlPool,
+    /// Identifier of the last delivered change.
+    cursor: i64,
+    /// Watched table names (all tables if empty).
+    tables: Vec<String>,
+    /// Maximum number of changes fetched by single poll.
+    batch_size: u64,
+    /// Delay between polls of empty outbox.
+    poll_interval: Duration,
+    /// Time to wait for unlocked holes in change identifiers.
+    gap_timeout: Duration,
+    /// Time when the hole above cursor was noticed unlocked.
+    gap_since: Option<Instant>,
+}
+
+impl ChangeFeed {
+    /// Construct new ChangeFeed object reading outbox from the beginning.
+    ///
+    /// # Parameters
+    /// - `pool` - given MySQL connection pool.
+    ///
+    /// # Returns
+    /// - New `ChangeFeed` object.
+    pub fn new(pool: MySqlPool) -> Self {
+        Self {
+            pool,
+            cursor:        0,
+            tables:        Vec::new(),
+            batch_size:    DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            gap_timeout:   DEFAULT_GAP_TIMEOUT,
+            gap_since:     None,
+        }
+    }
+
+    /// Construct new ChangeFeed object skipping already captured changes.
+    ///
+    /// # Parameters
+    /// - `pool` - given MySQL connection pool.
+    ///
+    /// # Returns
+    /// - New `ChangeFeed` object - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn tail(pool: MySqlPool) -> Result<Self, sqlx::Error> {
+        let query = format!("SELECT CAST(COALESCE(MAX(change_id), 0) AS SIGNED) FROM {OUTBOX_TABLE};");
+        let cursor: i64 = sqlx::query_scalar(query.as_str()).fetch_one(&pool).await?;
+
+        Ok(Self::new(pool).from_cursor(cursor))
+    }
+
+    /// Resume feed after given change.
+    pub fn from_cursor(mut self, cursor: i64) -> Self {
+        self.cursor    = cursor;
+        self.gap_since = None;
+        self
+    }
+
+    /// Deliver changes of given tables only.
+    pub fn tables(mut self, tables: &[&str]) -> Self {
+        self.tables = tables.iter().map(|table| table.to_string()).collect();
+        self
+    }
+
+    /// Set maximum number of changes fetched by single poll.
+    pub fn batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set delay between polls of empty outbox.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Set time to wait for unlocked holes in change identifiers.
+    pub fn gap_timeout(mut self, gap_timeout: Duration) -> Self {
+        self.gap_timeout = gap_timeout;
+        self
+    }
+
+    /// Get identifier of the last delivered change.
+    ///
+    /// Persist it to resume feed with `ChangeFeed::from_cursor`.
+    ///
+    /// # Returns
+    /// - Feed cursor.
+    #[inline(always)]
+    pub fn cursor(&self) -> i64 {
+        self.cursor
+    }
+
+    /// Fetch next changes & advance cursor.
+    ///
+    /// # Returns
+    /// - Vector of changes (empty if there are no new ones) - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn poll(&mut self) -> Result<Vec<ChangeEvent>, sqlx::Error> {
+        let query = format!(
+            r#"
+            SELECT change_id, table_name, operation, row_key, old_row, new_row, created_at
+            FROM {OUTBOX_TABLE}
+            WHERE change_id > ?
+            ORDER BY change_id
+            LIMIT ?;
+            "#
+        );
+
+        type Row = (i64, String, String, Value, Option<Value>, Option<Value>, NaiveDateTime);
+
+        let rows: Vec<Row> = sqlx::query_as(query.as_str())
+            .bind(self.cursor)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut events = Vec::new();
+
+        for (id, table, op, key, old, new, created_at) in rows {
+            if id != self.cursor + 1 && !self.skip_gap(id - 1).await? {
+                break;
+            }
+
+            self.gap_since = None;
+            self.cursor    = id;
+
+            if !self.tables.is_empty() && !self.tables.contains(&table) {
+                continue;
+            }
+
+            let op = op.parse().map_err(|err: String| sqlx::Error::Decode(err.into()))?;
+            events.push(ChangeEvent { id, table, op, key, old, new, created_at });
+        }
+
+        Ok(events)
+    }
+
+    /// Check whether hole between cursor & given change can be skipped.
+    ///
+    /// Hole is probed with `NOWAIT` locking read, which fails on rows of
+    /// running transactions & returns rows committed since outbox was read.
+    ///
+    /// # Parameters
+    /// - `to` - given the last missing change identifier.
+    ///
+    /// # Returns
+    /// - `true` if hole stayed unlocked & empty for gap timeout - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn skip_gap(&mut self, to: i64) -> Result<bool, sqlx::Error> {
+        let query  = format!(
+            "SELECT change_id FROM {OUTBOX_TABLE} WHERE change_id BETWEEN ? AND ? FOR SHARE NOWAIT;"
+        );
+
+        let result = sqlx::query(query.as_str())
+            .bind(self.cursor + 1)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await;
+
+        let pending = match result {
+            Ok(rows) => !rows.is_empty(),
+            Err(sqlx::Error::Database(err))
+                if err.try_downcast_ref::<MySqlDatabaseError>()
+                    .is_some_and(|err| err.number() == ER_LOCK_NOWAIT) =>
+            {
+                true
+            }
+            Err(err) => return Err(err),
+        };
+
+        if pending {
+            self.gap_since = None;
+            return Ok(false);
+        }
+
+        let gap_since = *self.gap_since.get_or_insert_with(Instant::now);
+        Ok(gap_since.elapsed() >= self.gap_timeout)
+    }
+
+    /// Convert feed into endless stream of changes.
+    ///
+    /// Errors are yielded as stream items & polling is retried after
+    /// poll interval, so consumer decides whether to stop.
+    ///
+    /// # Returns
+    /// - Stream of changes.
+    pub fn into_stream(self) -> impl Stream<Item = Result<ChangeEvent, sqlx::Error>> {
+        let state = (self, VecDeque::new(), false);
+
+        futures_util::stream::unfold(state, |(mut feed, mut buffer, mut wait)| async move {
+            loop {
+                if let Some(event) = buffer.pop_front() {
+                    return Some((Ok(event), (feed, buffer, false)));
+                }
+
+                if wait {
+                    tokio::time::sleep(feed.poll_interval).await;
+                }
+
+                match feed.poll().await {
+                    Ok(events) => {
+                        wait = events.is_empty();
+                        buffer.extend(events);
+                    }
+                    Err(err) => return Some((Err(err), (feed, buffer, true))),
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,1110 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! JSON document collection related declarations.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::{mysql::MySqlArguments, Arguments, MySqlConnection, MySqlPool};
+use serde_json::{Map, Value};
+use crate::db::{aggregate::Pipeline, error::DbError, schema::Schema};
+
+/// Prefix of tables backing named document collections.
+pub const COLLECTION_PREFIX: &str = "Collection_";
+
+/// Prefix of generated columns & indexes backing JSON indexes.
+pub const INDEX_PREFIX: &str = "jx_";
+
+/// Session variable holding author of document updates for triggers.
+pub const AUTHOR_VARIABLE: &str = "@dbproject_author";
+
+/// Check that name can be used as MySQL table or column name.
+///
+/// # Parameters
+/// - `name` - given name.
+///
+/// # Returns
+/// - `Ok` - in case of valid name.
+/// - `DbError::InvalidName` - otherwise.
+pub(crate) fn validate_name(name: &str) -> Result<(), DbError> {
+    let is_valid = !name.is_empty()
+        && name.len() <= 64
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !is_valid {
+        return Err(DbError::InvalidName(name.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Check that JSON path is a simple MySQL JSON path (e.g. `$.user.tags[0]`).
+///
+/// # Parameters
+/// - `path` - given JSON path.
+///
+/// # Returns
+/// - `Ok` - in case of valid path.
+/// - `DbError::InvalidPath` - otherwise.
+pub(crate) fn validate_path(path: &str) -> Result<(), DbError> {
+    let invalid  = || DbError::InvalidPath(path.to_string());
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix('.') {
+            // Member access: `.name`.
+            let len = tail
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(tail.len());
+
+            if len == 0 || tail.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+
+            rest = &tail[len..];
+        }
+        else if let Some(tail) = rest.strip_prefix('[') {
+            // Array access: `[index]`.
+            let end = tail.find(']').ok_or_else(invalid)?;
+
+            if end == 0 || !tail[..end].chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+
+            rest = &tail[end + 1..];
+        }
+        else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(())
+}
+
+/// Document stored in collection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document<T> {
+    /// Document identifier.
+    pub id: i64,
+    /// Document content.
+    pub data: T,
+}
+
+/// Document with its version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    /// Document version, incremented on every update.
+    pub version: i64,
+    /// Document content.
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    /// Get entity tag of document version.
+    ///
+    /// # Returns
+    /// - Strong ETag (e.g. `"3"`).
+    pub fn etag(&self) -> String {
+        etag(self.version)
+    }
+}
+
+/// Get entity tag of document version.
+///
+/// # Parameters
+/// - `version` - given document version.
+///
+/// # Returns
+/// - Strong ETag (e.g. `"3"`).
+pub fn etag(version: i64) -> String {
+    format!("\"{version}\"")
+}
+
+/// Precondition of conditional update (`If-Match` semantics).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfMatch {
+    /// Any existing document version matches (`If-Match: *`).
+    Any,
+    /// Only given document version matches.
+    Version(i64),
+}
+
+impl IfMatch {
+    /// Parse `If-Match` header value.
+    ///
+    /// Weak ETags (e.g. `W/"3"`) are accepted as well, since document
+    /// versions are compared exactly.
+    ///
+    /// # Parameters
+    /// - `header` - given header value (e.g. `"3"` or `*`).
+    ///
+    /// # Returns
+    /// - Parsed precondition - in case of success.
+    /// - `DbError::InvalidETag` - otherwise.
+    pub fn parse(header: &str) -> Result<Self, DbError> {
+        let header = header.trim();
+
+        if header == "*" {
+            return Ok(IfMatch::Any);
+        }
+
+        header
+            .strip_prefix("W/")
+            .unwrap_or(header)
+            .strip_prefix('"')
+            .and_then(|etag| etag.strip_suffix('"'))
+            .and_then(|version| version.parse().ok())
+            .map(IfMatch::Version)
+            .ok_or_else(|| DbError::InvalidETag(header.to_string()))
+    }
+
+    /// Check whether document version matches precondition.
+    ///
+    /// # Parameters
+    /// - `version` - given document version.
+    ///
+    /// # Returns
+    /// - `true` if version matches, `false` otherwise.
+    pub fn matches(&self, version: i64) -> bool {
+        match self {
+            IfMatch::Any               => true,
+            IfMatch::Version(expected) => *expected == version,
+        }
+    }
+}
+
+/// Filter comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl FilterOp {
+    /// Get SQL representation of the operator.
+    ///
+    /// # Returns
+    /// - SQL comparison operator.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            FilterOp::Eq  => "=",
+            FilterOp::Ne  => "<>",
+            FilterOp::Gt  => ">",
+            FilterOp::Gte => ">=",
+            FilterOp::Lt  => "<",
+            FilterOp::Lte => "<=",
+        }
+    }
+}
+
+/// Document filter comparing value at JSON path (e.g. `$.location = 'Moscow'`).
+///
+/// Values are compared as JSON, so numbers are compared numerically and
+/// strings lexicographically. Documents without the path never match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    /// JSON path of compared value.
+    pub path: String,
+    /// Comparison operator.
+    pub op: FilterOp,
+    /// Value to compare with.
+    pub value: Value,
+}
+
+impl Filter {
+    /// Construct new Filter object.
+    ///
+    /// # Parameters
+    /// - `path`  - given JSON path of compared value.
+    /// - `op`    - given comparison operator.
+    /// - `value` - given value to compare with.
+    ///
+    /// # Returns
+    /// - New `Filter` object.
+    pub fn new(path: &str, op: FilterOp, value: impl Into<Value>) -> Self {
+        Self {
+            path:  path.to_string(),
+            op,
+            value: value.into(),
+        }
+    }
+
+    /// Construct filter matching values equal to given one.
+    pub fn eq(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Eq, value)
+    }
+
+    /// Construct filter matching values not equal to given one.
+    pub fn ne(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Ne, value)
+    }
+
+    /// Construct filter matching values greater than given one.
+    pub fn gt(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Gt, value)
+    }
+
+    /// Construct filter matching values greater than or equal to given one.
+    pub fn gte(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Gte, value)
+    }
+
+    /// Construct filter matching values less than given one.
+    pub fn lt(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Lt, value)
+    }
+
+    /// Construct filter matching values less than or equal to given one.
+    pub fn lte(path: &str, value: impl Into<Value>) -> Self {
+        Self::new(path, FilterOp::Lte, value)
+    }
+}
+
+/// Type of value stored in JSON index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum IndexKind {
+    /// Integer values (`BIGINT`).
+    Integer,
+    /// Any numeric values (`DOUBLE`).
+    Double,
+    /// String values up to given length in characters (`VARCHAR`).
+    String {
+        length: u16,
+    },
+}
+
+impl IndexKind {
+    /// Get SQL type of generated column.
+    ///
+    /// # Returns
+    /// - Generated column type.
+    fn column_type(&self) -> String {
+        match self {
+            IndexKind::Integer           => "BIGINT".to_string(),
+            IndexKind::Double            => "DOUBLE".to_string(),
+            IndexKind::String { length } => format!("VARCHAR({length}) COLLATE utf8mb4_bin"),
+        }
+    }
+
+    /// Get `JSON_VALUE` return type.
+    ///
+    /// # Returns
+    /// - Type of `RETURNING` clause.
+    fn returning_type(&self) -> String {
+        match self {
+            IndexKind::Integer           => "SIGNED".to_string(),
+            IndexKind::Double            => "DOUBLE".to_string(),
+            IndexKind::String { length } => format!("CHAR({length})"),
+        }
+    }
+}
+
+/// Secondary index on JSON document field.
+///
+/// Index is backed by virtual generated column `jx_<name>` extracting value
+/// at path and regular index on it. Values which can not be converted to
+/// index kind (e.g. string in integer index or too long string) are stored
+/// as `NULL`, so declare index only on paths holding values of its kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonIndex {
+    /// Index name.
+    pub name: String,
+    /// Indexed JSON path.
+    pub path: String,
+    /// Indexed value kind.
+    pub kind: IndexKind,
+}
+
+impl JsonIndex {
+    /// Get name of generated column & index.
+    ///
+    /// # Returns
+    /// - Generated column name.
+    fn column(&self) -> String {
+        format!("{INDEX_PREFIX}{}", self.name)
+    }
+
+    /// Convert filter value to index column value.
+    ///
+    /// # Parameters
+    /// - `filter` - given document filter.
+    ///
+    /// # Returns
+    /// - Index column value if filter can be answered with index.
+    /// - `None` - otherwise.
+    fn value(&self, filter: &Filter) -> Option<Arg> {
+        if filter.path != self.path || filter.op == FilterOp::Ne {
+            return None;
+        }
+
+        match self.kind {
+            IndexKind::Integer => filter.value.as_i64().map(Arg::Int),
+            IndexKind::Double  => filter.value.as_f64().map(Arg::Double),
+            IndexKind::String { length } => {
+                // Longer strings are not indexed, so only equality with
+                // short enough value is answered by index.
+                let value = filter.value.as_str()?;
+
+                if filter.op != FilterOp::Eq || value.chars().count() > length as usize {
+                    return None;
+                }
+
+                Some(Arg::Str(value.to_string()))
+            }
+        }
+    }
+}
+
+/// Query plan of `Collection::find`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explain {
+    /// Executed query.
+    pub query: String,
+    /// Names of JSON indexes used by query.
+    pub indexes: Vec<String>,
+    /// MySQL `EXPLAIN FORMAT=JSON` output.
+    pub plan: Value,
+}
+
+/// Query argument.
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    Str(String),
+    Int(i64),
+    Double(f64),
+}
+
+/// Partial document update.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentPatch {
+    /// RFC 7396 JSON Merge Patch (e.g. `{"bio": "new", "location": null}`).
+    Merge(Value),
+    /// RFC 6902 JSON Patch (add/remove/replace/move/copy/test operations).
+    Json(json_patch::Patch),
+}
+
+impl DocumentPatch {
+    /// Parse RFC 6902 JSON Patch from its JSON representation.
+    ///
+    /// # Parameters
+    /// - `operations` - given array of patch operations
+    ///   (e.g. `[{"op": "replace", "path": "/bio", "value": "new"}]`).
+    ///
+    /// # Returns
+    /// - New `DocumentPatch` object - in case of success.
+    /// - `DbError::Json` - otherwise.
+    pub fn json(operations: Value) -> Result<Self, DbError> {
+        Ok(DocumentPatch::Json(serde_json::from_value(operations)?))
+    }
+
+    /// Apply patch to document.
+    ///
+    /// JSON Patch is applied as a whole: if any operation fails (including
+    /// `test`), document is left unchanged.
+    ///
+    /// # Parameters
+    /// - `doc` - given document to patch.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError::TestFailed` - if `test` operation did not match.
+    /// - `DbError::Patch` - if any other operation can not be applied.
+    pub fn apply(&self, doc: &mut Value) -> Result<(), DbError> {
+        match self {
+            DocumentPatch::Merge(patch) => json_patch::merge(doc, patch),
+            DocumentPatch::Json(patch)  => json_patch::patch(doc, patch)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// JSON document collection.
+///
+/// Collection is a table with identifier column & JSON document column.
+/// Named collections are created by `DocDBManager::create_collection`,
+/// existing tables with JSON columns (e.g. `Message.message_data`) can be
+/// used as collections too.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    /// Manager MySQL connection pool.
+    pool: MySqlPool,
+    /// Collection table name.
+    table: String,
+    /// Document identifier column name.
+    id_column: String,
+    /// Document JSON column name.
+    doc_column: String,
+    /// JSON Schema documents are validated against before writing.
+    schema: Option<Schema>,
+    /// Author of document updates.
+    author: Option<String>,
+    /// Document version column name.
+    version_column: Option<String>,
+}
+
+impl Collection {
+    /// Construct new Collection object.
+    ///
+    /// # Parameters
+    /// - `pool`       - given MySQL connection pool.
+    /// - `table`      - given collection table name.
+    /// - `id_column`  - given document identifier column name.
+    /// - `doc_column` - given document JSON column name.
+    ///
+    /// # Returns
+    /// - New `Collection` object - in case of success.
+    /// - `DbError::InvalidName` - otherwise.
+    pub fn new(pool: MySqlPool, table: &str, id_column: &str, doc_column: &str)
+        -> Result<Self, DbError>
+    {
+        validate_name(table)?;
+        validate_name(id_column)?;
+        validate_name(doc_column)?;
+
+        Ok(Self {
+            pool,
+            table:          table.to_string(),
+            id_column:      id_column.to_string(),
+            doc_column:     doc_column.to_string(),
+            schema:         None,
+            author:         None,
+            version_column: None,
+        })
+    }
+
+    /// Set column holding document versions for optimistic concurrency
+    /// control. Version is incremented on every update.
+    ///
+    /// # Parameters
+    /// - `column` - given version column name.
+    ///
+    /// # Returns
+    /// - Versioned collection - in case of success.
+    /// - `DbError::InvalidName` - otherwise.
+    pub fn with_version_column(mut self, column: &str) -> Result<Self, DbError> {
+        validate_name(column)?;
+
+        self.version_column = Some(column.to_string());
+        Ok(self)
+    }
+
+    /// Set author of document updates.
+    ///
+    /// Author is passed to triggers (e.g. revision history) in
+    /// `@dbproject_author` session variable.
+    ///
+    /// # Parameters
+    /// - `author` - given author or `None` for anonymous updates.
+    ///
+    /// # Returns
+    /// - Collection with given author.
+    pub fn with_author(mut self, author: Option<&str>) -> Self {
+        self.author = author.map(str::to_string);
+        self
+    }
+
+    /// Set JSON Schema documents are validated against before writing.
+    ///
+    /// # Parameters
+    /// - `schema` - given JSON Schema or `None` to disable validation.
+    ///
+    /// # Returns
+    /// - Collection with given schema.
+    pub fn with_schema(mut self, schema: Option<Schema>) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Get collection JSON Schema.
+    ///
+    /// # Returns
+    /// - JSON Schema if set.
+    #[inline(always)]
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    /// Get document column name.
+    ///
+    /// # Returns
+    /// - Document JSON column name.
+    #[inline(always)]
+    pub fn doc_column(&self) -> &str {
+        &self.doc_column
+    }
+
+    /// Validate document against collection JSON Schema if set.
+    ///
+    /// # Parameters
+    /// - `doc` - given document.
+    ///
+    /// # Returns
+    /// - `Ok` - if document is valid.
+    /// - `DbError::Validation` - otherwise.
+    fn validate(&self, doc: &Value) -> Result<(), DbError> {
+        match &self.schema {
+            Some(schema) => schema.validate(doc),
+            None         => Ok(()),
+        }
+    }
+
+    /// Get collection table name.
+    ///
+    /// # Returns
+    /// - Collection table name.
+    #[inline(always)]
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Insert new document.
+    ///
+    /// # Parameters
+    /// - `doc` - given document.
+    ///
+    /// # Returns
+    /// - Identifier of inserted document - in case of success.
+    /// - `DbError::Validation` - if document does not match collection schema.
+    /// - `DbError` - otherwise.
+    pub async fn insert<T: Serialize>(&self, doc: &T) -> Result<i64, DbError> {
+        let doc = serde_json::to_value(doc)?;
+        self.validate(&doc)?;
+
+        let query = format!(
+            "INSERT INTO `{}` (`{}`) VALUES (?);",
+            self.table, self.doc_column
+        );
+
+        let result = sqlx::query(query.as_str())
+            .bind(&doc)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.last_insert_id() as i64)
+    }
+
+    /// Get document by identifier.
+    ///
+    /// # Parameters
+    /// - `id` - given document identifier.
+    ///
+    /// # Returns
+    /// - Document or `None` if it does not exist - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn get<T: DeserializeOwned>(&self, id: i64) -> Result<Option<T>, DbError> {
+        let query = format!(
+            "SELECT `{}` FROM `{}` WHERE `{}` = ?;",
+            self.doc_column, self.table, self.id_column
+        );
+
+        let row: Option<(Option<Value>,)> = sqlx::query_as(query.as_str())
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row.and_then(|(doc,)| doc) {
+            Some(doc) => Ok(Some(serde_json::from_value(doc)?)),
+            None      => Ok(None),
+        }
+    }
+
+    /// Replace whole document.
+    ///
+    /// # Parameters
+    /// - `id`  - given document identifier.
+    /// - `doc` - given new document.
+    ///
+    /// # Returns
+    /// - `true` if document was replaced, `false` if it does not exist.
+    /// - `DbError::Validation` - if document does not match collection schema.
+    /// - `DbError` - otherwise.
+    pub async fn replace<T: Serialize>(&self, id: i64, doc: &T) -> Result<bool, DbError> {
+        let doc = serde_json::to_value(doc)?;
+        self.validate(&doc)?;
+
+        let mut transaction = self.pool.begin().await?;
+        let is_replaced     = self.update(&mut transaction, id, &doc, None).await?;
+
+        transaction.commit().await?;
+        Ok(is_replaced)
+    }
+
+    /// Update document on given connection with author session variable set.
+    ///
+    /// # Parameters
+    /// - `conn`     - given MySQL connection.
+    /// - `id`       - given document identifier.
+    /// - `doc`      - given new document.
+    /// - `expected` - given expected document version (versioned collections only).
+    ///
+    /// # Returns
+    /// - `true` if document was updated, `false` if it does not exist or
+    ///   its version does not match.
+    /// - `DbError` - otherwise.
+    async fn update(&self, conn: &mut MySqlConnection, id: i64, doc: &Value, expected: Option<i64>)
+        -> Result<bool, DbError>
+    {
+        // Variable is always set, so author of previous update made on
+        // pooled connection is never reused.
+        let query = format!("SET {AUTHOR_VARIABLE} = ?;");
+        sqlx::query(query.as_str()).bind(&self.author).execute(&mut *conn).await?;
+
+        let mut query = format!("UPDATE `{}` SET `{}` = ?", self.table, self.doc_column);
+
+        if let Some(version) = &self.version_column {
+            query.push_str(&format!(", `{version}` = `{version}` + 1"));
+        }
+
+        query.push_str(&format!(" WHERE `{}` = ?", self.id_column));
+
+        let expected = expected.filter(|_| self.version_column.is_some());
+
+        if let (Some(version), Some(_)) = (&self.version_column, expected) {
+            query.push_str(&format!(" AND `{version}` = ?"));
+        }
+
+        let mut update = sqlx::query(query.as_str()).bind(doc).bind(id);
+
+        if let Some(expected) = expected {
+            update = update.bind(expected);
+        }
+
+        let result = update.execute(&mut *conn).await?;
+
+        let query = format!("SET {AUTHOR_VARIABLE} = NULL;");
+        sqlx::query(query.as_str()).execute(&mut *conn).await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Get version column name of versioned collection.
+    ///
+    /// # Returns
+    /// - Version column name - in case of success.
+    /// - `DbError::InvalidName` - if collection is not versioned.
+    fn version_column(&self) -> Result<&str, DbError> {
+        self.version_column
+            .as_deref()
+            .ok_or_else(|| DbError::InvalidName(format!("{}.version", self.table)))
+    }
+
+    /// Get document with its version.
+    ///
+    /// # Parameters
+    /// - `id` - given document identifier.
+    ///
+    /// # Returns
+    /// - Versioned document or `None` if it does not exist - in case of success.
+    /// - `DbError::InvalidName` - if collection is not versioned.
+    /// - `DbError` - otherwise.
+    pub async fn get_versioned<T: DeserializeOwned>(&self, id: i64)
+        -> Result<Option<Versioned<T>>, DbError>
+    {
+        let query = format!(
+            "SELECT `{}`, `{}` FROM `{}` WHERE `{}` = ?;",
+            self.version_column()?, self.doc_column, self.table, self.id_column
+        );
+
+        let row: Option<(i64, Option<Value>)> = sqlx::query_as(query.as_str())
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some((version, Some(doc))) => {
+                Ok(Some(Versioned { version, data: serde_json::from_value(doc)? }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Replace whole document if its version matches expected one.
+    ///
+    /// # Parameters
+    /// - `id`       - given document identifier.
+    /// - `expected` - given expected document version.
+    /// - `doc`      - given new document.
+    ///
+    /// # Returns
+    /// - New document version - in case of success.
+    /// - `DbError::Conflict` - if document was updated concurrently.
+    /// - `DbError::Sqlx(RowNotFound)` - if document does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn update_if_version<T: Serialize>(&self, id: i64, expected: i64, doc: &T)
+        -> Result<i64, DbError>
+    {
+        self.update_if_match(id, IfMatch::Version(expected), doc).await
+    }
+
+    /// Replace whole document if its version matches precondition.
+    ///
+    /// # Parameters
+    /// - `id`       - given document identifier.
+    /// - `if_match` - given version precondition.
+    /// - `doc`      - given new document.
+    ///
+    /// # Returns
+    /// - New document version - in case of success.
+    /// - `DbError::Conflict` - if version does not match precondition.
+    /// - `DbError::Sqlx(RowNotFound)` - if document does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn update_if_match<T: Serialize>(&self, id: i64, if_match: IfMatch, doc: &T)
+        -> Result<i64, DbError>
+    {
+        let doc     = serde_json::to_value(doc)?;
+        let version = self.version_column()?.to_string();
+        self.validate(&doc)?;
+
+        let expected = match if_match {
+            IfMatch::Any               => None,
+            IfMatch::Version(expected) => Some(expected),
+        };
+
+        let mut transaction = self.pool.begin().await?;
+        let is_updated      = self.update(&mut transaction, id, &doc, expected).await?;
+
+        let query = format!(
+            "SELECT `{version}` FROM `{}` WHERE `{}` = ?;",
+            self.table, self.id_column
+        );
+
+        let actual: Option<(i64,)> = sqlx::query_as(query.as_str())
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        match (actual, expected) {
+            (None, _) => Err(DbError::Sqlx(sqlx::Error::RowNotFound)),
+            (Some((actual,)), Some(expected)) if !is_updated => {
+                Err(DbError::Conflict { expected, actual })
+            }
+            (Some((actual,)), _) => {
+                transaction.commit().await?;
+                Ok(actual)
+            }
+        }
+    }
+
+    /// Delete document.
+    ///
+    /// # Parameters
+    /// - `id` - given document identifier.
+    ///
+    /// # Returns
+    /// - `true` if document was deleted, `false` if it does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn delete(&self, id: i64) -> Result<bool, DbError> {
+        let query = format!(
+            "DELETE FROM `{}` WHERE `{}` = ?;",
+            self.table, self.id_column
+        );
+
+        let result = sqlx::query(query.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Find documents matching all filters.
+    ///
+    /// Filters on indexed paths are answered using JSON indexes
+    /// (see `Collection::create_index`).
+    ///
+    /// # Parameters
+    /// - `filters` - given document filters.
+    ///
+    /// # Returns
+    /// - Vector of matching documents ordered by identifier - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn find<T: DeserializeOwned>(&self, filters: &[Filter])
+        -> Result<Vec<Document<T>>, DbError>
+    {
+        let (query, args, _) = self.plan(filters).await?;
+
+        sqlx::query_as_with::<_, (i64, Value), _>(query.as_str(), args)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(id, doc)| Ok(Document { id, data: serde_json::from_value(doc)? }))
+            .collect()
+    }
+
+    /// Explain `Collection::find` query for debugging.
+    ///
+    /// # Parameters
+    /// - `filters` - given document filters.
+    ///
+    /// # Returns
+    /// - Query, used JSON indexes & MySQL query plan - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn explain(&self, filters: &[Filter]) -> Result<Explain, DbError> {
+        let (query, args, indexes) = self.plan(filters).await?;
+        let explain = format!("EXPLAIN FORMAT=JSON {query}");
+
+        let (plan,): (String,) = sqlx::query_as_with(explain.as_str(), args)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(Explain {
+            query,
+            indexes,
+            plan: serde_json::from_str(&plan)?,
+        })
+    }
+
+    /// Build `Collection::find` query.
+    ///
+    /// Filter answered by index is compared against generated column, while
+    /// original JSON comparison is kept to preserve JSON comparison semantics.
+    ///
+    /// # Parameters
+    /// - `filters` - given document filters.
+    ///
+    /// # Returns
+    /// - Query, its arguments & names of used indexes - in case of success.
+    /// - `DbError` - otherwise.
+    async fn plan(&self, filters: &[Filter])
+        -> Result<(String, MySqlArguments, Vec<String>), DbError>
+    {
+        for filter in filters {
+            validate_path(&filter.path)?;
+        }
+
+        let indexes        = if filters.is_empty() { Vec::new() } else { self.indexes().await? };
+        let mut conditions = vec![format!("`{}` IS NOT NULL", self.doc_column)];
+        let mut values     = Vec::new();
+        let mut used       = Vec::new();
+
+        for filter in filters {
+            let indexed = indexes
+                .iter()
+                .find_map(|index| index.value(filter).map(|value| (index, value)));
+
+            if let Some((index, value)) = indexed {
+                conditions.push(format!("`{}` {} ?", index.column(), filter.op.as_sql()));
+                values.push(value);
+
+                if !used.contains(&index.name) {
+                    used.push(index.name.clone());
+                }
+            }
+
+            conditions.push(format!(
+                "JSON_EXTRACT(`{}`, ?) {} CAST(? AS JSON)",
+                self.doc_column,
+                filter.op.as_sql()
+            ));
+            values.push(Arg::Str(filter.path.clone()));
+            values.push(Arg::Str(filter.value.to_string()));
+        }
+
+        let query = format!(
+            "SELECT `{}`, `{}` FROM `{}` WHERE {} ORDER BY `{}`",
+            self.id_column,
+            self.doc_column,
+            self.table,
+            conditions.join(" AND "),
+            self.id_column
+        );
+
+        let mut args = MySqlArguments::default();
+
+        for value in values {
+            let result = match value {
+                Arg::Str(value)    => args.add(value),
+                Arg::Int(value)    => args.add(value),
+                Arg::Double(value) => args.add(value),
+            };
+
+            result.map_err(sqlx::Error::Encode)?;
+        }
+
+        Ok((query, args, used))
+    }
+
+    /// Create secondary index on JSON document field.
+    ///
+    /// # Parameters
+    /// - `name` - given index name.
+    /// - `path` - given indexed JSON path (e.g. `$.channel_id`).
+    /// - `kind` - given indexed value kind.
+    ///
+    /// # Returns
+    /// - Created index - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn create_index(&self, name: &str, path: &str, kind: IndexKind)
+        -> Result<JsonIndex, DbError>
+    {
+        let index = JsonIndex {
+            name: name.to_string(),
+            path: path.to_string(),
+            kind,
+        };
+
+        validate_name(&index.column())?;
+        validate_path(path)?;
+
+        // Validated path & serialized kind do not contain quotes, so they
+        // can be inlined. Index description is stored in column comment.
+        let query = format!(
+            r#"
+            ALTER TABLE `{table}`
+            ADD COLUMN `{column}` {column_type}
+                GENERATED ALWAYS AS (
+                    JSON_VALUE(`{doc}`, '{path}' RETURNING {returning} NULL ON EMPTY NULL ON ERROR)
+                ) VIRTUAL
+                COMMENT '{comment}',
+            ADD INDEX `{column}` (`{column}`);
+            "#,
+            table       = self.table,
+            column      = index.column(),
+            column_type = kind.column_type(),
+            doc         = self.doc_column,
+            returning   = kind.returning_type(),
+            comment     = serde_json::to_string(&index)?,
+        );
+
+        sqlx::raw_sql(query.as_str()).execute(&self.pool).await?;
+        Ok(index)
+    }
+
+    /// Get secondary indexes of collection.
+    ///
+    /// # Returns
+    /// - Vector of indexes - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn indexes(&self) -> Result<Vec<JsonIndex>, DbError> {
+        let query =
+            r#"
+            SELECT CAST(COLUMN_COMMENT AS CHAR)
+            FROM information_schema.COLUMNS
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME LIKE ?
+            ORDER BY ORDINAL_POSITION;
+            "#;
+
+        let comments: Vec<(String,)> = sqlx::query_as(query)
+            .bind(&self.table)
+            .bind(format!("{}%", INDEX_PREFIX.replace('_', "\\_")))
+            .fetch_all(&self.pool)
+            .await?;
+
+        // Skip columns which were not created by `create_index`.
+        Ok(comments
+            .into_iter()
+            .filter_map(|(comment,)| serde_json::from_str(&comment).ok())
+            .collect())
+    }
+
+    /// Drop secondary index.
+    ///
+    /// # Parameters
+    /// - `name` - given index name.
+    ///
+    /// # Returns
+    /// - `true` if index was dropped, `false` if it does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn drop_index(&self, name: &str) -> Result<bool, DbError> {
+        let exists = self.indexes().await?.iter().any(|index| index.name == name);
+
+        if !exists {
+            return Ok(false);
+        }
+
+        // Dropping generated column drops its index as well.
+        let query = format!(
+            "ALTER TABLE `{}` DROP COLUMN `{INDEX_PREFIX}{name}`;",
+            self.table
+        );
+
+        sqlx::raw_sql(query.as_str()).execute(&self.pool).await?;
+        Ok(true)
+    }
+
+    /// Apply patch to document atomically.
+    ///
+    /// Document is locked while patch is applied, so concurrent patches
+    /// are applied one after another. Missing (`NULL`) document is patched
+    /// as empty object.
+    ///
+    /// # Parameters
+    /// - `id`    - given document identifier.
+    /// - `patch` - given document patch.
+    ///
+    /// # Returns
+    /// - Patched document or `None` if it does not exist - in case of success.
+    /// - `DbError::Validation` - if patched document does not match schema.
+    /// - `DbError` - otherwise, document is left unchanged.
+    pub async fn patch(&self, id: i64, patch: &DocumentPatch)
+        -> Result<Option<Value>, DbError>
+    {
+        let mut transaction = self.pool.begin().await?;
+
+        let query = format!(
+            "SELECT `{}` FROM `{}` WHERE `{}` = ? FOR UPDATE;",
+            self.doc_column, self.table, self.id_column
+        );
+
+        let row: Option<(Option<Value>,)> = sqlx::query_as(query.as_str())
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        let Some((doc,)) = row else {
+            return Ok(None);
+        };
+
+        let mut doc = doc.unwrap_or_else(|| Value::Object(Map::new()));
+        patch.apply(&mut doc)?;
+        self.validate(&doc)?;
+
+        self.update(&mut transaction, id, &doc, None).await?;
+
+        transaction.commit().await?;
+        Ok(Some(doc))
+    }
+
+    /// Run aggregation pipeline.
+    ///
+    /// # Parameters
+    /// - `pipeline` - given aggregation pipeline.
+    ///
+    /// # Returns
+    /// - Vector of result rows - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn aggregate(&self, pipeline: &Pipeline) -> Result<Vec<Value>, DbError> {
+        let (query, args) = pipeline.compile(self)?;
+        let mut rows      = sqlx::query_as::<_, (Value,)>(query.as_str());
+
+        for arg in args {
+            rows = rows.bind(arg);
+        }
+
+        Ok(rows
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(row,)| row)
+            .collect())
+    }
+}
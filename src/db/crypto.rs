@@ -0,0 +1,446 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Encryption at rest related declarations.
+//!
+//! Values are sealed with envelope encryption: every value is encrypted
+//! with its own random data key using AES-256-GCM, and the data key is
+//! encrypted with master key of keyring. Envelope stores identifier of
+//! master key, so keys can be rotated while old envelopes stay readable.
+//!
+//! Envelope format is `enc1:{key_id}:{wrapped_key}:{payload}`, where both
+//! wrapped key & payload are base64 encoded nonce followed by ciphertext.
+
+use std::{collections::{BTreeMap, HashSet}, fmt, fs, path::Path, sync::Arc};
+use aes_gcm::{aead::{Aead, KeyInit, Payload}, Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use crate::db::error::DbError;
+use rand::Rng;
+
+/// Prefix of encrypted values.
+pub const ENVELOPE_PREFIX: &str = "enc1";
+
+/// Length of master & data keys in bytes.
+pub const KEY_LENGTH: usize = 32;
+
+/// Length of AES-GCM nonce in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// Master key encrypting data keys.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MasterKey([u8; KEY_LENGTH]);
+
+impl MasterKey {
+    /// Generate new random master key.
+    ///
+    /// # Returns
+    /// - New `MasterKey` object.
+    pub fn generate() -> Self {
+        Self(rand::thread_rng().r#gen())
+    }
+
+    /// Construct master key from raw bytes.
+    ///
+    /// # Parameters
+    /// - `bytes` - given 32 key bytes.
+    ///
+    /// # Returns
+    /// - New `MasterKey` object - in case of success.
+    /// - `DbError::Crypto` - if key length is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DbError> {
+        let key = bytes.try_into()
+            .map_err(|_| DbError::Crypto(format!("master key must be {KEY_LENGTH} bytes long")))?;
+
+        Ok(Self(key))
+    }
+
+    /// Construct master key from base64 string.
+    ///
+    /// # Parameters
+    /// - `encoded` - given base64 encoded key.
+    ///
+    /// # Returns
+    /// - New `MasterKey` object - in case of success.
+    /// - `DbError::Crypto` - if key is not valid base64 or its length is invalid.
+    pub fn from_base64(encoded: &str) -> Result<Self, DbError> {
+        let bytes = STANDARD_NO_PAD.decode(encoded.trim_end_matches('='))
+            .map_err(|err| DbError::Crypto(format!("invalid master key: {err}")))?;
+
+        Self::from_bytes(&bytes)
+    }
+
+    /// Encode master key as base64 string.
+    ///
+    /// # Returns
+    /// - Base64 encoded key.
+    pub fn to_base64(&self) -> String {
+        STANDARD_NO_PAD.encode(self.0)
+    }
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MasterKey(..)")
+    }
+}
+
+/// Source of master keys.
+pub trait Keyring: fmt::Debug + Send + Sync {
+    /// Get identifier of master key sealing new values.
+    fn active(&self) -> &str;
+
+    /// Get master key by its identifier.
+    fn key(&self, id: &str) -> Option<&MasterKey>;
+}
+
+/// Keyring file content.
+#[derive(Serialize, Deserialize)]
+struct KeyringFile {
+    /// Identifier of active key.
+    active: String,
+    /// Base64 encoded keys by identifier.
+    keys: BTreeMap<String, String>,
+}
+
+/// Keyring kept in memory or in local JSON file.
+///
+/// File has the form `{"active": "k2", "keys": {"k1": "<base64>", "k2": "<base64>"}}`.
+#[derive(Debug, Clone)]
+pub struct LocalKeyring {
+    /// Master keys by identifier.
+    keys: BTreeMap<String, MasterKey>,
+    /// Identifier of active key.
+    active: String,
+}
+
+impl LocalKeyring {
+    /// Construct new LocalKeyring object with single active key.
+    ///
+    /// # Parameters
+    /// - `id`  - given key identifier.
+    /// - `key` - given master key.
+    ///
+    /// # Returns
+    /// - New `LocalKeyring` object - in case of success.
+    /// - `DbError::Crypto` - if key identifier is invalid.
+    pub fn new(id: &str, key: MasterKey) -> Result<Self, DbError> {
+        validate_key_id(id)?;
+
+        Ok(Self {
+            keys:   BTreeMap::from([(id.to_string(), key)]),
+            active: id.to_string(),
+        })
+    }
+
+    /// Add master key & make it active.
+    ///
+    /// Previous keys are kept to open existing envelopes.
+    ///
+    /// # Parameters
+    /// - `id`  - given key identifier.
+    /// - `key` - given master key.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError::Crypto` - if key identifier is invalid or already used.
+    pub fn rotate(&mut self, id: &str, key: MasterKey) -> Result<(), DbError> {
+        validate_key_id(id)?;
+
+        if self.keys.contains_key(id) {
+            return Err(DbError::Crypto(format!("master key {id:?} already exists")));
+        }
+
+        self.keys.insert(id.to_string(), key);
+        self.active = id.to_string();
+        Ok(())
+    }
+
+    /// Load keyring from JSON file.
+    ///
+    /// # Parameters
+    /// - `path` - given keyring file path.
+    ///
+    /// # Returns
+    /// - Loaded keyring - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        let file: KeyringFile = serde_json::from_slice(&fs::read(path)?)?;
+        let mut keys          = BTreeMap::new();
+
+        for (id, key) in &file.keys {
+            validate_key_id(id)?;
+            keys.insert(id.clone(), MasterKey::from_base64(key)?);
+        }
+
+        if !keys.contains_key(&file.active) {
+            return Err(DbError::Crypto(format!("active master key {:?} is missing", file.active)));
+        }
+
+        Ok(Self { keys, active: file.active })
+    }
+
+    /// Save keyring into JSON file.
+    ///
+    /// # Parameters
+    /// - `path` - given keyring file path.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DbError> {
+        let file = KeyringFile {
+            active: self.active.clone(),
+            keys:   self.keys.iter().map(|(id, key)| (id.clone(), key.to_base64())).collect(),
+        };
+
+        fs::write(path, serde_json::to_vec_pretty(&file)?)?;
+        Ok(())
+    }
+}
+
+impl Keyring for LocalKeyring {
+    fn active(&self) -> &str {
+        &self.active
+    }
+
+    fn key(&self, id: &str) -> Option<&MasterKey> {
+        self.keys.get(id)
+    }
+}
+
+/// Check master key identifier.
+///
+/// # Parameters
+/// - `id` - given key identifier.
+///
+/// # Returns
+/// - `Ok` - if identifier is non-empty & consists of ASCII alphanumerics, `-` or `_`.
+/// - `DbError::Crypto` - otherwise.
+fn validate_key_id(id: &str) -> Result<(), DbError> {
+    let is_valid = !id.is_empty()
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !is_valid {
+        return Err(DbError::Crypto(format!("invalid master key identifier {id:?}")));
+    }
+
+    Ok(())
+}
+
+/// Envelope encryption of values.
+#[derive(Debug, Clone)]
+pub struct Cipher {
+    /// Source of master keys.
+    keyring: Arc<dyn Keyring>,
+}
+
+impl Cipher {
+    /// Construct new Cipher object.
+    ///
+    /// # Parameters
+    /// - `keyring` - given source of master keys.
+    ///
+    /// # Returns
+    /// - New `Cipher` object.
+    pub fn new(keyring: impl Keyring + 'static) -> Self {
+        Self { keyring: Arc::new(keyring) }
+    }
+
+    /// Get identifier of master key sealing new values.
+    pub fn active_key(&self) -> &str {
+        self.keyring.active()
+    }
+
+    /// Encrypt value.
+    ///
+    /// # Parameters
+    /// - `plaintext` - given value.
+    /// - `context`   - given context, which must be the same on decryption.
+    ///
+    /// # Returns
+    /// - Envelope - in case of success.
+    /// - `DbError::Crypto` - otherwise.
+    pub fn encrypt(&self, plaintext: &str, context: &str) -> Result<String, DbError> {
+        let id         = self.keyring.active();
+        let master_key = self.master_key(id)?;
+        let data_key: [u8; KEY_LENGTH] = rand::thread_rng().r#gen();
+
+        let wrapped = seal(master_key.0, &data_key, id.as_bytes())?;
+        let payload = seal(data_key, plaintext.as_bytes(), context.as_bytes())?;
+
+        Ok(format!(
+            "{ENVELOPE_PREFIX}:{id}:{}:{}",
+            STANDARD_NO_PAD.encode(wrapped),
+            STANDARD_NO_PAD.encode(payload)
+        ))
+    }
+
+    /// Decrypt envelope.
+    ///
+    /// # Parameters
+    /// - `envelope` - given envelope.
+    /// - `context`  - given context used on encryption.
+    ///
+    /// # Returns
+    /// - Value - in case of success.
+    /// - `DbError::Crypto` - if envelope is malformed, its master key is
+    ///   unknown or context does not match.
+    pub fn decrypt(&self, envelope: &str, context: &str) -> Result<String, DbError> {
+        let malformed = || DbError::Crypto("malformed envelope".to_string());
+        let decode    = |part: &str| STANDARD_NO_PAD.decode(part).map_err(|_| malformed());
+
+        let mut parts = envelope.split(':');
+
+        let (Some(ENVELOPE_PREFIX), Some(id), Some(wrapped), Some(payload), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(malformed());
+        };
+
+        let master_key = self.master_key(id)?;
+        let data_key   = open(master_key.0, &decode(wrapped)?, id.as_bytes())?;
+        let data_key   = data_key.try_into().map_err(|_| malformed())?;
+        let plaintext  = open(data_key, &decode(payload)?, context.as_bytes())?;
+
+        String::from_utf8(plaintext).map_err(|_| malformed())
+    }
+
+    /// Check whether envelope must be re-encrypted with active master key.
+    ///
+    /// # Parameters
+    /// - `value` - given stored value.
+    ///
+    /// # Returns
+    /// - `true` if value is plaintext or sealed with another master key.
+    pub fn needs_rotation(&self, value: &str) -> bool {
+        key_id(value) != Some(self.keyring.active())
+    }
+
+    /// Get master key by its identifier.
+    fn master_key(&self, id: &str) -> Result<&MasterKey, DbError> {
+        self.keyring.key(id).ok_or_else(|| DbError::Crypto(format!("unknown master key {id:?}")))
+    }
+}
+
+/// Check whether value is envelope.
+///
+/// # Parameters
+/// - `value` - given stored value.
+///
+/// # Returns
+/// - `true` if value has envelope prefix.
+pub fn is_envelope(value: &str) -> bool {
+    value.strip_prefix(ENVELOPE_PREFIX).is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// Get identifier of master key sealing envelope.
+///
+/// # Parameters
+/// - `value` - given stored value.
+///
+/// # Returns
+/// - Master key identifier or `None` if value is not envelope.
+pub fn key_id(value: &str) -> Option<&str> {
+    let rest = value.strip_prefix(ENVELOPE_PREFIX)?.strip_prefix(':')?;
+    rest.split(':').next()
+}
+
+/// Encrypt bytes with AES-256-GCM.
+///
+/// # Returns
+/// - Random nonce followed by ciphertext - in case of success.
+/// - `DbError::Crypto` - otherwise.
+fn seal(key: [u8; KEY_LENGTH], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, DbError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let nonce: [u8; NONCE_LENGTH] = rand::thread_rng().r#gen();
+
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(|_| DbError::Crypto("encryption failed".to_string()))?;
+
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// Decrypt bytes sealed with AES-256-GCM.
+///
+/// # Returns
+/// - Plaintext - in case of success.
+/// - `DbError::Crypto` - if data is malformed or tampered with.
+fn open(key: [u8; KEY_LENGTH], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, DbError> {
+    if sealed.len() < NONCE_LENGTH {
+        return Err(DbError::Crypto("malformed envelope".to_string()));
+    }
+
+    let (nonce, msg) = sealed.split_at(NONCE_LENGTH);
+    let cipher       = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+
+    cipher.decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| DbError::Crypto("decryption failed: wrong key or context".to_string()))
+}
+
+/// Cipher with set of encrypted keys or fields.
+#[derive(Debug, Clone)]
+pub(crate) struct Encryption {
+    /// Envelope encryption of values.
+    cipher: Cipher,
+    /// Names of encrypted keys or fields.
+    names: HashSet<String>,
+}
+
+impl Encryption {
+    /// Construct new Encryption object.
+    pub(crate) fn new(cipher: Cipher, names: &[&str]) -> Self {
+        Self {
+            cipher,
+            names: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// Get envelope encryption of values.
+    pub(crate) fn cipher(&self) -> &Cipher {
+        &self.cipher
+    }
+
+    /// Check whether key or field is encrypted.
+    pub(crate) fn covers(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    /// Get names of encrypted keys or fields.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// Encrypt value of key or field if it is encrypted.
+    pub(crate) fn conceal(&self, name: &str, value: &str, context: &str) -> Result<String, DbError> {
+        if !self.covers(name) {
+            return Ok(value.to_string());
+        }
+
+        self.cipher.encrypt(value, context)
+    }
+
+    /// Decrypt value of key or field if it is encrypted.
+    ///
+    /// Plaintext written before key was encrypted is returned as is.
+    pub(crate) fn reveal(&self, name: &str, value: String, context: &str) -> Result<String, DbError> {
+        if !self.covers(name) || !is_envelope(&value) {
+            return Ok(value);
+        }
+
+        self.cipher.decrypt(&value, context)
+    }
+}
@@ -15,9 +15,95 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{chat::{create_db_tables, fill_db_tables}, db::ConnectionConfig};
-use sqlx::{MySqlPool, Row};
-use crate::chat::UserProfile;
-use crate::db::create_db;
+use crate::db::collection::{validate_name, etag, Collection, DocumentPatch, Explain, IfMatch, IndexKind, Versioned};
+use crate::db::collection::{COLLECTION_PREFIX, INDEX_PREFIX};
+use crate::db::{aggregate::Pipeline, add_column, create_db, create_table, error::DbError, schema::Schema};
+use crate::db::changefeed::{self, ChangeFeed};
+use crate::db::crypto::{Cipher, Encryption, ENVELOPE_PREFIX};
+use sqlx::{mysql::MySqlDatabaseError, MySqlPool};
+use serde_json::{json, Value};
+use crate::chat::{ProfileData, UserProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use chrono::NaiveDateTime;
+
+/// Table storing JSON Schemas of document collections.
+pub const SCHEMAS_TABLE: &str = "Document_Schemas";
+
+/// Table storing previous versions of documents.
+pub const REVISIONS_TABLE: &str = "Document_Revisions";
+
+/// Name of JSON index on channel of message documents.
+pub const MESSAGE_CHANNEL_INDEX: &str = "channel_id";
+
+/// Tables whose changes are captured into change feed.
+pub const WATCHED_TABLES: [&str; 2] = ["Message", "User_Profiles"];
+
+/// Error numbers of servers unable to install `JSON_SCHEMA_VALID` CHECK
+/// constraint: missing function (MariaDB, MySQL before 8.0.17), unsupported
+/// feature & function not allowed in CHECK constraint.
+const ER_CHECK_UNSUPPORTED: [u16; 3] = [1305, 1235, 3814];
+
+/// Get JSON Schema of user profile data.
+///
+/// # Returns
+/// - JSON Schema requiring `bio` string up to 500 characters and
+///   `profile_picture_url` URI.
+pub fn profile_schema() -> Value {
+    json!({
+        "type": "object",
+        "required": ["bio"],
+        "properties": {
+            "bio":                 { "type": "string", "maxLength": 500 },
+            "profile_picture_url": { "type": "string", "format": "uri" },
+            "location":            { "type": ["string", "null"] }
+        }
+    })
+}
+
+/// Generate SQL expression reading text field of new profile data.
+///
+/// # Parameters
+/// - `field` - given profile data field name.
+///
+/// # Returns
+/// - SQL expression, which is `NULL` for missing or JSON `null` fields and
+///   envelopes of encrypted fields.
+fn profile_field(field: &str) -> String {
+    let value = format!("JSON_EXTRACT(NEW.profile_data, '$.{field}')");
+    format!(
+        "IF(JSON_TYPE({value}) = 'NULL' OR JSON_UNQUOTE({value}) LIKE '{ENVELOPE_PREFIX}:%', \
+         NULL, JSON_UNQUOTE({value}))"
+    )
+}
+
+/// Get encryption context of profile field.
+///
+/// Context binds envelope to field only, not to profile: envelope copied
+/// into the same field of another profile is decrypted. This keeps envelopes
+/// valid when profile identifier is reassigned by `GlobalDB::move_user`.
+///
+/// # Parameters
+/// - `field` - given profile field name.
+///
+/// # Returns
+/// - Context string.
+fn profile_context(field: &str) -> String {
+    format!("User_Profiles/{field}")
+}
+
+/// Previous version of user profile data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Revision {
+    /// Revision number, starting from 1 for the oldest version.
+    pub revision: i64,
+    /// Profile data before update.
+    pub doc: Option<Value>,
+    /// Author of update which replaced this version.
+    pub author: Option<String>,
+    /// Time of update which replaced this version.
+    pub created_at: NaiveDateTime,
+}
 
 /// Document-oriented database Manager.
 #[derive(Debug, Default)]
@@ -26,6 +112,10 @@ pub struct DocDBManager {
     pool: Option<MySqlPool>,
     /// Connection config associated with DocDBManager.
     config: ConnectionConfig,
+    /// JSON Schemas of collections by table name.
+    schemas: HashMap<String, Schema>,
+    /// Encryption of sensitive profile fields (fields are stored as is if `None`).
+    encryption: Option<Encryption>,
 }
 
 impl DocDBManager {
@@ -37,6 +127,26 @@ impl DocDBManager {
         Self::default()
     }
 
+    /// Encrypt string values of given profile fields at rest.
+    ///
+    /// Fields are encrypted & decrypted transparently by profile, patch,
+    /// revision & versioned update methods, while collection returned by
+    /// `DocDBManager::profiles` sees envelopes. Profile schema is checked
+    /// against plaintext, CHECK constraint installed by
+    /// `DocDBManager::set_schema` does not check encrypted fields.
+    ///
+    /// Encrypted fields are not copied into profile columns, which are left
+    /// `NULL`, so `search_profiles` does not match nor return encrypted `bio`
+    /// & `location`.
+    ///
+    /// # Parameters
+    /// - `cipher` - given envelope encryption.
+    /// - `fields` - given names of encrypted top-level profile fields.
+    pub fn with_encryption(mut self, cipher: Cipher, fields: &[&str]) -> Self {
+        self.encryption = Some(Encryption::new(cipher, fields));
+        self
+    }
+
     /// Connect database.
     ///
     /// # Parameters
@@ -54,10 +164,42 @@ impl DocDBManager {
         let pool = MySqlPool::connect(config.url_db().as_str()).await?;
 
         create_db_tables(&pool).await?;
+        add_column(&pool, "User_Profiles", "version", "BIGINT NOT NULL DEFAULT 1").await?;
         fill_db_tables(&pool, 1).await?;
+
+        let content = String::from(
+            r#"
+            table_name VARCHAR(64) NOT NULL,
+            json_schema JSON NOT NULL,
+            PRIMARY KEY(table_name)
+            "#
+        );
+
+        create_table(&pool, &SCHEMAS_TABLE.to_string(), &content).await?;
+
+        let query   = format!("SELECT table_name, json_schema FROM {SCHEMAS_TABLE};");
+        let schemas = sqlx::query_as::<_, (String, Value)>(query.as_str())
+            .fetch_all(&pool)
+            .await?;
+
+        self.schemas.clear();
+
+        for (table, schema) in schemas {
+            let schema = Schema::new(schema)
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+            self.schemas.insert(table, schema);
+        }
+
         self.pool   = Some(pool);
         self.config = config;
 
+        // Messages are grouped by channel, see `count_messages_by_channel`.
+        self.index_messages().await.map_err(|err| match err {
+            DbError::Sqlx(err) => err,
+            err                => sqlx::Error::Decode(Box::new(err)),
+        })?;
+
         Ok(())
     }
 
@@ -121,131 +263,527 @@ impl DocDBManager {
 
             sqlx::raw_sql(query).execute(pool).await?;
 
-            let query =
+            // Profile data is the source of truth for inserted profiles,
+            // columns are filled from it (or it is built from columns).
+            let query = format!(
+                r#"
+                CREATE TRIGGER UpdateProfileDataBeforeInsert
+                BEFORE INSERT ON User_Profiles
+                FOR EACH ROW
+                BEGIN
+                    IF NEW.profile_data IS NULL THEN
+                        SET NEW.profile_data = JSON_OBJECT(
+                            'bio', NEW.bio,
+                            'profile_picture_url', NEW.profile_picture_url,
+                            'location', NEW.location
+                        );
+                    ELSE
+                        SET NEW.bio                 = {bio};
+                        SET NEW.profile_picture_url = {profile_picture_url};
+                        SET NEW.location            = {location};
+                    END IF;
+                END;
+                "#,
+                bio                 = profile_field("bio"),
+                profile_picture_url = profile_field("profile_picture_url"),
+                location            = profile_field("location"),
+            );
+
+            sqlx::raw_sql("DROP TRIGGER IF EXISTS UpdateProfileDataBeforeInsert;")
+                .execute(pool)
+                .await?;
+
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
+
+            // Keep profile columns & profile_data in sync in both directions:
+            // changed column is copied into JSON, changed JSON into column.
+            // Version is maintained here, so raw updates are versioned too.
+            let query = format!(
                 r#"
-                CREATE TRIGGER IF NOT EXISTS UpdateProfileDataBeforeUpdate
+                CREATE TRIGGER UpdateProfileDataBeforeUpdate
                 BEFORE UPDATE ON User_Profiles
                 FOR EACH ROW
                 BEGIN
-                    SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.bio', NEW.bio);
-                    SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.profile_picture_url', NEW.profile_picture_url);
-                    SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.location', NEW.location);
+                    DECLARE v_data_changed BOOLEAN;
+
+                    SET v_data_changed   = NOT (NEW.profile_data <=> OLD.profile_data);
+                    SET NEW.profile_data = COALESCE(NEW.profile_data, JSON_OBJECT());
+
+                    IF NOT (NEW.bio <=> OLD.bio) THEN
+                        SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.bio', NEW.bio);
+                    ELSEIF v_data_changed THEN
+                        SET NEW.bio = {bio};
+                    END IF;
+
+                    IF NOT (NEW.profile_picture_url <=> OLD.profile_picture_url) THEN
+                        SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.profile_picture_url', NEW.profile_picture_url);
+                    ELSEIF v_data_changed THEN
+                        SET NEW.profile_picture_url = {profile_picture_url};
+                    END IF;
+
+                    IF NOT (NEW.location <=> OLD.location) THEN
+                        SET NEW.profile_data = JSON_SET(NEW.profile_data, '$.location', NEW.location);
+                    ELSEIF v_data_changed THEN
+                        SET NEW.location = {location};
+                    END IF;
+
+                    -- Any change of profile data produces new version.
+                    SET NEW.version = IF(
+                        NEW.profile_data <=> OLD.profile_data, OLD.version, OLD.version + 1
+                    );
+                END;
+                "#,
+                bio                 = profile_field("bio"),
+                profile_picture_url = profile_field("profile_picture_url"),
+                location            = profile_field("location"),
+            );
+
+            sqlx::raw_sql("DROP TRIGGER IF EXISTS UpdateProfileDataBeforeUpdate;")
+                .execute(pool)
+                .await?;
+
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
+
+            let content = String::from(
+                r#"
+                table_name VARCHAR(64) NOT NULL,
+                document_id BIGINT NOT NULL,
+                revision BIGINT NOT NULL,
+                doc JSON,
+                author VARCHAR(255),
+                created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+                PRIMARY KEY(table_name, document_id, revision)
+                "#
+            );
+
+            create_table(pool, &REVISIONS_TABLE.to_string(), &content).await?;
+
+            // Store previous profile data on every change. Revisions of
+            // the same profile are numbered under lock of the latest one.
+            let query =
+                r#"
+                CREATE TRIGGER RecordProfileRevisionAfterUpdate
+                AFTER UPDATE ON User_Profiles
+                FOR EACH ROW
+                BEGIN
+                    DECLARE v_revision BIGINT;
+
+                    IF NOT (NEW.profile_data <=> OLD.profile_data) THEN
+                        SELECT COALESCE(MAX(revision), 0) + 1 INTO v_revision
+                        FROM Document_Revisions
+                        WHERE table_name = 'User_Profiles' AND document_id = OLD.profile_id
+                        FOR UPDATE;
+
+                        INSERT INTO Document_Revisions
+                        (table_name, document_id, revision, doc, author)
+                        VALUES ('User_Profiles', OLD.profile_id, v_revision, OLD.profile_data, @dbproject_author);
+                    END IF;
                 END;
                 "#;
 
+            sqlx::raw_sql("DROP TRIGGER IF EXISTS RecordProfileRevisionAfterUpdate;")
+                .execute(pool)
+                .await?;
+
             sqlx::raw_sql(query).execute(pool).await?;
+
+            changefeed::install(pool, &WATCHED_TABLES).await?;
             Ok(())
         }
         else {
             // Handle case when connection pool was not initialized.
             eprintln!(
-                "Error: connection pool is None: {}",
-                "call DocDBManager::connect() method first!"
+                "Error: connection pool is None: \
+                 call DocDBManager::connect() method first!"
             );
             Err(sqlx::Error::PoolClosed)
         }
     }
 
+    /// Add user profile & fill its data using `AddUserProfileData` procedure.
+    ///
+    /// # Parameters
+    /// - `user_profile` - given user profile.
+    ///
+    /// # Returns
+    /// - Identifier of added profile - in case of success.
+    /// - `sqlx::Error` - otherwise.
     pub async fn add_user_profile_data(&self, user_profile: &UserProfile)
-        -> Result<(), sqlx::Error>
+        -> Result<i64, sqlx::Error>
     {
+        let conceal = |field: &str, value: &str| {
+            self.conceal_field(field, value).map_err(|err| sqlx::Error::Encode(Box::new(err)))
+        };
+
+        let bio                 = conceal("bio", &user_profile.bio)?;
+        let profile_picture_url = conceal("profile_picture_url", &user_profile.profile_picture_url)?;
+        let location            = conceal("location", &user_profile.location)?;
+
         if let Some(pool) = &self.pool {
-            // Insert the new user profile into the database.
-            sqlx::query(
-                r#"
-                INSERT INTO User_Profiles
-                (user_id, bio, profile_picture_url, location)
-                VALUES (?, ?, ?, ?)
-                "#,
-            )
-                .bind(&user_profile.profile_id)
-                .bind(&user_profile.bio)
-                .bind(&user_profile.profile_picture_url)
-                .bind(&user_profile.location)
-                .execute(pool)
+            let mut transaction = pool.begin().await?;
+
+            let result = sqlx::query("INSERT INTO User_Profiles (user_id) VALUES (?);")
+                .bind(user_profile.user_id)
+                .execute(&mut *transaction)
                 .await?;
 
+            let profile_id = result.last_insert_id() as i64;
+
             sqlx::query("CALL AddUserProfileData(?, ?, ?, ?)")
-                .bind(&user_profile.profile_id)
-                .bind(&user_profile.bio)
-                .bind(&user_profile.profile_picture_url)
-                .bind(&user_profile.location)
-                .execute(pool)
+                .bind(profile_id)
+                .bind(bio)
+                .bind(profile_picture_url)
+                .bind(location)
+                .execute(&mut *transaction)
                 .await?;
+
+            transaction.commit().await?;
+            return Ok(profile_id);
         }
 
-        Ok(())
+        Err(sqlx::Error::PoolClosed)
     }
 
+    /// Get user profile with fields read from its data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    ///
+    /// # Returns
+    /// - User profile - in case of success.
+    /// - `sqlx::Error::RowNotFound` - if profile does not exist.
+    /// - `sqlx::Error` - otherwise.
     pub async fn get_user_profile_data(&self, profile_id: i64)
         -> Result<UserProfile, sqlx::Error>
     {
-        let mut user_profile = UserProfile::default();
-
         if let Some(pool) = &self.pool {
-            sqlx::query("CALL GetUserProfileData(?, @bio, @profile_picture_url, @location);")
-                .bind(&profile_id)
-                .execute(pool)
-                .await?;
+            return self.fetch_profile(pool, "profile_id", profile_id)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound);
+        }
 
-            let row: (Option<String>, Option<String>, Option<String>) =
-                sqlx::query_as("SELECT @bio, @profile_picture_url, @location;")
-                .fetch_one(pool)
-                .await?;
+        Err(sqlx::Error::PoolClosed)
+    }
+
+    /// Fetch the first user profile with given column value.
+    ///
+    /// # Parameters
+    /// - `pool`   - given MySQL connection pool.
+    /// - `column` - given identifier column name.
+    /// - `id`     - given identifier value.
+    ///
+    /// # Returns
+    /// - User profile or `None` if it does not exist - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn fetch_profile(&self, pool: &MySqlPool, column: &str, id: i64)
+        -> Result<Option<UserProfile>, sqlx::Error>
+    {
+        let query = format!(
+            r#"
+            SELECT profile_id, user_id, COALESCE(profile_data, JSON_OBJECT())
+            FROM User_Profiles
+            WHERE {column} = ?
+            ORDER BY profile_id
+            LIMIT 1;
+            "#
+        );
 
-            user_profile.bio                 = row.0.unwrap_or_default();
-            user_profile.profile_picture_url = row.1.unwrap_or_default();
-            user_profile.location            = row.2.unwrap_or_default();
+        let row: Option<(i64, Option<i64>, Value)> = sqlx::query_as(query.as_str())
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
 
-            println!("Bio: {}", user_profile.bio);
-            println!("Profile Picture URL: {}", user_profile.profile_picture_url);
-            println!("Location: {}", user_profile.location);
-            println!("CALL DIRECTLY IN DB");
-        }
+        row.map(|(profile_id, user_id, mut data)| {
+            self.open_profile(&mut data).map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
 
-        Ok(user_profile)
+            UserProfile::from_data(profile_id, user_id.unwrap_or_default(), data)
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)))
+        })
+        .transpose()
     }
 
-    pub async fn test_trigger(&self, user_profile: &UserProfile) -> Result<(), sqlx::Error>
-    {
-        if let Some(pool) = &self.pool {
-            let query =
-                r#"
-                UPDATE User_Profiles
-                SET bio = ?, profile_picture_url = ?, location = ?
-                WHERE profile_id = ?;
-                "#;
+    /// Create user profile.
+    ///
+    /// # Parameters
+    /// - `user_id` - given user identifier.
+    /// - `data`    - given profile data.
+    ///
+    /// # Returns
+    /// - Identifier of created profile - in case of success.
+    /// - `DbError::Validation` - if profile data does not match profile schema.
+    /// - `DbError` - otherwise.
+    pub async fn create_profile(&self, user_id: i64, data: &ProfileData) -> Result<i64, DbError> {
+        let doc = self.seal_profile(data)?;
 
-            sqlx::query(query)
-                .bind(&user_profile.bio)
-                .bind(&user_profile.profile_picture_url)
-                .bind(&user_profile.location)
-                .bind(&user_profile.profile_id)
-                .execute(pool)
-                .await?;
+        let result = sqlx::query("INSERT INTO User_Profiles (user_id, profile_data) VALUES (?, ?);")
+            .bind(user_id)
+            .bind(&doc)
+            .execute(self.pool()?)
+            .await?;
 
-            let profile_data: (Option<String>, Option<String>, Option<String>) =
-                sqlx::query_as("SELECT bio, profile_picture_url, location FROM User_Profiles WHERE profile_id = ?")
-                .bind(&user_profile.profile_id)
-                .fetch_one(pool)
-                .await?;
+        Ok(result.last_insert_id() as i64)
+    }
 
-            println!("Updated Profile Data: {:?}", profile_data);
+    /// Get user profile.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    ///
+    /// # Returns
+    /// - User profile or `None` if it does not exist - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn get_profile(&self, profile_id: i64) -> Result<Option<UserProfile>, DbError> {
+        Ok(self.fetch_profile(self.pool()?, "profile_id", profile_id).await?)
+    }
+
+    /// Get the first profile of user.
+    ///
+    /// # Parameters
+    /// - `user_id` - given user identifier.
+    ///
+    /// # Returns
+    /// - User profile or `None` if user has no profile - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn get_profile_by_user(&self, user_id: i64) -> Result<Option<UserProfile>, DbError> {
+        Ok(self.fetch_profile(self.pool()?, "user_id", user_id).await?)
+    }
+
+    /// Replace user profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `data`       - given new profile data.
+    ///
+    /// # Returns
+    /// - `true` if profile was updated, `false` if it does not exist.
+    /// - `DbError::Validation` - if profile data does not match profile schema.
+    /// - `DbError` - otherwise.
+    pub async fn update_profile(&self, profile_id: i64, data: &ProfileData) -> Result<bool, DbError> {
+        let doc = self.seal_profile(data)?;
+
+        // Plaintext is already validated, envelopes would not match schema.
+        self.profiles()?.with_schema(None).replace(profile_id, &doc).await
+    }
+
+    /// Validate profile data & encrypt its sensitive fields.
+    ///
+    /// # Parameters
+    /// - `data` - given profile data.
+    ///
+    /// # Returns
+    /// - Stored profile document - in case of success.
+    /// - `DbError::Validation` - if profile data does not match profile schema.
+    /// - `DbError` - otherwise.
+    fn seal_profile(&self, data: &ProfileData) -> Result<Value, DbError> {
+        self.seal_doc(serde_json::to_value(data)?)
+    }
+
+    /// Validate profile document & encrypt its sensitive fields.
+    ///
+    /// # Parameters
+    /// - `doc` - given profile document.
+    ///
+    /// # Returns
+    /// - Stored profile document - in case of success.
+    /// - `DbError::Validation` - if document does not match profile schema.
+    /// - `DbError` - otherwise.
+    fn seal_doc(&self, mut doc: Value) -> Result<Value, DbError> {
+        if let Some(schema) = self.profiles()?.schema() {
+            schema.validate(&doc)?;
+        }
+
+        if let Value::Object(fields) = &mut doc {
+            for (field, value) in fields.iter_mut() {
+                if let Value::String(plaintext) = value {
+                    *value = Value::String(self.conceal_field(field, plaintext)?);
+                }
+            }
+        }
+
+        Ok(doc)
+    }
+
+    /// Decrypt sensitive fields of stored profile document.
+    ///
+    /// # Parameters
+    /// - `doc` - given stored profile document.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    fn open_profile(&self, doc: &mut Value) -> Result<(), DbError> {
+        let (Some(encryption), Value::Object(fields)) = (&self.encryption, doc) else {
+            return Ok(());
+        };
+
+        for (field, value) in fields.iter_mut() {
+            if let Value::String(stored) = value {
+                let context = profile_context(field);
+                *value      = Value::String(encryption.reveal(field, std::mem::take(stored), &context)?);
+            }
         }
 
         Ok(())
     }
 
-    pub async fn count_messages_by_channel(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    /// Encrypt value of profile field if it is encrypted.
+    ///
+    /// # Parameters
+    /// - `field` - given profile field name.
+    /// - `value` - given field value.
+    ///
+    /// # Returns
+    /// - Stored value - in case of success.
+    /// - `DbError` - otherwise.
+    fn conceal_field(&self, field: &str, value: &str) -> Result<String, DbError> {
+        match &self.encryption {
+            Some(encryption) => encryption.conceal(field, value, &profile_context(field)),
+            None             => Ok(value.to_string()),
+        }
+    }
+
+    /// Re-encrypt sensitive profile fields with active master key.
+    ///
+    /// Fields sealed with previous master keys or written as plaintext are
+    /// updated in batches of profiles, each in its own transaction. Profile
+    /// changed concurrently is left to its writer. Revisions keep envelopes
+    /// of previous keys.
+    ///
+    /// # Parameters
+    /// - `batch_size` - given maximum number of profiles read at once.
+    ///
+    /// # Returns
+    /// - Number of re-encrypted profiles - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn rotate_encryption(&self, batch_size: u32) -> Result<u64, DbError> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(0);
+        };
+
+        let pool  = self.pool()?;
+        let limit = batch_size.max(1);
         let query =
             r#"
-        SELECT JSON_UNQUOTE(JSON_EXTRACT(CAST(message_data AS CHAR), '$.channel_id')) AS channel_id, COUNT(*) AS message_count
-        FROM Message
-        GROUP BY JSON_UNQUOTE(JSON_EXTRACT(CAST(message_data AS CHAR), '$.channel_id'));
-        "#;
+            SELECT profile_id, version, profile_data FROM User_Profiles
+            WHERE profile_id > ? AND profile_data IS NOT NULL
+            ORDER BY profile_id
+            LIMIT ?;
+            "#;
+
+        let mut cursor  = 0;
+        let mut rotated = 0;
+
+        loop {
+            let rows: Vec<(i64, i64, Value)> = sqlx::query_as(query)
+                .bind(cursor)
+                .bind(limit)
+                .fetch_all(pool)
+                .await?;
+
+            let mut transaction = pool.begin().await?;
+
+            for (profile_id, version, mut doc) in rows.iter().cloned() {
+                let Value::Object(fields) = &mut doc else {
+                    continue;
+                };
+
+                let mut is_changed = false;
+
+                for field in encryption.names() {
+                    let Some(Value::String(stored)) = fields.get_mut(field) else {
+                        continue;
+                    };
+
+                    if !encryption.cipher().needs_rotation(stored) {
+                        continue;
+                    }
+
+                    let context   = profile_context(field);
+                    let plaintext = encryption.reveal(field, std::mem::take(stored), &context)?;
+
+                    *stored    = encryption.conceal(field, &plaintext, &context)?;
+                    is_changed = true;
+                }
+
+                if !is_changed {
+                    continue;
+                }
+
+                // Version is bumped by trigger.
+                let result = sqlx::query("UPDATE User_Profiles SET profile_data = ? WHERE profile_id = ? AND version = ?;")
+                    .bind(&doc)
+                    .bind(profile_id)
+                    .bind(version)
+                    .execute(&mut *transaction)
+                    .await?;
+
+                rotated += result.rows_affected();
+            }
+
+            transaction.commit().await?;
+
+            match rows.last() {
+                Some((profile_id, _, _)) if rows.len() == limit as usize => cursor = *profile_id,
+                _                                                        => break,
+            }
+        }
+
+        Ok(rotated)
+    }
+
+    /// Delete user profile.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    ///
+    /// # Returns
+    /// - `true` if profile was deleted, `false` if it does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn delete_profile(&self, profile_id: i64) -> Result<bool, DbError> {
+        self.profiles()?.delete(profile_id).await
+    }
+
+    /// Declare index on channel of message documents if it does not exist.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    async fn index_messages(&self) -> Result<(), DbError> {
+        let messages = self.messages()?;
+        let exists   = messages.indexes().await?.iter().any(|index| index.name == MESSAGE_CHANNEL_INDEX);
+
+        if !exists {
+            messages.create_index(MESSAGE_CHANNEL_INDEX, "$.channel_id", IndexKind::Integer).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Build `DocDBManager::count_messages_by_channel` query.
+    ///
+    /// Messages are grouped by generated column of `MESSAGE_CHANNEL_INDEX`,
+    /// so query reads index instead of scanning documents.
+    ///
+    /// # Returns
+    /// - Query.
+    fn count_messages_query() -> String {
+        format!(
+            "SELECT CAST(`{column}` AS CHAR) AS channel_id, COUNT(*) AS message_count \
+             FROM Message WHERE `{column}` IS NOT NULL GROUP BY `{column}`",
+            column = format!("{INDEX_PREFIX}{MESSAGE_CHANNEL_INDEX}")
+        )
+    }
 
+    /// Count messages by channel stored in message documents.
+    ///
+    /// Messages without integer `$.channel_id` are not counted.
+    ///
+    /// # Returns
+    /// - Vector of channel identifiers & message counts - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn count_messages_by_channel(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
         if let Some(pool) = &self.pool {
-            let results = sqlx::query_as::<_, (String, i64)>(query)
+            let results = sqlx::query_as::<_, (String, i64)>(&Self::count_messages_query())
                 .fetch_all(pool)
                 .await?;
 
@@ -255,4 +793,614 @@ impl DocDBManager {
         Err(sqlx::Error::RowNotFound)
     }
 
+    /// Get query plan of `DocDBManager::count_messages_by_channel`.
+    ///
+    /// # Returns
+    /// - Query, used JSON indexes & MySQL query plan - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn explain_count_messages_by_channel(&self) -> Result<Explain, DbError> {
+        let query   = Self::count_messages_query();
+        let explain = format!("EXPLAIN FORMAT=JSON {query}");
+
+        let (plan,): (String,) = sqlx::query_as(explain.as_str())
+            .fetch_one(self.pool()?)
+            .await?;
+
+        Ok(Explain {
+            query,
+            indexes: vec![MESSAGE_CHANNEL_INDEX.to_string()],
+            plan:    serde_json::from_str(&plan)?,
+        })
+    }
+
+    /// Get manager connection pool.
+    ///
+    /// # Returns
+    /// - Manager MySQL connection pool - in case of success.
+    /// - `DbError` - if manager is not connected.
+    fn pool(&self) -> Result<&MySqlPool, DbError> {
+        self.pool.as_ref().ok_or(DbError::Sqlx(sqlx::Error::PoolClosed))
+    }
+
+    /// Create named document collection if not exists.
+    ///
+    /// # Parameters
+    /// - `name` - given collection name.
+    ///
+    /// # Returns
+    /// - Collection - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn create_collection(&self, name: &str) -> Result<Collection, DbError> {
+        let collection = self.collection(name)?;
+        let content    = String::from(
+            r#"
+            id BIGINT AUTO_INCREMENT,
+            doc JSON NOT NULL,
+            version BIGINT NOT NULL DEFAULT 1,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+                ON UPDATE CURRENT_TIMESTAMP,
+            PRIMARY KEY(id)
+            "#
+        );
+
+        create_table(self.pool()?, &collection.table().to_string(), &content).await?;
+
+        // Collections created before versioning was introduced.
+        add_column(self.pool()?, collection.table(), "version", "BIGINT NOT NULL DEFAULT 1").await?;
+        Ok(collection)
+    }
+
+    /// Get named document collection.
+    ///
+    /// # Parameters
+    /// - `name` - given collection name.
+    ///
+    /// # Returns
+    /// - Collection - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn collection(&self, name: &str) -> Result<Collection, DbError> {
+        validate_name(name)?;
+
+        let table = format!("{COLLECTION_PREFIX}{name}");
+        let collection = Collection::new(self.pool()?.clone(), &table, "id", "doc")?;
+        self.attach(collection.with_version_column("version")?)
+    }
+
+    /// Drop named document collection with all its documents.
+    ///
+    /// # Parameters
+    /// - `name` - given collection name.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn drop_collection(&self, name: &str) -> Result<(), DbError> {
+        let collection = self.collection(name)?;
+        let query      = format!("DROP TABLE IF EXISTS `{}`;", collection.table());
+
+        sqlx::query(query.as_str()).execute(self.pool()?).await?;
+        Ok(())
+    }
+
+    /// Get collection of message documents stored in `Message.message_data`.
+    ///
+    /// # Returns
+    /// - Collection - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn messages(&self) -> Result<Collection, DbError> {
+        self.attach(Collection::new(self.pool()?.clone(), "Message", "message_id", "message_data")?)
+    }
+
+    /// Run aggregation pipeline over message documents.
+    ///
+    /// # Parameters
+    /// - `pipeline` - given aggregation pipeline.
+    ///
+    /// # Returns
+    /// - Vector of result rows - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn aggregate_messages(&self, pipeline: &Pipeline) -> Result<Vec<Value>, DbError> {
+        self.messages()?.aggregate(pipeline).await
+    }
+
+    /// Get collection of user profile documents stored in
+    /// `User_Profiles.profile_data`.
+    ///
+    /// # Returns
+    /// - Collection - in case of success.
+    /// - `DbError` - otherwise.
+    pub fn profiles(&self) -> Result<Collection, DbError> {
+        let collection = Collection::new(self.pool()?.clone(), "User_Profiles", "profile_id", "profile_data")?;
+        self.attach(collection.with_version_column("version")?)
+    }
+
+    /// Apply patch to user profile data atomically.
+    ///
+    /// Patch of encrypted profile is applied to decrypted data and retried
+    /// if profile was updated concurrently.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `patch`      - given JSON Merge Patch or JSON Patch.
+    ///
+    /// # Returns
+    /// - Patched profile data or `None` if profile does not exist.
+    /// - `DbError` - otherwise, profile data is left unchanged.
+    pub async fn patch(&self, profile_id: i64, patch: &DocumentPatch)
+        -> Result<Option<Value>, DbError>
+    {
+        if self.encryption.is_none() {
+            return self.profiles()?.patch(profile_id, patch).await;
+        }
+
+        let profiles = self.profiles()?.with_schema(None);
+        let query    = "SELECT version, profile_data FROM User_Profiles WHERE profile_id = ?;";
+
+        loop {
+            let row: Option<(i64, Option<Value>)> = sqlx::query_as(query)
+                .bind(profile_id)
+                .fetch_optional(self.pool()?)
+                .await?;
+
+            let Some((version, doc)) = row else {
+                return Ok(None);
+            };
+
+            let mut doc = doc.unwrap_or_else(|| json!({}));
+            self.open_profile(&mut doc)?;
+            patch.apply(&mut doc)?;
+
+            let sealed = self.seal_doc(doc.clone())?;
+
+            match profiles.update_if_version(profile_id, version, &sealed).await {
+                Ok(_)                                        => return Ok(Some(doc)),
+                Err(DbError::Conflict { .. })                => continue,
+                Err(DbError::Sqlx(sqlx::Error::RowNotFound)) => return Ok(None),
+                Err(err)                                     => return Err(err),
+            }
+        }
+    }
+
+    /// Attach JSON Schema of collection table if set.
+    ///
+    /// # Parameters
+    /// - `collection` - given collection.
+    ///
+    /// # Returns
+    /// - Collection with its schema.
+    fn attach(&self, collection: Collection) -> Result<Collection, DbError> {
+        let schema = self.schemas.get(collection.table()).cloned();
+        Ok(collection.with_schema(schema))
+    }
+
+    /// Attach JSON Schema to collection.
+    ///
+    /// Documents are validated in Rust before every insert, replace & patch
+    /// of collections returned by this manager. Where the server supports it,
+    /// `CHECK (JSON_SCHEMA_VALID(...))` constraint is installed as well
+    /// (note that MySQL supports only draft 4 & ignores string formats).
+    ///
+    /// # Parameters
+    /// - `collection` - given collection, its schema is updated as well.
+    /// - `schema`     - given JSON Schema.
+    ///
+    /// # Returns
+    /// - `true` if CHECK constraint was installed, `false` if server does
+    ///   not support it - in case of success.
+    /// - `DbError::InvalidSchema` - if schema is invalid.
+    /// - `DbError` - otherwise (e.g. existing documents violate schema),
+    ///   previous schema & its constraint are kept.
+    pub async fn set_schema(&mut self, collection: &mut Collection, schema: Value)
+        -> Result<bool, DbError>
+    {
+        let schema = Schema::new(schema)?;
+        let pool   = self.pool()?;
+
+        // New constraint is added before old one is dropped, so that failed
+        // change (e.g. existing documents violate schema) keeps old one.
+        // Constraint names alternate, since both exist in between.
+        let existing = Self::schema_checks(pool, collection).await?;
+        let [primary, secondary] = Self::schema_check_names(collection);
+        let name = if existing.contains(&primary) { secondary } else { primary };
+
+        // Leftover of interrupted change.
+        if existing.contains(&name) {
+            Self::drop_check(pool, collection, &name).await?;
+        }
+
+        // Schema can not be bound in DDL statement, so it is inlined
+        // as escaped string literal.
+        let check   = self.check_schema(collection, &schema);
+        let literal = check.to_string().replace('\\', "\\\\").replace('\'', "''");
+        let query   = format!(
+            "ALTER TABLE `{}` ADD CONSTRAINT `{name}` CHECK (JSON_SCHEMA_VALID('{}', `{}`));",
+            collection.table(),
+            literal,
+            collection.doc_column()
+        );
+
+        let installed = match sqlx::raw_sql(query.as_str()).execute(pool).await {
+            Ok(_) => true,
+            Err(sqlx::Error::Database(err))
+                if err.try_downcast_ref::<MySqlDatabaseError>()
+                    .is_some_and(|err| ER_CHECK_UNSUPPORTED.contains(&err.number())) =>
+            {
+                false
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for old in existing.iter().filter(|old| **old != name) {
+            Self::drop_check(pool, collection, old).await?;
+        }
+
+        let query = format!(
+            r#"
+            INSERT INTO {SCHEMAS_TABLE} (table_name, json_schema) VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE json_schema = VALUES(json_schema);
+            "#
+        );
+
+        sqlx::query(query.as_str())
+            .bind(collection.table())
+            .bind(schema.json())
+            .execute(pool)
+            .await?;
+
+        self.schemas.insert(collection.table().to_string(), schema.clone());
+        *collection = collection.clone().with_schema(Some(schema));
+
+        Ok(installed)
+    }
+
+    /// Get JSON Schema checked by CHECK constraint of collection.
+    ///
+    /// Envelopes of encrypted profile fields would not match their schema
+    /// (e.g. `maxLength`), so these fields are accepted as is and left to
+    /// validation of plaintext before encryption.
+    ///
+    /// # Parameters
+    /// - `collection` - given collection.
+    /// - `schema`     - given JSON Schema.
+    ///
+    /// # Returns
+    /// - JSON Schema of CHECK constraint.
+    fn check_schema(&self, collection: &Collection, schema: &Schema) -> Value {
+        let mut json = schema.json().clone();
+
+        let (Some(encryption), "User_Profiles") = (&self.encryption, collection.table()) else {
+            return json;
+        };
+
+        if let Some(Value::Object(properties)) = json.get_mut("properties") {
+            for field in encryption.names() {
+                if let Some(property) = properties.get_mut(field) {
+                    *property = json!({});
+                }
+            }
+        }
+
+        json
+    }
+
+    /// Detach JSON Schema from collection.
+    ///
+    /// # Parameters
+    /// - `collection` - given collection, its schema is removed as well.
+    ///
+    /// # Returns
+    /// - `true` if schema was detached, `false` if collection had no schema.
+    /// - `DbError` - otherwise.
+    pub async fn remove_schema(&mut self, collection: &mut Collection) -> Result<bool, DbError> {
+        let pool  = self.pool()?;
+        let query = format!("DELETE FROM {SCHEMAS_TABLE} WHERE table_name = ?;");
+
+        let result = sqlx::query(query.as_str())
+            .bind(collection.table())
+            .execute(pool)
+            .await?;
+
+        Self::drop_schema_check(pool, collection).await?;
+
+        self.schemas.remove(collection.table());
+        *collection = collection.clone().with_schema(None);
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Get names of collection schema CHECK constraint.
+    ///
+    /// # Parameters
+    /// - `collection` - given collection.
+    ///
+    /// # Returns
+    /// - Constraint names, used alternately by `DocDBManager::set_schema`.
+    fn schema_check_names(collection: &Collection) -> [String; 2] {
+        [
+            format!("{:.57}_schema", collection.table()),
+            format!("{:.56}_schema2", collection.table()),
+        ]
+    }
+
+    /// Get existing collection schema CHECK constraints.
+    ///
+    /// # Parameters
+    /// - `pool`       - given MySQL connection pool.
+    /// - `collection` - given collection.
+    ///
+    /// # Returns
+    /// - Vector of constraint names - in case of success.
+    /// - `DbError` - otherwise.
+    async fn schema_checks(pool: &MySqlPool, collection: &Collection) -> Result<Vec<String>, DbError> {
+        let [primary, secondary] = Self::schema_check_names(collection);
+        let query =
+            r#"
+            SELECT CONSTRAINT_NAME FROM information_schema.TABLE_CONSTRAINTS
+            WHERE CONSTRAINT_SCHEMA = DATABASE() AND TABLE_NAME = ?
+                AND CONSTRAINT_NAME IN (?, ?) AND CONSTRAINT_TYPE = 'CHECK';
+            "#;
+
+        let names: Vec<(String,)> = sqlx::query_as(query)
+            .bind(collection.table())
+            .bind(primary)
+            .bind(secondary)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(names.into_iter().map(|(name,)| name).collect())
+    }
+
+    /// Drop CHECK constraint of collection.
+    ///
+    /// # Parameters
+    /// - `pool`       - given MySQL connection pool.
+    /// - `collection` - given collection.
+    /// - `name`       - given constraint name.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    async fn drop_check(pool: &MySqlPool, collection: &Collection, name: &str) -> Result<(), DbError> {
+        let query = format!("ALTER TABLE `{}` DROP CHECK `{name}`;", collection.table());
+        sqlx::raw_sql(query.as_str()).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Drop collection schema CHECK constraints if exist.
+    ///
+    /// # Parameters
+    /// - `pool`       - given MySQL connection pool.
+    /// - `collection` - given collection.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    async fn drop_schema_check(pool: &MySqlPool, collection: &Collection) -> Result<(), DbError> {
+        for name in Self::schema_checks(pool, collection).await? {
+            Self::drop_check(pool, collection, &name).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get revision history of user profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    ///
+    /// # Returns
+    /// - Vector of revisions from the oldest one - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn revisions(&self, profile_id: i64) -> Result<Vec<Revision>, DbError> {
+        let query = format!(
+            r#"
+            SELECT revision, doc, author, created_at FROM {REVISIONS_TABLE}
+            WHERE table_name = 'User_Profiles' AND document_id = ?
+            ORDER BY revision;
+            "#
+        );
+
+        let mut revisions = sqlx::query_as::<_, Revision>(query.as_str())
+            .bind(profile_id)
+            .fetch_all(self.pool()?)
+            .await?;
+
+        for revision in &mut revisions {
+            if let Some(doc) = &mut revision.doc {
+                self.open_profile(doc)?;
+            }
+        }
+
+        Ok(revisions)
+    }
+
+    /// Get revision of user profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `revision`   - given revision number.
+    ///
+    /// # Returns
+    /// - Revision or `None` if it does not exist - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn revision(&self, profile_id: i64, revision: i64)
+        -> Result<Option<Revision>, DbError>
+    {
+        let query = format!(
+            r#"
+            SELECT revision, doc, author, created_at FROM {REVISIONS_TABLE}
+            WHERE table_name = 'User_Profiles' AND document_id = ? AND revision = ?;
+            "#
+        );
+
+        let mut revision = sqlx::query_as::<_, Revision>(query.as_str())
+            .bind(profile_id)
+            .bind(revision)
+            .fetch_optional(self.pool()?)
+            .await?;
+
+        if let Some(Revision { doc: Some(doc), .. }) = &mut revision {
+            self.open_profile(doc)?;
+        }
+
+        Ok(revision)
+    }
+
+    /// Get difference between two versions of user profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `from`       - given revision number of the base version.
+    /// - `to`         - given target revision number (`None` for current data).
+    ///
+    /// # Returns
+    /// - JSON Patch converting base version into the target one.
+    /// - `DbError::Sqlx(RowNotFound)` - if profile or revision does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn diff_revisions(&self, profile_id: i64, from: i64, to: Option<i64>)
+        -> Result<json_patch::Patch, DbError>
+    {
+        let base   = self.revision_doc(profile_id, Some(from)).await?;
+        let target = self.revision_doc(profile_id, to).await?;
+
+        Ok(json_patch::diff(&base, &target))
+    }
+
+    /// Restore revision of user profile data.
+    ///
+    /// Restoring is an update too, so the replaced version is kept in
+    /// history and restoring can be undone.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `revision`   - given revision number.
+    /// - `author`     - given author of restoring.
+    ///
+    /// # Returns
+    /// - Restored profile data - in case of success.
+    /// - `DbError::Sqlx(RowNotFound)` - if profile or revision does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn restore_revision(&self, profile_id: i64, revision: i64, author: Option<&str>)
+        -> Result<Value, DbError>
+    {
+        let doc      = self.revision_doc(profile_id, Some(revision)).await?;
+        let sealed   = self.seal_doc(doc.clone())?;
+        let profiles = self.profiles()?.with_schema(None).with_author(author);
+
+        if !profiles.replace(profile_id, &sealed).await? {
+            return Err(DbError::Sqlx(sqlx::Error::RowNotFound));
+        }
+
+        Ok(doc)
+    }
+
+    /// Get version of user profile data.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `revision`   - given revision number or `None` for the current one.
+    ///
+    /// # Returns
+    /// - Profile data (missing data is empty object) - in case of success.
+    /// - `DbError::Sqlx(RowNotFound)` - if profile or revision does not exist.
+    /// - `DbError` - otherwise.
+    async fn revision_doc(&self, profile_id: i64, revision: Option<i64>) -> Result<Value, DbError> {
+        let doc = match revision {
+            Some(revision) => self.revision(profile_id, revision).await?.map(|revision| revision.doc),
+            None => {
+                let query = "SELECT profile_data FROM User_Profiles WHERE profile_id = ?;";
+
+                let row = sqlx::query_as::<_, (Option<Value>,)>(query)
+                    .bind(profile_id)
+                    .fetch_optional(self.pool()?)
+                    .await?;
+
+                match row {
+                    Some((Some(mut doc),)) => {
+                        self.open_profile(&mut doc)?;
+                        Some(Some(doc))
+                    }
+                    row => row.map(|(doc,)| doc),
+                }
+            }
+        };
+
+        match doc {
+            Some(doc) => Ok(doc.unwrap_or_else(|| json!({}))),
+            None      => Err(DbError::Sqlx(sqlx::Error::RowNotFound)),
+        }
+    }
+
+    /// Get user profile data with its version.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    ///
+    /// # Returns
+    /// - Versioned profile data or `None` if it does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn get_versioned(&self, profile_id: i64) -> Result<Option<Versioned<Value>>, DbError> {
+        let mut versioned = self.profiles()?.get_versioned::<Value>(profile_id).await?;
+
+        if let Some(versioned) = &mut versioned {
+            self.open_profile(&mut versioned.data)?;
+        }
+
+        Ok(versioned)
+    }
+
+    /// Replace user profile data if its version matches expected one.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `expected`   - given expected profile version.
+    /// - `doc`        - given new profile data.
+    ///
+    /// # Returns
+    /// - New profile version - in case of success.
+    /// - `DbError::Conflict` - if profile was updated concurrently.
+    /// - `DbError` - otherwise.
+    pub async fn update_if_version(&self, profile_id: i64, expected: i64, doc: &Value)
+        -> Result<i64, DbError>
+    {
+        let doc = self.seal_doc(doc.clone())?;
+        self.profiles()?.with_schema(None).update_if_version(profile_id, expected, &doc).await
+    }
+
+    /// Replace user profile data if `If-Match` header value matches its version.
+    ///
+    /// # Parameters
+    /// - `profile_id` - given profile identifier.
+    /// - `if_match`   - given `If-Match` header value (e.g. `"3"` or `*`).
+    /// - `doc`        - given new profile data.
+    ///
+    /// # Returns
+    /// - ETag of new profile version - in case of success.
+    /// - `DbError::InvalidETag` - if header value is invalid.
+    /// - `DbError::Conflict` - if profile version does not match.
+    /// - `DbError` - otherwise.
+    pub async fn update_if_match(&self, profile_id: i64, if_match: &str, doc: &Value)
+        -> Result<String, DbError>
+    {
+        let if_match = IfMatch::parse(if_match)?;
+        let doc      = self.seal_doc(doc.clone())?;
+        let profiles = self.profiles()?.with_schema(None);
+        let version  = profiles.update_if_match(profile_id, if_match, &doc).await?;
+
+        Ok(etag(version))
+    }
+
+    /// Get change feed of messages & user profiles.
+    ///
+    /// Feed starts after the last captured change. Use `ChangeFeed::from_cursor`
+    /// to resume it from persisted cursor.
+    ///
+    /// # Returns
+    /// - Change feed - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn changes(&self) -> Result<ChangeFeed, DbError> {
+        let feed = ChangeFeed::tail(self.pool()?.clone()).await?;
+        Ok(feed.tables(&WATCHED_TABLES))
+    }
 }
@@ -0,0 +1,137 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Database manager error related declarations.
+
+use crate::db::schema::ValidationError;
+use std::{error::Error, fmt};
+
+/// Database manager error.
+#[derive(Debug)]
+pub enum DbError {
+    /// MySQL query error.
+    Sqlx(sqlx::Error),
+    /// JSON serialization or deserialization error.
+    Json(serde_json::Error),
+    /// Invalid table, column or collection name.
+    InvalidName(String),
+    /// Invalid JSON path.
+    InvalidPath(String),
+    /// JSON Patch operation can not be applied.
+    Patch(json_patch::PatchError),
+    /// JSON Patch `test` operation failed at given path.
+    TestFailed(String),
+    /// Invalid JSON Schema.
+    InvalidSchema(String),
+    /// Document does not match collection JSON Schema.
+    Validation(ValidationError),
+    /// Document version does not match expected one.
+    Conflict {
+        /// Expected document version.
+        expected: i64,
+        /// Actual document version.
+        actual: i64,
+    },
+    /// Invalid ETag or `If-Match` header value.
+    InvalidETag(String),
+    /// Stored or written value has unexpected type or format.
+    InvalidValue(String),
+    /// Setting is not declared in settings registry.
+    UnknownSetting(String),
+    /// Setting can not be stored in given scope.
+    InvalidScope(String),
+    /// CSV serialization or deserialization error.
+    Csv(csv::Error),
+    /// Imported setting differs from stored one.
+    ImportConflict(String),
+    /// Value can not be encrypted or decrypted.
+    Crypto(String),
+    /// File system error.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Sqlx(err)         => write!(f, "database error: {err}"),
+            DbError::Json(err)         => write!(f, "JSON error: {err}"),
+            DbError::InvalidName(name) => write!(f, "invalid name: {name:?}"),
+            DbError::InvalidPath(path) => write!(f, "invalid JSON path: {path:?}"),
+            DbError::Patch(err)        => write!(f, "JSON Patch error: {err}"),
+            DbError::TestFailed(path)  => write!(f, "JSON Patch test failed at {path:?}"),
+            DbError::InvalidSchema(e)  => write!(f, "invalid JSON Schema: {e}"),
+            DbError::Validation(err)   => write!(f, "{err}"),
+            DbError::Conflict { expected, actual } => {
+                write!(f, "version conflict: expected {expected}, actual {actual}")
+            }
+            DbError::InvalidETag(etag) => write!(f, "invalid ETag: {etag:?}"),
+            DbError::InvalidValue(e)   => write!(f, "invalid value: {e}"),
+            DbError::UnknownSetting(n) => write!(f, "unknown setting: {n:?}"),
+            DbError::InvalidScope(e)   => write!(f, "invalid setting scope: {e}"),
+            DbError::Csv(err)          => write!(f, "CSV error: {err}"),
+            DbError::ImportConflict(e) => write!(f, "import conflict: {e}"),
+            DbError::Crypto(e)         => write!(f, "encryption error: {e}"),
+            DbError::Io(err)           => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::Sqlx(err)       => Some(err),
+            DbError::Json(err)       => Some(err),
+            DbError::Patch(err)      => Some(err),
+            DbError::Validation(err) => Some(err),
+            DbError::Csv(err)        => Some(err),
+            DbError::Io(err)         => Some(err),
+            _                        => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(err: sqlx::Error) -> Self {
+        DbError::Sqlx(err)
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(err: serde_json::Error) -> Self {
+        DbError::Json(err)
+    }
+}
+
+impl From<std::io::Error> for DbError {
+    fn from(err: std::io::Error) -> Self {
+        DbError::Io(err)
+    }
+}
+
+impl From<csv::Error> for DbError {
+    fn from(err: csv::Error) -> Self {
+        DbError::Csv(err)
+    }
+}
+
+impl From<json_patch::PatchError> for DbError {
+    fn from(err: json_patch::PatchError) -> Self {
+        match err.kind {
+            json_patch::PatchErrorKind::TestFailed => DbError::TestFailed(err.path.to_string()),
+            _                                      => DbError::Patch(err),
+        }
+    }
+}
@@ -16,12 +16,475 @@
 
 //! Global database manager related declarations.
 
-use super::{ConnectionConfig, area::{Area, AreaDB}, dump_db, restore_db, create_db};
-use std::collections::HashMap;
-use chrono::NaiveDate;
-use sqlx::MySqlPool;
+use super::{ConnectionConfig, area::{Area, AreaDB}, dump_db, restore_db, create_db, create_table};
+use super::{crypto::{is_envelope, Cipher}, docdb::REVISIONS_TABLE, kvdb::Namespace};
+use super::search::{self, MessageHit, MessageQuery, ProfileHit, ProfileQuery};
+use sqlx::{pool::PoolConnection, MySql, MySqlConnection, MySqlPool, Row};
+use serde::{Deserialize, Serialize};
+use chrono::{NaiveDateTime, Utc};
+use std::{cmp::Ordering, collections::{HashMap, HashSet}};
+use tokio::task::JoinSet;
 use crate::chat::User;
 
+/// CoreDB table used as durable two-phase commit transaction log.
+const XA_LOG_TABLE: &str = "Xa_Transaction_Log";
+
+/// Default core database name.
+const DEFAULT_CORE_DB: &str = "CoreDB";
+
+/// Prefix of global transaction identifiers issued by the coordinator.
+const XA_PREFIX: &str = "dbp-";
+
+/// Table name, user identifier column & moved columns with their types.
+type UserTable = (&'static str, &'static str, &'static [(&'static str, &'static str)]);
+
+/// Area tables referencing users, moved together with user row.
+///
+/// Surrogate keys are not listed, target area assigns new ones.
+const USER_TABLES: &[UserTable] = &[
+    ("Channel_Users", "user_id", &[
+        ("channel_id", "BIGINT"), ("joined_at", "DATE"), ("role", "TEXT"),
+    ]),
+    ("Bans", "user_id", &[
+        ("channel_id", "BIGINT"), ("banned_at", "DATE"), ("reason", "TEXT"),
+    ]),
+    ("Logs", "user_id", &[
+        ("event_type", "TEXT"), ("channel_id", "BIGINT"),
+        ("timestamp", "DATETIME"), ("details", "TEXT"),
+    ]),
+    ("User_Profiles", "user_id", &[
+        ("bio", "TEXT"), ("profile_picture_url", "TEXT"), ("location", "TEXT"),
+        ("profile_data", "JSON"), ("version", "BIGINT"),
+    ]),
+    ("User_Settings", "user_id", &[
+        ("settings_name", "TEXT"), ("settings_value", "TEXT"),
+    ]),
+    ("User_Settings_KV", "user_id", &[
+        ("setting_name", "VARCHAR(255)"), ("setting_value", "TEXT"),
+        ("expires_at", "DATETIME(6)"),
+    ]),
+];
+
+/// Messages of moved user, inserted one by one to map their identifiers.
+const MESSAGE_TABLE: UserTable = ("Message", "user_id", &[
+    ("channel_id", "BIGINT"), ("message_text", "TEXT"),
+    ("timestamp", "DATETIME"), ("message_data", "JSON"),
+]);
+
+/// Reactions of moved user, inserted with remapped message identifier.
+const REACTION_TABLE: UserTable = ("Reactions", "user_id", &[
+    ("timestamp", "DATETIME"), ("reaction_type", "VARCHAR(255)"),
+]);
+
+/// Build `JSON_OBJECT` expression of moved columns.
+///
+/// # Parameters
+/// - `columns` - given moved columns with their types.
+///
+/// # Returns
+/// - SQL expression of moved row.
+fn moved_object(columns: &[(&str, &str)]) -> String {
+    let objects = columns
+        .iter()
+        .map(|(column, _)| format!("'{column}', {column}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("JSON_OBJECT({objects})")
+}
+
+/// Build query inserting moved rows given as JSON array.
+///
+/// Query binds new user identifier, values of `references` & JSON array.
+///
+/// # Parameters
+/// - `table`       - given table name.
+/// - `user_column` - given user identifier column.
+/// - `references`  - given columns bound as is (e.g. remapped identifiers).
+/// - `columns`     - given moved columns with their types.
+///
+/// # Returns
+/// - SQL `INSERT` query.
+fn insert_moved(table: &str, user_column: &str, references: &[&str], columns: &[(&str, &str)])
+    -> String
+{
+    let names = columns
+        .iter()
+        .map(|(column, _)| *column)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let paths = columns
+        .iter()
+        .map(|(column, kind)| format!("{column} {kind} PATH '$.{column}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let targets = [user_column].iter().chain(references).copied().collect::<Vec<_>>();
+    let params  = vec!["?"; targets.len()].join(", ");
+
+    format!(
+        "INSERT INTO {table} ({}, {names}) \
+         SELECT {params}, {names} FROM JSON_TABLE(?, '$[*]' COLUMNS ({paths})) AS moved;",
+        targets.join(", ")
+    )
+}
+
+/// Cross-area transaction state stored in the transaction log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XaState {
+    /// Branches are started, commit decision is not made yet.
+    Active,
+    /// All branches are prepared & decision to commit is durable.
+    Committing,
+    /// All branches are committed.
+    Committed,
+    /// Transaction is rolled back.
+    RolledBack,
+}
+
+impl XaState {
+    /// Get transaction log representation of the state.
+    ///
+    /// # Returns
+    /// - State name stored in transaction log.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            XaState::Active     => "ACTIVE",
+            XaState::Committing => "COMMITTING",
+            XaState::Committed  => "COMMITTED",
+            XaState::RolledBack => "ROLLED_BACK",
+        }
+    }
+
+    /// Parse transaction log representation of the state.
+    ///
+    /// # Parameters
+    /// - `state` - given state name stored in transaction log.
+    ///
+    /// # Returns
+    /// - Parsed state or `None` for unknown state names.
+    pub fn parse(state: &str) -> Option<Self> {
+        match state {
+            "ACTIVE"      => Some(XaState::Active),
+            "COMMITTING"  => Some(XaState::Committing),
+            "COMMITTED"   => Some(XaState::Committed),
+            "ROLLED_BACK" => Some(XaState::RolledBack),
+            _             => None,
+        }
+    }
+}
+
+/// Execute XA statement for specific transaction branch.
+///
+/// XA statements are not supported by prepared statement protocol,
+/// so identifiers are inlined. Both parts are generated by coordinator.
+///
+/// # Parameters
+/// - `conn`    - given branch connection.
+/// - `command` - given XA command (e.g. `START`, `PREPARE`).
+/// - `gtrid`   - given global transaction identifier.
+/// - `bqual`   - given branch qualifier.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+async fn xa_exec(conn: &mut MySqlConnection, command: &str, gtrid: &str, bqual: &str)
+    -> Result<(), sqlx::Error>
+{
+    let query = format!("XA {command} '{gtrid}','{bqual}';");
+    sqlx::raw_sql(query.as_str()).execute(conn).await?;
+
+    Ok(())
+}
+
+/// Atomically move transaction log record from one state to another.
+///
+/// # Parameters
+/// - `pool`  - given core database MySQL connection pool.
+/// - `xid`   - given global transaction identifier.
+/// - `from`  - given expected current state.
+/// - `to`    - given new state.
+///
+/// # Returns
+/// - `true` if state was changed, `false` if record is in another state.
+/// - `sqlx::Error` - otherwise.
+async fn xa_set_state(pool: &MySqlPool, xid: &str, from: XaState, to: XaState)
+    -> Result<bool, sqlx::Error>
+{
+    let query = format!(
+        "UPDATE {XA_LOG_TABLE} SET state = ?, updated_at = NOW() \
+         WHERE xid = ? AND state = ?;"
+    );
+
+    let result = sqlx::query(query.as_str())
+        .bind(to.as_str())
+        .bind(xid)
+        .bind(from.as_str())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Split `XA RECOVER CONVERT XID` data into transaction identifier parts.
+///
+/// # Parameters
+/// - `data`         - given hexadecimal XID data (e.g. `0x6462702d...`).
+/// - `gtrid_length` - given global transaction identifier length.
+///
+/// # Returns
+/// - Tuple of global transaction identifier & branch qualifier.
+/// - `None` - if XID was not issued by this coordinator.
+fn parse_xid(data: &str, gtrid_length: usize) -> Option<(String, String)> {
+    let hex   = data.strip_prefix("0x")?;
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    if gtrid_length > bytes.len() {
+        return None;
+    }
+
+    let (gtrid, bqual) = bytes.split_at(gtrid_length);
+    let gtrid = String::from_utf8(gtrid.to_vec()).ok()?;
+    let bqual = String::from_utf8(bqual.to_vec()).ok()?;
+
+    // Identifiers are inlined into XA statements, accept only own format.
+    let is_safe = |s: &String| s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+    if !gtrid.starts_with(XA_PREFIX) || !is_safe(&gtrid) || !is_safe(&bqual) {
+        return None;
+    }
+
+    Some((gtrid, bqual))
+}
+
+/// Cross-area transaction coordinated with MySQL XA two-phase commit.
+///
+/// Each area participates with its own branch connection. Statements are
+/// executed on branches via [`XaTransaction::branch`], then transaction
+/// must be finished with [`XaTransaction::commit`] or
+/// [`XaTransaction::rollback`]. Unfinished transaction closes its branch
+/// connections on drop, which makes server roll them back.
+#[derive(Debug)]
+pub struct XaTransaction {
+    /// Global transaction identifier.
+    xid: String,
+    /// Core database MySQL connection pool holding transaction log.
+    core_pool: MySqlPool,
+    /// Area-specific transaction branches.
+    branches: Vec<(Area, PoolConnection<MySql>)>,
+    /// Whether transaction was committed or rolled back.
+    finished: bool,
+}
+
+impl XaTransaction {
+    /// Get global transaction identifier.
+    ///
+    /// # Returns
+    /// - Global transaction identifier.
+    #[inline(always)]
+    pub fn xid(&self) -> &str {
+        &self.xid
+    }
+
+    /// Get transaction branch connection of specific area.
+    ///
+    /// # Parameters
+    /// - `area` - given manager area.
+    ///
+    /// # Returns
+    /// - Branch connection or `None` if area does not participate.
+    pub fn branch(&mut self, area: &Area) -> Option<&mut MySqlConnection> {
+        self.branches
+            .iter_mut()
+            .find(|(branch_area, _)| branch_area == area)
+            .map(|(_, conn)| &mut **conn)
+    }
+
+    /// Commit transaction on every area.
+    ///
+    /// First phase prepares all branches. If any branch fails to prepare,
+    /// whole transaction is rolled back. Otherwise commit decision is stored
+    /// in transaction log before second phase, so that in-doubt branches
+    /// can be committed by recovery after a crash.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn commit(mut self) -> Result<(), sqlx::Error> {
+        // Phase 1: end & prepare every branch.
+        for i in 0..self.branches.len() {
+            let (area, conn) = &mut self.branches[i];
+            let bqual = area.to_string();
+
+            let mut prepared = xa_exec(conn, "END", &self.xid, &bqual).await;
+
+            if prepared.is_ok() {
+                prepared = xa_exec(conn, "PREPARE", &self.xid, &bqual).await;
+            }
+
+            if let Err(err) = prepared {
+                self.abort().await?;
+                return Err(err);
+            }
+        }
+
+        // Store commit decision. Recovery may have rolled transaction back.
+        let decided = xa_set_state(
+            &self.core_pool, &self.xid, XaState::Active, XaState::Committing
+        ).await?;
+
+        if !decided {
+            self.abort().await?;
+            return Err(sqlx::Error::Protocol(
+                format!("transaction {} was rolled back by recovery", self.xid)
+            ));
+        }
+
+        // Phase 2: commit every branch.
+        let mut result = Ok(());
+
+        for (area, conn) in self.branches.iter_mut() {
+            if let Err(err) = xa_exec(conn, "COMMIT", &self.xid, &area.to_string()).await {
+                // Branch stays prepared and will be committed by recovery.
+                conn.close_on_drop();
+
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        self.finished = true;
+        result?;
+
+        xa_set_state(
+            &self.core_pool, &self.xid, XaState::Committing, XaState::Committed
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Roll back transaction on every area.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn rollback(mut self) -> Result<(), sqlx::Error> {
+        self.abort().await
+    }
+
+    /// Roll back every branch & mark transaction as rolled back.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn abort(&mut self) -> Result<(), sqlx::Error> {
+        for (area, conn) in self.branches.iter_mut() {
+            let bqual = area.to_string();
+
+            // Branch may be already ended or prepared, so END error is expected.
+            let _ = xa_exec(conn, "END", &self.xid, &bqual).await;
+
+            if xa_exec(conn, "ROLLBACK", &self.xid, &bqual).await.is_err() {
+                conn.close_on_drop();
+            }
+        }
+
+        self.finished = true;
+        xa_set_state(
+            &self.core_pool, &self.xid, XaState::Active, XaState::RolledBack
+        ).await?;
+
+        Ok(())
+    }
+}
+
+impl Drop for XaTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            // Closing connection makes server roll back unprepared branch.
+            for (_, conn) in self.branches.iter_mut() {
+                conn.close_on_drop();
+            }
+        }
+    }
+}
+
+/// Number of users in specific area.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreaUserCount {
+    /// Area of the users.
+    pub area: Area,
+    /// Number of users.
+    pub count: i64,
+}
+
+/// Number of messages in specific area.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AreaMessageCount {
+    /// Area of the messages.
+    pub area: Area,
+    /// Number of messages.
+    pub count: i64,
+}
+
+/// Channel without any messages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmptyChannel {
+    /// Area of the channel.
+    pub area: Area,
+    /// Name of the channel.
+    pub channel_name: String,
+}
+
+/// Last activity of specific user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserActivity {
+    /// Area of the user.
+    pub area: Area,
+    /// Name of the user.
+    pub username: String,
+    /// Time of the latest logged event or `None` if user has no events.
+    pub last_activity: Option<NaiveDateTime>,
+}
+
+/// Total number of reactions made by specific user across all areas.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserReactions {
+    /// User identifier.
+    pub user_id: i64,
+    /// Number of reactions.
+    pub total_reactions: i64,
+}
+
+/// Parse area from report region column.
+///
+/// # Parameters
+/// - `region` - given region name (e.g. "Russia" or "USA").
+///
+/// # Returns
+/// - Parsed area - in case of success.
+/// - `sqlx::Error` - otherwise.
+fn parse_region(region: &str) -> Result<Area, sqlx::Error> {
+    region.parse().map_err(|err: String| sqlx::Error::Decode(err.into()))
+}
+
+/// Compare relevance scores, the most relevant first.
+///
+/// # Parameters
+/// - `a` - given first score.
+/// - `b` - given second score.
+///
+/// # Returns
+/// - Ordering of scores.
+fn rank(a: f64, b: f64) -> Ordering {
+    b.total_cmp(&a)
+}
+
 /// Global database manager.
 #[derive(Debug, Default)]
 pub struct GlobalDB {
@@ -31,6 +494,12 @@ pub struct GlobalDB {
     global_pool: Option<MySqlPool>,
     /// Core database MySQL connection pool.
     core_pool: Option<MySqlPool>,
+    /// Core database name (`CoreDB` if not set).
+    core_db: Option<String>,
+    /// Skip resolving in-doubt transactions on connect & area insertion.
+    skip_recovery: bool,
+    /// Envelope encryption of user settings, re-encrypted on user move.
+    cipher: Option<Cipher>,
 }
 
 impl GlobalDB {
@@ -42,6 +511,57 @@ impl GlobalDB {
         Self::default()
     }
 
+    /// Set core database name.
+    ///
+    /// # Parameters
+    /// - `name` - given core database name.
+    ///
+    /// # Returns
+    /// - Updated `GlobalDB` object.
+    pub fn with_core_db(mut self, name: &str) -> Self {
+        self.core_db = Some(name.to_string());
+        self
+    }
+
+    /// Set envelope encryption of key-value user settings.
+    ///
+    /// Envelopes are bound to user identifier, so `GlobalDB::move_user`
+    /// re-encrypts them under identifier assigned by target area. Cipher must
+    /// be the one of `KeyValueDBManager::with_encryption`.
+    ///
+    /// # Parameters
+    /// - `cipher` - given envelope encryption.
+    ///
+    /// # Returns
+    /// - Updated `GlobalDB` object.
+    pub fn with_encryption(mut self, cipher: Cipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Do not resolve in-doubt cross-area transactions on connect & area
+    /// insertion.
+    ///
+    /// Recovery runs `XA RECOVER`, which requires `XA_RECOVER_ADMIN`
+    /// privilege since MySQL 8.0.19. Users without it may skip recovery
+    /// and call `GlobalDB::recover()` with another account instead.
+    ///
+    /// # Returns
+    /// - Updated `GlobalDB` object.
+    pub fn without_recovery(mut self) -> Self {
+        self.skip_recovery = true;
+        self
+    }
+
+    /// Get core database name.
+    ///
+    /// # Returns
+    /// - Core database name.
+    #[inline(always)]
+    pub fn core_db(&self) -> &str {
+        self.core_db.as_deref().unwrap_or(DEFAULT_CORE_DB)
+    }
+
     /// Connect global database.
     ///
     /// # Parameters
@@ -57,47 +577,98 @@ impl GlobalDB {
         self.global_pool = Some(MySqlPool::connect(global_url.as_str()).await?);
 
         if let Some(pool) = &self.global_pool {
-            create_db(pool, &"CoreDB".to_string()).await?;
+            create_db(pool, &self.core_db().to_string()).await?;
         }
 
-        let core_db_url = format!("{}CoreDB", global_url);
+        let core_db_url = format!("{}/{}", config.url(), self.core_db());
         self.core_pool  = Some(MySqlPool::connect(&core_db_url).await?);
         self.set_procedures().await?;
+        self.set_tables().await?;
+
+        // Resolve cross-area transactions left in-doubt by previous run.
+        if let Some(pool) = &self.global_pool
+            && !self.skip_recovery
+        {
+            self.recover_xa(pool).await?;
+            self.finalize_xa().await?;
+        }
+
         Ok(())
     }
 
+    /// Create core database tables.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn set_tables(&self) -> Result<(), sqlx::Error> {
+        if let Some(pool) = &self.core_pool {
+            let content = String::from(
+                r#"
+                xid VARCHAR(64) PRIMARY KEY,
+                state VARCHAR(16) NOT NULL,
+                areas VARCHAR(255) NOT NULL,
+                created_at DATETIME NOT NULL,
+                updated_at DATETIME NOT NULL
+                "#
+            );
+
+            create_table(pool, &XA_LOG_TABLE.to_string(), &content).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Create user management procedures dispatching by registered areas.
+    ///
+    /// Procedures are recreated every time new area is inserted.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
     pub async fn set_procedures(&self) -> Result<(), sqlx::Error> {
         if let Some(pool) = &self.core_pool {
             // Procedure to add a new user.
-            let query =
+            let dispatch = self.region_dispatch(|database| format!(
                 r#"
-                CREATE PROCEDURE IF NOT EXISTS AddUser(
+                INSERT INTO {database}.User
+                (username, password_hash, email, created_at, last_login)
+                VALUES (p_username, p_password_hash, p_email, CURDATE(), CURDATE());
+                "#
+            ));
+
+            let query = format!(
+                r#"
+                CREATE PROCEDURE AddUser(
                     IN p_username VARCHAR(255),
                     IN p_password_hash VARCHAR(255),
                     IN p_email VARCHAR(255),
                     IN p_region VARCHAR(255)
                 )
                 BEGIN
-                    IF p_region = 'Russia' THEN
-                        INSERT INTO AreaDB_Russia.User
-                        (username, password_hash, email, created_at, last_login)
-                        VALUES (p_username, p_password_hash, p_email, CURDATE(), CURDATE());
-                    ELSEIF p_region = 'USA' THEN
-                        INSERT INTO AreaDB_USA.User
-                        (username, password_hash, email, created_at, last_login)
-                        VALUES (p_username, p_password_hash, p_email, CURDATE(), CURDATE());
-                    ELSE
-                        SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Invalid region specified';
-                    END IF;
+                    {dispatch}
                 END;
-                "#;
+                "#
+            );
 
-            sqlx::raw_sql(query).execute(pool).await?;
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS AddUser;").execute(pool).await?;
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
 
             // Procedure to update user info.
-            let query =
+            let dispatch = self.region_dispatch(|database| format!(
+                r#"
+                UPDATE {database}.User
+                SET username = p_username,
+                    password_hash = p_password_hash,
+                    email = p_email,
+                    last_login = CURDATE()
+                WHERE user_id = p_user_id;
+                "#
+            ));
+
+            let query = format!(
                 r#"
-                CREATE PROCEDURE IF NOT EXISTS UpdateUser(
+                CREATE PROCEDURE UpdateUser(
                     IN p_user_id BIGINT,
                     IN p_username VARCHAR(255),
                     IN p_password_hash VARCHAR(255),
@@ -105,55 +676,42 @@ impl GlobalDB {
                     IN p_region VARCHAR(255)
                 )
                 BEGIN
-                    IF p_region = 'Russia' THEN
-                        UPDATE AreaDB_Russia.User
-                        SET username = p_username,
-                            password_hash = p_password_hash,
-                            email = p_email,
-                            last_login = CURDATE()
-                        WHERE user_id = p_user_id;
-                    ELSEIF p_region = 'USA' THEN
-                        UPDATE AreaDB_USA.User
-                        SET username = p_username,
-                            password_hash = p_password_hash,
-                            email = p_email,
-                            last_login = CURDATE()
-                        WHERE user_id = p_user_id;
-                    ELSE
-                        SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Invalid region specified';
-                    END IF;
+                    {dispatch}
                 END;
-                "#;
+                "#
+            );
 
-            sqlx::raw_sql(query).execute(pool).await?;
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS UpdateUser;").execute(pool).await?;
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
 
             // Procedure to delete user info.
-            let query =
+            let dispatch = self.region_dispatch(|database| format!(
+                r#"
+                DELETE FROM {database}.User
+                WHERE user_id = p_user_id;
+                "#
+            ));
+
+            let query = format!(
                 r#"
-                CREATE PROCEDURE IF NOT EXISTS DeleteUser(
+                CREATE PROCEDURE DeleteUser(
                     IN p_user_id BIGINT,
                     IN p_region VARCHAR(255)
                 )
                 BEGIN
-                    IF p_region = 'Russia' THEN
-                        DELETE FROM AreaDB_Russia.User
-                        WHERE user_id = p_user_id;
-                    ELSEIF p_region = 'USA' THEN
-                        DELETE FROM AreaDB_USA.User
-                        WHERE user_id = p_user_id;
-                    ELSE
-                        SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Invalid region specified';
-                    END IF;
+                    {dispatch}
                 END;
-                "#;
+                "#
+            );
 
-            sqlx::raw_sql(query).execute(pool).await?;
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS DeleteUser;").execute(pool).await?;
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
         }
         else {
             // Handle case when connection pool was not initialized.
             eprintln!(
-                "Error: connection pool is None: {}",
-                "call GlobalDB::connect() method first!"
+                "Error: connection pool is None: \
+                 call GlobalDB::connect() method first!"
             );
             return Err(sqlx::Error::PoolClosed)
         }
@@ -161,6 +719,67 @@ impl GlobalDB {
         Ok(())
     }
 
+    /// Get registered area-specific database managers ordered by area name.
+    ///
+    /// # Returns
+    /// - Vector of area-specific database managers.
+    fn areas(&self) -> Vec<&AreaDB> {
+        let mut areas: Vec<&AreaDB> = self.table.values().collect();
+        areas.sort_by_key(|area_db| area_db.area().to_string());
+        areas
+    }
+
+    /// Generate procedure body executing statement in database of `p_region`.
+    ///
+    /// # Parameters
+    /// - `statement` - given generator of statement for area database name.
+    ///
+    /// # Returns
+    /// - Procedure body signalling error for unknown regions.
+    fn region_dispatch(&self, statement: impl Fn(&str) -> String) -> String {
+        let signal = "SIGNAL SQLSTATE '45000' SET MESSAGE_TEXT = 'Invalid region specified';";
+        let areas  = self.areas();
+
+        if areas.is_empty() {
+            return signal.to_string();
+        }
+
+        let mut body = String::new();
+
+        for (i, area_db) in areas.iter().enumerate() {
+            let keyword = if i == 0 { "IF" } else { "ELSEIF" };
+
+            body.push_str(&format!(
+                "{keyword} p_region = '{}' THEN {}\n",
+                area_db.area(),
+                statement(area_db.name())
+            ));
+        }
+
+        body.push_str(&format!("ELSE {signal}\nEND IF;"));
+        body
+    }
+
+    /// Generate query combining per-area queries with `UNION ALL`.
+    ///
+    /// # Parameters
+    /// - `select` - given generator of query for area & its database name.
+    ///
+    /// # Returns
+    /// - Combined query or `None` if there are no registered areas.
+    fn union_by_area(&self, select: impl Fn(&Area, &str) -> String) -> Option<String> {
+        let selects = self.areas()
+            .iter()
+            .map(|area_db| select(&area_db.area(), area_db.name()))
+            .collect::<Vec<_>>();
+
+        if selects.is_empty() {
+            return None;
+        }
+
+        Some(selects.join("\nUNION ALL\n"))
+    }
+
     /// Insert area-specific database manager.
     ///
     /// # Parameters
@@ -182,10 +801,506 @@ impl GlobalDB {
         let url = config.url_db();
         manager.connect(url.as_str()).await?;
 
-        self.table.insert(manager.area(), manager);
+        let area = manager.area();
+        self.table.insert(area, manager);
+
+        // Area may be hosted on another server with its own in-doubt branches.
+        if let Some(pool) = self.table.get(&area).and_then(AreaDB::pool)
+            && !self.skip_recovery
+        {
+            self.recover_xa(pool).await?;
+            self.finalize_xa().await?;
+        }
+
+        self.set_procedures().await?;
         Ok(())
     }
 
+    /// Begin cross-area transaction.
+    ///
+    /// # Parameters
+    /// - `areas` - given areas participating in transaction.
+    ///
+    /// # Returns
+    /// - New `XaTransaction` object - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn begin_xa(&self, areas: &[Area])
+        -> Result<XaTransaction, sqlx::Error>
+    {
+        let Some(core_pool) = &self.core_pool else {
+            return Err(sqlx::Error::PoolClosed);
+        };
+
+        let xid = format!(
+            "{}{:x}-{:016x}",
+            XA_PREFIX,
+            Utc::now().timestamp_millis(),
+            rand::random::<u64>()
+        );
+
+        let area_names = areas
+            .iter()
+            .map(|area| area.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let query = format!(
+            "INSERT INTO {XA_LOG_TABLE} (xid, state, areas, created_at, updated_at) \
+             VALUES (?, ?, ?, NOW(), NOW());"
+        );
+
+        sqlx::query(query.as_str())
+            .bind(&xid)
+            .bind(XaState::Active.as_str())
+            .bind(&area_names)
+            .execute(core_pool)
+            .await?;
+
+        let mut transaction = XaTransaction {
+            xid,
+            core_pool: core_pool.clone(),
+            branches:  Vec::with_capacity(areas.len()),
+            finished:  false,
+        };
+
+        for area in areas {
+            if transaction.branch(area).is_some() {
+                continue;
+            }
+
+            let started = async {
+                let pool = self.table
+                    .get(area)
+                    .and_then(AreaDB::pool)
+                    .ok_or(sqlx::Error::RowNotFound)?;
+
+                let mut conn = pool.acquire().await?;
+                xa_exec(&mut conn, "START", &transaction.xid, &area.to_string()).await?;
+                Ok::<_, sqlx::Error>(conn)
+            }.await;
+
+            match started {
+                Ok(conn) => transaction.branches.push((*area, conn)),
+                Err(err) => {
+                    transaction.rollback().await?;
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(transaction)
+    }
+
+    /// Resolve in-doubt cross-area transactions.
+    ///
+    /// Requires `XA_RECOVER_ADMIN` privilege since MySQL 8.0.19.
+    ///
+    /// # Returns
+    /// - Number of resolved transaction branches - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn recover(&self) -> Result<usize, sqlx::Error> {
+        let mut resolved = 0;
+
+        for pool in self.xa_pools() {
+            resolved += self.recover_xa(pool).await?;
+        }
+
+        self.finalize_xa().await?;
+        Ok(resolved)
+    }
+
+    /// Get connection pools of all servers which may host transaction branches.
+    ///
+    /// # Returns
+    /// - Global & area MySQL connection pools.
+    fn xa_pools(&self) -> Vec<&MySqlPool> {
+        self.global_pool
+            .iter()
+            .chain(self.table.values().filter_map(AreaDB::pool))
+            .collect()
+    }
+
+    /// Get prepared transaction branches issued by this coordinator.
+    ///
+    /// # Parameters
+    /// - `pool` - given MySQL connection pool of server hosting branches.
+    ///
+    /// # Returns
+    /// - Vector of global transaction identifiers & branch qualifiers -
+    ///   in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn prepared_xa(pool: &MySqlPool) -> Result<Vec<(String, String)>, sqlx::Error> {
+        let rows = sqlx::raw_sql("XA RECOVER CONVERT XID;")
+            .fetch_all(pool)
+            .await?;
+
+        let mut prepared = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let gtrid_length: i64 = row.try_get("gtrid_length")?;
+            let data: String      = row.try_get("data")?;
+
+            if let Some(xid) = parse_xid(&data, gtrid_length as usize) {
+                prepared.push(xid);
+            }
+        }
+
+        Ok(prepared)
+    }
+
+    /// Mark committing transactions without prepared branches as committed.
+    ///
+    /// Branches of committing transaction can not be rolled back, so once
+    /// none of them is prepared on servers of its areas, all of them are
+    /// committed. Transactions with areas not inserted yet are left as is.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn finalize_xa(&self) -> Result<(), sqlx::Error> {
+        let Some(core_pool) = &self.core_pool else {
+            return Err(sqlx::Error::PoolClosed);
+        };
+
+        let query = format!("SELECT xid, areas FROM {XA_LOG_TABLE} WHERE state = ?;");
+        let committing: Vec<(String, String)> = sqlx::query_as(query.as_str())
+            .bind(XaState::Committing.as_str())
+            .fetch_all(core_pool)
+            .await?;
+
+        if committing.is_empty() {
+            return Ok(());
+        }
+
+        let mut prepared = HashSet::new();
+
+        for pool in self.xa_pools() {
+            prepared.extend(Self::prepared_xa(pool).await?.into_iter().map(|(gtrid, _)| gtrid));
+        }
+
+        for (xid, areas) in committing {
+            let is_known = areas
+                .split(',')
+                .all(|area| area.parse().is_ok_and(|area| self.table.contains_key(&area)));
+
+            if is_known && !prepared.contains(&xid) {
+                xa_set_state(core_pool, &xid, XaState::Committing, XaState::Committed).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve prepared transaction branches visible through given pool.
+    ///
+    /// Branches of transactions with durable commit decision are committed.
+    /// Any other branch is rolled back (presumed abort). Branches logged by
+    /// coordinators using another core database are left untouched.
+    ///
+    /// # Parameters
+    /// - `pool` - given MySQL connection pool of server hosting branches.
+    ///
+    /// # Returns
+    /// - Number of resolved transaction branches - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn recover_xa(&self, pool: &MySqlPool) -> Result<usize, sqlx::Error> {
+        let Some(core_pool) = &self.core_pool else {
+            return Err(sqlx::Error::PoolClosed);
+        };
+
+        let mut resolved = 0;
+
+        for (gtrid, bqual) in Self::prepared_xa(pool).await? {
+            // Log record is written before branches are started, so transaction
+            // without commit decision can not be committed anymore.
+            xa_set_state(core_pool, &gtrid, XaState::Active, XaState::RolledBack).await?;
+
+            let query = format!("SELECT state FROM {XA_LOG_TABLE} WHERE xid = ?;");
+            let state: Option<(String,)> = sqlx::query_as(query.as_str())
+                .bind(&gtrid)
+                .fetch_optional(core_pool)
+                .await?;
+
+            // Branch belongs to coordinator with another core database.
+            let Some((state,)) = state else {
+                continue;
+            };
+
+            let command = match XaState::parse(&state) {
+                Some(XaState::Committing | XaState::Committed) => "COMMIT",
+                _ => "ROLLBACK",
+            };
+
+            let query = format!("XA {command} '{gtrid}','{bqual}';");
+            sqlx::raw_sql(query.as_str()).execute(pool).await?;
+            resolved += 1;
+        }
+
+        Ok(resolved)
+    }
+
+    /// Move user from one area to another atomically.
+    ///
+    /// Rows of `USER_TABLES` referencing the user (channel memberships, bans,
+    /// logs, profile & settings), messages & reactions are moved in the same
+    /// transaction under new user identifier. Their surrogate keys are
+    /// reassigned by target area. Encrypted user settings require cipher set
+    /// by `GlobalDB::with_encryption`.
+    ///
+    /// Reactions of the user are moved with remapped message identifiers.
+    /// Reactions of other users on moved messages & reactions of the user on
+    /// messages staying in source area are deleted, as are revisions of moved
+    /// profile. Channels are not moved, so channel identifiers of memberships,
+    /// bans, messages & logs are kept as is and refer to channels of source
+    /// area.
+    ///
+    /// # Parameters
+    /// - `user_id` - given user identifier in source area.
+    /// - `from`    - given source area.
+    /// - `to`      - given target area.
+    ///
+    /// # Returns
+    /// - User identifier in target area - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn move_user(&self, user_id: i64, from: &Area, to: &Area)
+        -> Result<i64, sqlx::Error>
+    {
+        if from == to {
+            return Ok(user_id);
+        }
+
+        let mut transaction = self.begin_xa(&[*from, *to]).await?;
+
+        let cipher = self.cipher.as_ref();
+
+        match Self::move_user_branches(&mut transaction, cipher, user_id, from, to).await {
+            Ok(new_user_id) => {
+                transaction.commit().await?;
+                Ok(new_user_id)
+            }
+            Err(err) => {
+                transaction.rollback().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Execute user move statements on transaction branches.
+    ///
+    /// # Parameters
+    /// - `transaction` - given cross-area transaction.
+    /// - `cipher`      - given envelope encryption of user settings.
+    /// - `user_id`     - given user identifier in source area.
+    /// - `from`        - given source area.
+    /// - `to`          - given target area.
+    ///
+    /// # Returns
+    /// - User identifier in target area - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn move_user_branches(
+        transaction: &mut XaTransaction,
+        cipher: Option<&Cipher>,
+        user_id: i64,
+        from: &Area,
+        to: &Area
+    ) -> Result<i64, sqlx::Error>
+    {
+        let source = transaction.branch(from).ok_or(sqlx::Error::RowNotFound)?;
+
+        let user: User = sqlx::query_as("SELECT * FROM User WHERE user_id = ?;")
+            .bind(user_id)
+            .fetch_optional(&mut *source)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        sqlx::query("DELETE FROM User WHERE user_id = ?;")
+            .bind(user_id)
+            .execute(&mut *source)
+            .await?;
+
+        // Reactions referencing messages of another author can not be moved.
+        let query =
+            r#"
+            DELETE Reactions FROM Reactions
+            JOIN Message ON Message.message_id = Reactions.message_id
+            WHERE (Message.user_id = ?) <> (Reactions.user_id = ?);
+            "#;
+
+        sqlx::query(query)
+            .bind(user_id)
+            .bind(user_id)
+            .execute(&mut *source)
+            .await?;
+
+        // Revisions are keyed by profile identifier reassigned by target area.
+        let query =
+            r#"
+            SELECT COUNT(*) FROM information_schema.TABLES
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?;
+            "#;
+
+        let (revisions,): (i64,) = sqlx::query_as(query)
+            .bind(REVISIONS_TABLE)
+            .fetch_one(&mut *source)
+            .await?;
+
+        if revisions > 0 {
+            let query = format!(
+                "DELETE FROM {REVISIONS_TABLE} WHERE table_name = 'User_Profiles' AND document_id IN \
+                 (SELECT profile_id FROM User_Profiles WHERE user_id = ?);"
+            );
+
+            sqlx::query(query.as_str())
+                .bind(user_id)
+                .execute(&mut *source)
+                .await?;
+        }
+
+        let mut moved = Vec::with_capacity(USER_TABLES.len());
+
+        for (table, user_column, columns) in USER_TABLES {
+            let query = format!(
+                "SELECT CAST(COALESCE(JSON_ARRAYAGG({}), JSON_ARRAY()) AS CHAR) \
+                 FROM {table} WHERE {user_column} = ?;",
+                moved_object(columns)
+            );
+
+            let (rows,): (String,) = sqlx::query_as(query.as_str())
+                .bind(user_id)
+                .fetch_one(&mut *source)
+                .await?;
+
+            moved.push(rows);
+        }
+
+        // Messages & reactions are keyed by source message identifier.
+        let messages  = Self::select_by_message(source, &MESSAGE_TABLE, user_id).await?;
+        let reactions = Self::select_by_message(source, &REACTION_TABLE, user_id).await?;
+
+        for (table, user_column, _) in USER_TABLES.iter().chain([&MESSAGE_TABLE, &REACTION_TABLE]) {
+            let query = format!("DELETE FROM {table} WHERE {user_column} = ?;");
+            sqlx::query(query.as_str())
+                .bind(user_id)
+                .execute(&mut *source)
+                .await?;
+        }
+
+        let target = transaction.branch(to).ok_or(sqlx::Error::RowNotFound)?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO User
+            (username, password_hash, email, created_at, last_login)
+            VALUES (?, ?, ?, ?, ?);
+            "#
+        )
+            .bind(&user.username)
+            .bind(&user.password_hash)
+            .bind(&user.email)
+            .bind(user.created_at)
+            .bind(user.last_login)
+            .execute(&mut *target)
+            .await?;
+
+        let new_user_id = result.last_insert_id() as i64;
+
+        for ((table, user_column, columns), rows) in USER_TABLES.iter().zip(moved) {
+            let query = insert_moved(table, user_column, &[], columns);
+
+            sqlx::query(query.as_str())
+                .bind(new_user_id)
+                .bind(rows)
+                .execute(&mut *target)
+                .await?;
+        }
+
+        // Envelopes are bound to user identifier, which target area reassigns.
+        let settings: Vec<(String, String)> =
+            sqlx::query_as("SELECT setting_name, setting_value FROM User_Settings_KV WHERE user_id = ?;")
+                .bind(new_user_id)
+                .fetch_all(&mut *target)
+                .await?;
+
+        for (name, value) in settings.into_iter().filter(|(_, value)| is_envelope(value)) {
+            let cipher = cipher.ok_or_else(|| {
+                sqlx::Error::Configuration("user settings are encrypted, but cipher is not set".into())
+            })?;
+
+            let value = cipher
+                .decrypt(&value, &Namespace::User(user_id).context(&name))
+                .and_then(|plaintext| cipher.encrypt(&plaintext, &Namespace::User(new_user_id).context(&name)))
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+            sqlx::query("UPDATE User_Settings_KV SET setting_value = ? WHERE user_id = ? AND setting_name = ?;")
+                .bind(value)
+                .bind(new_user_id)
+                .bind(name)
+                .execute(&mut *target)
+                .await?;
+        }
+
+        let (table, user_column, columns) = MESSAGE_TABLE;
+        let query = insert_moved(table, user_column, &[], columns);
+        let mut message_ids = HashMap::with_capacity(messages.len());
+
+        for (message_id, row) in messages {
+            let result = sqlx::query(query.as_str())
+                .bind(new_user_id)
+                .bind(row)
+                .execute(&mut *target)
+                .await?;
+
+            message_ids.insert(message_id, result.last_insert_id() as i64);
+        }
+
+        let (table, user_column, columns) = REACTION_TABLE;
+        let query = insert_moved(table, user_column, &["message_id"], columns);
+
+        // Reactions on messages missing in source area are dropped.
+        for (message_id, row) in reactions {
+            let Some(message_id) = message_ids.get(&message_id) else {
+                continue;
+            };
+
+            sqlx::query(query.as_str())
+                .bind(new_user_id)
+                .bind(message_id)
+                .bind(row)
+                .execute(&mut *target)
+                .await?;
+        }
+
+        Ok(new_user_id)
+    }
+
+    /// Select moved rows of user keyed by message identifier.
+    ///
+    /// # Parameters
+    /// - `conn`    - given source branch connection.
+    /// - `table`   - given table with `message_id` column.
+    /// - `user_id` - given user identifier in source area.
+    ///
+    /// # Returns
+    /// - Vector of message identifiers & single row JSON arrays -
+    ///   in case of success.
+    /// - `sqlx::Error` - otherwise.
+    async fn select_by_message(conn: &mut MySqlConnection, table: &UserTable, user_id: i64)
+        -> Result<Vec<(i64, String)>, sqlx::Error>
+    {
+        let (table, user_column, columns) = table;
+
+        let query = format!(
+            "SELECT message_id, CAST(JSON_ARRAY({}) AS CHAR) FROM {table} \
+             WHERE {user_column} = ? ORDER BY message_id;",
+            moved_object(columns)
+        );
+
+        sqlx::query_as(query.as_str())
+            .bind(user_id)
+            .fetch_all(conn)
+            .await
+    }
+
     /// Dump database by specific area.
     ///
     /// # Parameters
@@ -205,7 +1320,7 @@ impl GlobalDB {
         let area_db   = self.table.get(area).unwrap();
         let dump_name = format!("{}_backup.sql", area_db.name());
 
-        dump_db(&area_db.config(), &dump_name)?;
+        dump_db(area_db.config(), &dump_name)?;
 
         // Store backup on current server.
         let mut config  = area_db.config().clone();
@@ -237,7 +1352,7 @@ impl GlobalDB {
         let area_db   = self.table.get(area).unwrap();
         let dump_name = format!("{}_backup.sql", area_db.name());
 
-        restore_db(&area_db.config(), &dump_name)?;
+        restore_db(area_db.config(), &dump_name)?;
         Ok(())
     }
 
@@ -258,7 +1373,7 @@ impl GlobalDB {
                 .bind(&user.username)
                 .bind(&user.password_hash)
                 .bind(&user.email)
-                .bind(&area.to_string())
+                .bind(area.to_string())
                 .execute(pool)
                 .await?;
         }
@@ -280,11 +1395,11 @@ impl GlobalDB {
 
         if let Some(pool) = &self.core_pool {
             sqlx::query(query)
-                .bind(&user.user_id)
+                .bind(user.user_id)
                 .bind(&user.username)
                 .bind(&user.password_hash)
                 .bind(&user.email)
-                .bind(&area.to_string())
+                .bind(area.to_string())
                 .execute(pool)
                 .await?;
         }
@@ -306,8 +1421,8 @@ impl GlobalDB {
 
         if let Some(pool) = &self.core_pool {
             sqlx::query(query)
-                .bind(&user_id)
-                .bind(&area.to_string())
+                .bind(user_id)
+                .bind(area.to_string())
                 .execute(pool)
                 .await?;
         }
@@ -315,121 +1430,67 @@ impl GlobalDB {
         Ok(())
     }
 
-    /// Print all users from the specified region.
+    /// List users from the specified area.
     ///
     /// # Parameters
-    /// - `pool` - The database connection pool.
-    /// - `region` - The region to filter users by (e.g., "Russia" or "USA").
+    /// - `area`   - given manager area.
+    /// - `offset` - given number of users to skip.
+    /// - `limit`  - given maximum number of users to return.
     ///
     /// # Returns
-    /// - `Result<(), sqlx::Error>` - Ok if successful, or an error if something goes wrong.
-    pub async fn print_users_by_area(&self, area: &Area) -> Result<(), sqlx::Error> {
-        let area_db = self.table.get(area).unwrap();
-        let query   = format!("SELECT * FROM {}.User;", area_db.config().database);
+    /// - Vector of users ordered by identifier - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn list_users_by_area(&self, area: &Area, offset: u64, limit: u64)
+        -> Result<Vec<User>, sqlx::Error>
+    {
+        let area_db = self.table.get(area).ok_or(sqlx::Error::RowNotFound)?;
+        let query   = format!(
+            "SELECT * FROM {}.User ORDER BY user_id LIMIT ? OFFSET ?;",
+            area_db.config().database
+        );
 
         if let Some(pool) = &self.global_pool {
             let users: Vec<User> = sqlx::query_as(query.as_str())
+                .bind(limit)
+                .bind(offset)
                 .fetch_all(pool)
                 .await?;
 
-            for user in users {
-                println!(
-                    "User ID: {}, Username: {}, Email: {}, Created At: {:?}, Last Login: {:?}",
-                    user.user_id, user.username, user.email, user.created_at, user.last_login
-                );
-            }
+            return Ok(users);
         }
 
-        Ok(())
-    }
-
-    pub async fn test_procedures(&self) -> Result<(), sqlx::Error> {
-        // Create a test user
-        let test_user = User {
-            user_id:        2,
-            username:       "test_user".to_string(),
-            password_hash:  "hashed_password".to_string(),
-            email:          "test_user@example.com".to_string(),
-            created_at:     None,
-            last_login:     None,
-        };
-
-        let area    = Area::Usa;
-        let area_db = self.table.get(&area).unwrap();
-
-        println!("Test procedures");
-        println!("{:#?}", test_user);
-
-        println!("Before test:");
-        self.print_users_by_area(&area).await?;
-
-        println!("Test AddUser():");
-        // Add the user
-        self.add_user(&test_user, &area).await?;
-
-        println!("After test AddUser():");
-        self.print_users_by_area(&area).await?;
-
-        // Update the user
-        let updated_user = User {
-            user_id:       test_user.user_id,
-            username:      "updated_user".to_string(),
-            password_hash: "new_hashed_password".to_string(),
-            email:         "updated_user@example.com".to_string(),
-            created_at:    test_user.created_at,
-            last_login:    test_user.last_login,
-        };
-
-        self.update_user(&updated_user, &area).await?;
-
-        println!("After test UpdateUser():");
-        self.print_users_by_area(&area).await?;
-
-        // Delete the user
-        self.delete_user(updated_user.user_id, &area).await?;
-
-        println!("After test DeleteUser():");
-        self.print_users_by_area(&area).await?;
-
-        if let Some(pool) = &self.global_pool {
-            let query = format!("TRUNCATE TABLE {}.User;", area_db.config().database);
-
-            sqlx::query(query.as_str())
-                .bind(&area_db.config().database)
-                .execute(pool)
-                .await?;
-        }
-
-        Ok(())
+        Err(sqlx::Error::PoolClosed)
     }
 
     /// Get user count by each region.
     ///
     /// # Returns
-    /// - Vector of tuple of area name & its user count - in case of success.
+    /// - Vector of area user counts - in case of success.
     /// - `sqlx::Error` - otherwise.
-    pub async fn get_user_count(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
-        let query =
+    pub async fn get_user_count(&self) -> Result<Vec<AreaUserCount>, sqlx::Error> {
+        let query = self.union_by_area(|area, database| format!(
             r#"
             SELECT
-                'Russia' AS region,
-                COUNT(*) AS user_count
-            FROM
-                AreaDB_Russia.User
-            UNION ALL
-            SELECT
-                'USA' AS region,
+                '{area}' AS region,
                 COUNT(*) AS user_count
             FROM
-                AreaDB_USA.User;
-            "#;
+                {database}.User
+            "#
+        ));
 
         if let Some(pool) = &self.core_pool {
-            let count = sqlx::query_as::<_, (String, i64)>(query)
+            let Some(query) = query else {
+                return Ok(Vec::new());
+            };
+
+            let rows = sqlx::query_as::<_, (String, i64)>(query.as_str())
                 .fetch_all(pool)
                 .await?;
 
-            return Ok(count);
+            return rows
+                .into_iter()
+                .map(|(region, count)| Ok(AreaUserCount { area: parse_region(&region)?, count }))
+                .collect();
         }
 
         Err(sqlx::Error::RowNotFound)
@@ -438,202 +1499,246 @@ impl GlobalDB {
     /// Get message count by each region.
     ///
     /// # Returns
-    /// - Vector of tuple of area name & its message count - in case of success.
+    /// - Vector of area message counts - in case of success.
     /// - `sqlx::Error` - otherwise.
-    pub async fn get_message_count(&self) -> Result<Vec<(String, i64)>, sqlx::Error> {
-        let query =
+    pub async fn get_message_count(&self) -> Result<Vec<AreaMessageCount>, sqlx::Error> {
+        let query = self.union_by_area(|area, database| format!(
             r#"
-            WITH MessageCounts AS (
-                SELECT
-                    'Russia' AS region,
-                    COUNT(m.message_id) AS message_count
-                FROM
-                    AreaDB_Russia.Message m
-                UNION ALL
-                SELECT
-                    'USA' AS region,
-                    COUNT(m.message_id) AS message_count
-                FROM
-                    AreaDB_USA.Message m
-            )
-
             SELECT
-                region,
-                CAST(SUM(message_count) AS SIGNED) AS total_messages
+                '{area}' AS region,
+                COUNT(m.message_id) AS message_count
             FROM
-                MessageCounts
-            GROUP BY
-                region;
-            "#;
+                {database}.Message m
+            "#
+        ));
 
         if let Some(pool) = &self.core_pool {
-            let count = sqlx::query_as::<_, (String, i64)>(query)
+            let Some(query) = query else {
+                return Ok(Vec::new());
+            };
+
+            let rows = sqlx::query_as::<_, (String, i64)>(query.as_str())
                 .fetch_all(pool)
                 .await?;
 
-            return Ok(count);
+            return rows
+                .into_iter()
+                .map(|(region, count)| Ok(AreaMessageCount { area: parse_region(&region)?, count }))
+                .collect();
         }
 
         Err(sqlx::Error::RowNotFound)
     }
 
-    /// Get the top 5 channels by message count for each region.
+    /// Get channels without any messages for each region.
     ///
     /// # Returns
-    /// - Vector of tuples of area, channel, and its message count - in case of success.
+    /// - Vector of empty channels - in case of success.
     /// - `sqlx::Error` - otherwise.
-    pub async fn get_channels_with_no_messages(&self) -> Result<Vec<(String, String)>, sqlx::Error> {
-        let query = r#"
+    pub async fn get_channels_with_no_messages(&self) -> Result<Vec<EmptyChannel>, sqlx::Error> {
+        let query = self.union_by_area(|area, database| format!(
+            r#"
             SELECT
                 c.channel_name,
-                'Russia' AS region
+                '{area}' AS region
             FROM
-                AreaDB_Russia.Channel c
+                {database}.Channel c
             LEFT JOIN
-                AreaDB_Russia.Message m ON c.channel_id = m.channel_id
+                {database}.Message m ON c.channel_id = m.channel_id
             WHERE
                 m.message_id IS NULL
-
-            UNION ALL
-
-            SELECT
-                c.channel_name,
-                'USA' AS region
-            FROM
-                AreaDB_USA.Channel c
-            LEFT JOIN
-                AreaDB_USA.Message m ON c.channel_id = m.channel_id
-            WHERE
-                m.message_id IS NULL;
-        "#;
+            "#
+        ));
 
         if let Some(pool) = &self.core_pool {
-            let channels = sqlx::query_as::<_, (String, String)>(query)
+            let Some(query) = query else {
+                return Ok(Vec::new());
+            };
+
+            let rows = sqlx::query_as::<_, (String, String)>(query.as_str())
                 .fetch_all(pool)
                 .await?;
 
-            return Ok(channels);
+            return rows
+                .into_iter()
+                .map(|(channel_name, region)| {
+                    Ok(EmptyChannel { area: parse_region(&region)?, channel_name })
+                })
+                .collect();
         }
 
         Err(sqlx::Error::RowNotFound)
     }
 
-    pub async fn get_last_activity(&self)
-        -> Result<Vec<(String, String, Option<NaiveDate>)>, sqlx::Error>
-    {
-        let query = r#"
+    /// Get last activity of every user for each region.
+    ///
+    /// # Returns
+    /// - Vector of user activities - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn get_last_activity(&self) -> Result<Vec<UserActivity>, sqlx::Error> {
+        let query = self.union_by_area(|area, database| format!(
+            r#"
             SELECT
-                'Russia' AS region,
+                '{area}' AS region,
                 u.username,
                 MAX(l.timestamp) AS last_activity
             FROM
-                AreaDB_Russia.User u
+                {database}.User u
             LEFT JOIN
-                AreaDB_Russia.Logs l ON u.user_id = l.user_id
+                {database}.Logs l ON u.user_id = l.user_id
             GROUP BY
                 u.user_id
-
-            UNION ALL
-
-            SELECT
-                'USA' AS region,
-                u.username,
-                MAX(l.timestamp) AS last_activity
-            FROM
-                AreaDB_USA.User u
-            LEFT JOIN
-                AreaDB_USA.Logs l ON u.user_id = l.user_id
-            GROUP BY
-                u.user_id;
-        "#;
+            "#
+        ));
 
         if let Some(pool) = &self.core_pool {
-            let activities = sqlx::query_as::<_, (String, String, Option<NaiveDate>)>(query)
+            let Some(query) = query else {
+                return Ok(Vec::new());
+            };
+
+            let rows = sqlx::query_as::<_, (String, String, Option<NaiveDateTime>)>(query.as_str())
                 .fetch_all(pool)
                 .await?;
 
-            return Ok(activities);
+            return rows
+                .into_iter()
+                .map(|(region, username, last_activity)| {
+                    Ok(UserActivity { area: parse_region(&region)?, username, last_activity })
+                })
+                .collect();
         }
 
         Err(sqlx::Error::RowNotFound)
     }
 
-    pub async fn get_total_reactions(&self) -> Result<Vec<(i64, i64)>, sqlx::Error> {
-        let query = r#"
+    /// Get total reactions of every user across all regions.
+    ///
+    /// # Returns
+    /// - Vector of user reaction totals - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn get_total_reactions(&self) -> Result<Vec<UserReactions>, sqlx::Error> {
+        let reactions = self.union_by_area(|_, database| format!(
+            r#"
             SELECT
-                user_id,
-                CAST(SUM(reaction_count) AS SIGNED) AS total_reactions
-            FROM (
-                SELECT
-                    r.user_id,
-                    COUNT(r.reaction_id) AS reaction_count
-                FROM
-                    AreaDB_Russia.Reactions r
-                GROUP BY
-                    r.user_id
-                UNION ALL
-                SELECT
-                    r.user_id,
-                    COUNT(r.reaction_id) AS reaction_count
-                FROM
-                    AreaDB_USA.Reactions r
-                GROUP BY
-                    r.user_id
-            ) AS combined_reactions
+                r.user_id,
+                COUNT(r.reaction_id) AS reaction_count
+            FROM
+                {database}.Reactions r
             GROUP BY
-                user_id;
-        "#;
+                r.user_id
+            "#
+        ));
 
         if let Some(pool) = &self.core_pool {
-            let reactions = sqlx::query_as::<_, (i64, i64)>(query)
+            let Some(reactions) = reactions else {
+                return Ok(Vec::new());
+            };
+
+            let query = format!(
+                r#"
+                SELECT
+                    user_id,
+                    CAST(SUM(reaction_count) AS SIGNED) AS total_reactions
+                FROM (
+                    {reactions}
+                ) AS combined_reactions
+                GROUP BY
+                    user_id;
+                "#
+            );
+
+            let rows = sqlx::query_as::<_, (i64, i64)>(query.as_str())
                 .fetch_all(pool)
                 .await?;
 
-            return Ok(reactions);
+            return Ok(rows
+                .into_iter()
+                .map(|(user_id, total_reactions)| UserReactions { user_id, total_reactions })
+                .collect());
         }
 
         Err(sqlx::Error::RowNotFound)
     }
 
-    pub async fn test_requests(&self) -> Result<(), sqlx::Error> {
-        println!("Test get_user_count():");
-        let user_count = self.get_user_count().await?;
+    /// Search messages across all areas.
+    ///
+    /// Areas are searched concurrently & hits are merged by relevance score.
+    /// Scores are computed by every area separately, so they are comparable
+    /// only approximately.
+    ///
+    /// # Parameters
+    /// - `query` - given search query.
+    ///
+    /// # Returns
+    /// - Vector of at most `query.limit` hits ordered by relevance.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn search_messages(&self, query: &MessageQuery)
+        -> Result<Vec<MessageHit>, sqlx::Error>
+    {
+        let mut tasks = JoinSet::new();
+
+        for area_db in self.areas() {
+            let Some(pool) = area_db.pool().cloned() else {
+                return Err(sqlx::Error::PoolClosed);
+            };
 
-        for (area, count) in user_count {
-            println!("Area: {area}, Count: {count}");
+            let (area, query) = (area_db.area(), query.clone());
+            tasks.spawn(async move { search::search_messages(&pool, area, &query).await });
         }
 
-        println!("Test get_message_count():");
-        let msg_count = self.get_message_count().await?;
+        let mut hits = Vec::new();
 
-        for (area, count) in msg_count {
-            println!("Area: {area}, Count: {count}");
+        while let Some(result) = tasks.join_next().await {
+            hits.extend(result.map_err(|_| sqlx::Error::WorkerCrashed)??);
         }
 
-        println!("Test get_channels_with_no_messages():");
-        let channels = self.get_channels_with_no_messages().await?;
+        hits.sort_by(|a, b| {
+            rank(a.score, b.score)
+                .then_with(|| a.area.to_string().cmp(&b.area.to_string()))
+                .then(a.message_id.cmp(&b.message_id))
+        });
 
-        for (channel_name, region) in channels {
-            println!("Channel: {}, Region: {}", channel_name, region);
-        }
+        hits.truncate(query.limit as usize);
+        Ok(hits)
+    }
+
+    /// Search user profiles across all areas by bio & location.
+    ///
+    /// Areas are searched concurrently & hits are merged by relevance score.
+    ///
+    /// # Parameters
+    /// - `query` - given search query.
+    ///
+    /// # Returns
+    /// - Vector of at most `query.limit` hits ordered by relevance.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn search_profiles(&self, query: &ProfileQuery)
+        -> Result<Vec<ProfileHit>, sqlx::Error>
+    {
+        let mut tasks = JoinSet::new();
 
-        println!("Test get_last_activity():");
-        let activities = self.get_last_activity().await?;
+        for area_db in self.areas() {
+            let Some(pool) = area_db.pool().cloned() else {
+                return Err(sqlx::Error::PoolClosed);
+            };
 
-        for (region, username, last_activity) in activities {
-            match last_activity {
-                Some(timestamp) => println!("Region: {}, Username: {}, Last Activity: {}", region, username, timestamp),
-                None => println!("Region: {}, Username: {}, Last Activity: None", region, username),
-            }
+            let (area, query) = (area_db.area(), query.clone());
+            tasks.spawn(async move { search::search_profiles(&pool, area, &query).await });
         }
 
-        println!("Test get_total_reactions():");
-        let reactions = self.get_total_reactions().await?;
+        let mut hits = Vec::new();
 
-        for (user_id, total_reactions) in reactions {
-            println!("User ID: {}, Total Reactions: {}", user_id, total_reactions);
+        while let Some(result) = tasks.join_next().await {
+            hits.extend(result.map_err(|_| sqlx::Error::WorkerCrashed)??);
         }
 
-        Ok(())
+        hits.sort_by(|a, b| {
+            rank(a.score, b.score)
+                .then_with(|| a.area.to_string().cmp(&b.area.to_string()))
+                .then(a.profile_id.cmp(&b.profile_id))
+        });
+
+        hits.truncate(query.limit as usize);
+        Ok(hits)
     }
-}
\ No newline at end of file
+}
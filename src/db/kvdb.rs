@@ -14,10 +14,299 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use sqlx::{mysql::{MySqlArguments, MySqlDatabaseError}, Arguments, MySqlPool};
+use tokio::task::JoinHandle;
+use std::{collections::{HashMap, HashSet}, time::Duration};
 use serde_json::Value;
-use sqlx::MySqlPool;
+use chrono::NaiveDateTime;
 use crate::chat::{create_db_tables, fill_db_tables, UserSettingKV};
-use crate::db::{create_db, ConnectionConfig};
+use crate::db::{add_column, add_index, drop_index, changefeed::{self, ChangeFeed}, create_db, create_table, ConnectionConfig};
+use crate::db::cache::{CacheConfig, CacheStats, KvCache, VERSIONS_TABLE};
+use crate::db::crypto::{Cipher, Encryption, ENVELOPE_PREFIX};
+use crate::db::transfer::{ConflictPolicy, ImportAction, ImportReport, SettingChange, SettingRecord};
+use crate::db::{collection::validate_name, error::DbError, registry::{EffectiveSetting, EffectiveSettings, SettingScope, SettingSource, SettingType, SettingsRegistry}};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Table storing keys of custom namespaces.
+pub const CUSTOM_TABLE: &str = "Custom_KV";
+
+/// Tables storing keys of all namespaces.
+pub const KV_TABLES: [&str; 4] = [
+    "Server_Settings_KV", "Channel_Settings_KV", "User_Settings_KV", CUSTOM_TABLE
+];
+
+/// Tables whose changes are captured into change feed.
+pub const WATCHED_TABLES: [&str; 4] = KV_TABLES;
+
+/// Maximum key length.
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Maximum value length in characters (before encryption).
+pub const MAX_VALUE_LENGTH: usize = 255;
+
+/// SQL condition selecting keys which are not expired.
+const NOT_EXPIRED: &str = "(expires_at IS NULL OR expires_at > NOW(6))";
+
+/// MySQL error number of duplicate key.
+const ER_DUP_ENTRY: u16 = 1062;
+
+/// MySQL error number of deadlock.
+const ER_LOCK_DEADLOCK: u16 = 1213;
+
+/// Maximum number of attempts of counter update.
+const INCR_ATTEMPTS: usize = 3;
+
+/// Maximum number of namespace versions checked by single query.
+const VERSIONS_BATCH_SIZE: usize = 500;
+
+/// Remaining time to live of key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ttl {
+    /// Key never expires.
+    Persistent,
+    /// Key expires after given duration.
+    Expires(Duration),
+}
+
+/// Key-value store namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    /// Server settings (`Server_Settings_KV`).
+    Server,
+    /// Settings of channel (`Channel_Settings_KV`).
+    Channel(i64),
+    /// Settings of user (`User_Settings_KV`).
+    User(i64),
+    /// Arbitrary named namespace (`Custom_KV`).
+    Custom(String),
+}
+
+impl Namespace {
+    /// Get table storing keys of the namespace.
+    ///
+    /// # Returns
+    /// - Table name.
+    pub fn table(&self) -> &'static str {
+        match self {
+            Namespace::Server     => "Server_Settings_KV",
+            Namespace::Channel(_) => "Channel_Settings_KV",
+            Namespace::User(_)    => "User_Settings_KV",
+            Namespace::Custom(_)  => CUSTOM_TABLE,
+        }
+    }
+
+    /// Get column separating namespaces stored in the same table.
+    ///
+    /// # Returns
+    /// - Column name or `None` for server namespace.
+    pub fn scope_column(&self) -> Option<&'static str> {
+        match self {
+            Namespace::Server     => None,
+            Namespace::Channel(_) => Some("channel_id"),
+            Namespace::User(_)    => Some("user_id"),
+            Namespace::Custom(_)  => Some("namespace"),
+        }
+    }
+
+    /// Get scope of namespace within its table.
+    ///
+    /// # Returns
+    /// - Value of scope column as string (empty for server namespace).
+    pub(crate) fn scope(&self) -> String {
+        match self {
+            Namespace::Server       => String::new(),
+            Namespace::Channel(id)  => id.to_string(),
+            Namespace::User(id)     => id.to_string(),
+            Namespace::Custom(name) => name.clone(),
+        }
+    }
+
+    /// Check namespace name.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of valid namespace.
+    /// - `DbError::InvalidName` - otherwise.
+    fn validate(&self) -> Result<(), DbError> {
+        match self {
+            Namespace::Custom(name) => validate_name(name),
+            _                       => Ok(()),
+        }
+    }
+
+    /// Generate SQL condition selecting keys of the namespace.
+    ///
+    /// # Returns
+    /// - SQL condition with scope placeholder (see `Namespace::add_scope`).
+    fn condition(&self) -> String {
+        match self.scope_column() {
+            Some(column) => format!("{column} = ? AND setting_value IS NOT NULL AND {NOT_EXPIRED}"),
+            None         => format!("setting_value IS NOT NULL AND {NOT_EXPIRED}"),
+        }
+    }
+
+    /// Get encryption context of key, binding its envelope to location.
+    ///
+    /// # Parameters
+    /// - `key` - given key.
+    ///
+    /// # Returns
+    /// - Context string.
+    pub(crate) fn context(&self, key: &str) -> String {
+        match self {
+            Namespace::Server       => format!("server/{key}"),
+            Namespace::Channel(id)  => format!("channel/{id}/{key}"),
+            Namespace::User(id)     => format!("user/{id}/{key}"),
+            Namespace::Custom(name) => format!("custom/{name}/{key}"),
+        }
+    }
+
+    /// Add namespace scope to query arguments.
+    ///
+    /// # Parameters
+    /// - `args` - given query arguments.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    fn add_scope(&self, args: &mut MySqlArguments) -> Result<(), sqlx::Error> {
+        let result = match self {
+            Namespace::Server       => Ok(()),
+            Namespace::Channel(id)  => args.add(*id),
+            Namespace::User(id)     => args.add(*id),
+            Namespace::Custom(name) => args.add(name.clone()),
+        };
+
+        result.map_err(sqlx::Error::Encode)
+    }
+}
+
+/// Page of keys returned by `KeyValueDBManager::scan`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanPage {
+    /// Keys & values ordered by key.
+    pub entries: Vec<(String, String)>,
+    /// Cursor of the next page or `None` if there are no more keys.
+    pub cursor: Option<String>,
+}
+
+/// Check key name.
+///
+/// # Parameters
+/// - `key` - given key.
+///
+/// # Returns
+/// - `Ok` - in case of valid key.
+/// - `DbError::InvalidName` - otherwise.
+fn validate_key(key: &str) -> Result<(), DbError> {
+    if key.is_empty() || key.chars().count() > MAX_KEY_LENGTH {
+        return Err(DbError::InvalidName(key.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Convert duration into whole microseconds.
+///
+/// # Parameters
+/// - `duration` - given duration.
+///
+/// # Returns
+/// - Number of microseconds (saturated).
+fn micros(duration: Duration) -> i64 {
+    i64::try_from(duration.as_micros()).unwrap_or(i64::MAX)
+}
+
+/// Escape `LIKE` pattern special characters.
+///
+/// # Parameters
+/// - `value` - given string.
+///
+/// # Returns
+/// - String matching itself literally.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Make scope column plain part of primary key.
+///
+/// Old schema declared scope column as `AUTO_INCREMENT UNIQUE`, so only
+/// single key per scope could be stored. Existing rows are kept as is.
+///
+/// # Parameters
+/// - `pool`   - given MySQL connection pool.
+/// - `table`  - given key-value table name.
+/// - `column` - given scope column name.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+async fn migrate_scope_key(pool: &MySqlPool, table: &str, column: &str) -> Result<(), sqlx::Error> {
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?
+        AND EXTRA LIKE '%auto_increment%';
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query)
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+
+    if count > 0 {
+        let query = format!("ALTER TABLE `{table}` MODIFY `{column}` BIGINT NOT NULL;");
+        sqlx::query(query.as_str()).execute(pool).await?;
+    }
+
+    // Unique index is named after its column.
+    drop_index(pool, table, column).await?;
+    Ok(())
+}
+
+/// Make value column long enough to store envelopes of encrypted values.
+///
+/// Old schema declared value column as `VARCHAR(255)`, which fits envelope
+/// of less than 100 bytes. Value length is limited by `MAX_VALUE_LENGTH`
+/// on write instead.
+///
+/// # Parameters
+/// - `pool`  - given MySQL connection pool.
+/// - `table` - given key-value table name.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+async fn migrate_value_column(pool: &MySqlPool, table: &str) -> Result<(), sqlx::Error> {
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = 'setting_value'
+        AND DATA_TYPE = 'varchar';
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query)
+        .bind(table)
+        .fetch_one(pool)
+        .await?;
+
+    if count > 0 {
+        let query = format!("ALTER TABLE `{table}` MODIFY `setting_value` TEXT;");
+        sqlx::query(query.as_str()).execute(pool).await?;
+    }
+
+    Ok(())
+}
 
 /// Key-value database Manager.
 #[derive(Debug, Default)]
@@ -26,6 +315,12 @@ pub struct KeyValueDBManager {
     pool: Option<MySqlPool>,
     /// Connection config associated with DB manager.
     config: ConnectionConfig,
+    /// Registry of known settings (writes are not checked if `None`).
+    registry: Option<SettingsRegistry>,
+    /// Read-through cache (reads are not cached if `None`).
+    cache: Option<KvCache>,
+    /// Encryption of sensitive keys (values are stored as is if `None`).
+    encryption: Option<Encryption>,
 }
 
 impl KeyValueDBManager {
@@ -37,6 +332,64 @@ impl KeyValueDBManager {
         Self::default()
     }
 
+    /// Check settings against registry.
+    ///
+    /// Writes of unknown or invalid settings into server, channel & user
+    /// namespaces are rejected. Custom namespaces are not checked.
+    pub fn with_registry(mut self, registry: SettingsRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Cache reads of keys.
+    ///
+    /// Writes made through this manager invalidate cached keys at once,
+    /// writes of other processes are noticed on the next version check
+    /// (requires `KeyValueDBManager::set_procedures`).
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(KvCache::new(config));
+        self
+    }
+
+    /// Encrypt values of given keys at rest.
+    ///
+    /// Keys are encrypted in every namespace & decrypted transparently on
+    /// read. Plaintext written before is still readable and is encrypted by
+    /// `KeyValueDBManager::rotate_encryption`. Encrypted keys can not be
+    /// incremented.
+    ///
+    /// # Parameters
+    /// - `cipher` - given envelope encryption.
+    /// - `keys`   - given names of encrypted keys.
+    pub fn with_encryption(mut self, cipher: Cipher, keys: &[&str]) -> Self {
+        self.encryption = Some(Encryption::new(cipher, keys));
+        self
+    }
+
+    /// Get cache metrics.
+    ///
+    /// # Returns
+    /// - Cache metrics or `None` if reads are not cached.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(KvCache::stats)
+    }
+
+    /// Invalidate all cached keys.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Get registry of known settings.
+    ///
+    /// # Returns
+    /// - Settings registry or `None` if settings are not checked.
+    #[inline(always)]
+    pub fn registry(&self) -> Option<&SettingsRegistry> {
+        self.registry.as_ref()
+    }
+
     /// Connect database.
     ///
     /// # Parameters
@@ -54,7 +407,54 @@ impl KeyValueDBManager {
         let pool = MySqlPool::connect(config.url_db().as_str()).await?;
 
         create_db_tables(&pool).await?;
+
+        let content = String::from(
+            r#"
+            namespace VARCHAR(64) NOT NULL,
+            setting_name VARCHAR(255) NOT NULL,
+            setting_value TEXT,
+            expires_at DATETIME(6),
+            PRIMARY KEY(namespace, setting_name)
+            "#
+        );
+
+        create_table(&pool, &CUSTOM_TABLE.to_string(), &content).await?;
+
+        let content = String::from(
+            r#"
+            table_name VARCHAR(64) NOT NULL,
+            scope VARCHAR(64) NOT NULL DEFAULT '',
+            version BIGINT NOT NULL,
+            PRIMARY KEY(table_name, scope)
+            "#
+        );
+
+        create_table(&pool, &VERSIONS_TABLE.to_string(), &content).await?;
+
+        // Tables created before per-namespace versions hold version per table.
+        if add_column(&pool, VERSIONS_TABLE, "scope", "VARCHAR(64) NOT NULL DEFAULT '' AFTER table_name").await? {
+            let query = format!("ALTER TABLE {VERSIONS_TABLE} DROP PRIMARY KEY, ADD PRIMARY KEY(table_name, scope);");
+            sqlx::raw_sql(query.as_str()).execute(&pool).await?;
+        }
+
+        // Tables created before key expiry support have no expiry column.
+        for table in KV_TABLES {
+            add_column(&pool, table, "expires_at", "DATETIME(6)").await?;
+            add_index(&pool, table, "ix_expires_at", "expires_at").await?;
+        }
+
+        // Tables created before encryption support can not store envelopes.
+        for table in KV_TABLES {
+            migrate_value_column(&pool, table).await?;
+        }
+
+        // Tables created before multiple keys per scope support allow single key.
+        for (table, column) in [("Channel_Settings_KV", "channel_id"), ("User_Settings_KV", "user_id")] {
+            migrate_scope_key(&pool, table, column).await?;
+        }
+
         fill_db_tables(&pool, 1).await?;
+
         self.pool   = Some(pool);
         self.config = config;
 
@@ -63,54 +463,51 @@ impl KeyValueDBManager {
 
     pub async fn set_procedures(&self) -> Result<(), sqlx::Error> {
         if let Some(pool) = &self.pool {
+            // Procedures are recreated, so existing databases get
+            // expiry support & atomic upsert.
             let query =
                 r#"
-                CREATE  PROCEDURE IF NOT EXISTS AddUserSetting(
+                CREATE PROCEDURE AddUserSetting(
                     IN p_user_id BIGINT,
                     IN p_setting_name VARCHAR(255),
-                    IN p_setting_value VARCHAR(255)
+                    IN p_setting_value TEXT
                 )
                 BEGIN
-                    DECLARE existing_count INT;
-
-                    SELECT COUNT(*)
-                    INTO existing_count
-                    FROM User_Settings_KV
-                    WHERE user_id = p_user_id AND setting_name = p_setting_name;
-
-                    IF existing_count > 0 THEN
-                        UPDATE User_Settings_KV
-                        SET setting_value = p_setting_value
-                        WHERE user_id = p_user_id AND setting_name = p_setting_name;
-                    ELSE
-                        INSERT INTO User_Settings_KV (user_id, setting_name, setting_value)
-                        VALUES (p_user_id, p_setting_name, p_setting_value);
-                    END IF;
+                    INSERT INTO User_Settings_KV (user_id, setting_name, setting_value)
+                    VALUES (p_user_id, p_setting_name, p_setting_value) AS new
+                    ON DUPLICATE KEY UPDATE
+                        setting_value = new.setting_value,
+                        expires_at    = NULL;
                 END;
                 "#;
 
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS AddUserSetting;").execute(pool).await?;
             sqlx::raw_sql(query).execute(pool).await?;
 
             let query =
                 r#"
-                CREATE PROCEDURE IF NOT EXISTS GetUserSetting(
+                CREATE PROCEDURE GetUserSetting(
                     IN p_user_id BIGINT,
                     IN p_setting_name VARCHAR(255),
-                    OUT p_setting_value VARCHAR(255)
+                    OUT p_setting_value TEXT
                 )
                 BEGIN
+                    SET p_setting_value = NULL;
+
                     SELECT setting_value
                     INTO p_setting_value
                     FROM User_Settings_KV
-                    WHERE user_id = p_user_id AND setting_name = p_setting_name;
+                    WHERE user_id = p_user_id AND setting_name = p_setting_name
+                        AND (expires_at IS NULL OR expires_at > NOW(6));
                 END;
                 "#;
 
+            sqlx::raw_sql("DROP PROCEDURE IF EXISTS GetUserSetting;").execute(pool).await?;
             sqlx::raw_sql(query).execute(pool).await?;
 
             let query =
                 r#"
-                CREATE FUNCTION IF NOT EXISTS GetAllUserSettings(
+                CREATE FUNCTION GetAllUserSettings(
                     p_user_id BIGINT
                 ) RETURNS JSON
                 READS SQL DATA
@@ -120,21 +517,79 @@ impl KeyValueDBManager {
                     SELECT JSON_OBJECTAGG(setting_name, setting_value)
                     INTO settings
                     FROM User_Settings_KV
-                    WHERE user_id = p_user_id;
+                    WHERE user_id = p_user_id
+                        AND (expires_at IS NULL OR expires_at > NOW(6));
 
                     RETURN settings;
                 END;
                 "#;
 
+            sqlx::raw_sql("DROP FUNCTION IF EXISTS GetAllUserSettings;").execute(pool).await?;
             sqlx::raw_sql(query).execute(pool).await?;
 
+            // Every change bumps version of its namespace, so caches of
+            // other processes notice it.
+            let namespaces = [
+                Namespace::Server,
+                Namespace::Channel(0),
+                Namespace::User(0),
+                Namespace::Custom(String::new()),
+            ];
+
+            for namespace in namespaces {
+                let table = namespace.table();
+                let bump  = |row: &str| {
+                    let scope = match namespace.scope_column() {
+                        Some(column) => format!("CAST({row}.{column} AS CHAR)"),
+                        None         => "''".to_string(),
+                    };
+
+                    format!(
+                        "INSERT INTO {VERSIONS_TABLE} (table_name, scope, version) \
+                         VALUES ('{table}', {scope}, 1) AS new \
+                         ON DUPLICATE KEY UPDATE version = {VERSIONS_TABLE}.version + 1;"
+                    )
+                };
+
+                for op in ["INSERT", "UPDATE", "DELETE"] {
+                    let body = match (op, namespace.scope_column()) {
+                        ("INSERT", _) => bump("NEW"),
+                        ("DELETE", _) => bump("OLD"),
+                        (_, None)     => bump("NEW"),
+                        // Key moved to another scope changes both of them.
+                        (_, Some(column)) => format!(
+                            "{} IF NOT (OLD.{column} <=> NEW.{column}) THEN {} END IF;",
+                            bump("NEW"),
+                            bump("OLD")
+                        ),
+                    };
+
+                    let trigger = format!("BumpVersion{op}_{table}");
+                    let query   = format!(
+                        r#"
+                        CREATE TRIGGER {trigger}
+                        AFTER {op} ON {table}
+                        FOR EACH ROW
+                        BEGIN
+                            {body}
+                        END;
+                        "#
+                    );
+
+                    let drop = format!("DROP TRIGGER IF EXISTS {trigger};");
+                    sqlx::raw_sql(drop.as_str()).execute(pool).await?;
+                    sqlx::raw_sql(query.as_str()).execute(pool).await?;
+                }
+            }
+
+            changefeed::install(pool, &WATCHED_TABLES).await?;
             Ok(())
         }
         else {
             // Handle case when connection pool was not initialized.
             eprintln!(
-                "Error: connection pool is None: {}",
-                "call KeyValueDBManager::connect() method first!"
+                "Error: connection pool is None: \
+                 call KeyValueDBManager::connect() method first!"
             );
             Err(sqlx::Error::PoolClosed)
         }
@@ -143,15 +598,25 @@ impl KeyValueDBManager {
     pub async fn add_user_setting(&self, user_setting_kv: &UserSettingKV)
         -> Result<(), sqlx::Error>
     {
+        let namespace = Namespace::User(user_setting_kv.user_id);
+
+        self.check_write(&namespace, &user_setting_kv.settings_name, &user_setting_kv.settings_value)
+            .map_err(|err| sqlx::Error::Encode(Box::new(err)))?;
+
+        let value = self.conceal(&namespace, &user_setting_kv.settings_name, &user_setting_kv.settings_value)
+            .map_err(|err| sqlx::Error::Encode(Box::new(err)))?;
+
         if let Some(pool) = &self.pool {
             let query = "CALL AddUserSetting(?, ?, ?);";
 
             sqlx::query(query)
-                .bind(&user_setting_kv.user_id)
+                .bind(user_setting_kv.user_id)
                 .bind(&user_setting_kv.settings_name)
-                .bind(&user_setting_kv.settings_value)
+                .bind(value)
                 .execute(pool)
                 .await?;
+
+            self.invalidate(&namespace, &user_setting_kv.settings_name);
         }
 
         Ok(())
@@ -160,25 +625,38 @@ impl KeyValueDBManager {
     pub async fn get_user_setting(&self, user_id: i64, setting_name: String)
         -> Result<String, sqlx::Error>
     {
+        if self.cache.is_some() {
+            let value = self.get(&Namespace::User(user_id), &setting_name)
+                .await
+                .map_err(|err| match err {
+                    DbError::Sqlx(err) => err,
+                    err                => sqlx::Error::Decode(Box::new(err)),
+                })?;
+
+            return value.ok_or(sqlx::Error::RowNotFound);
+        }
+
         if let Some(pool) = &self.pool {
+            // Session variable is read on the same connection.
+            let mut conn = pool.acquire().await?;
+
             let query = "CALL GetUserSetting(?, ?, @setting_value);";
             sqlx::query(query)
                 .bind(user_id)
                 .bind(&setting_name)
-                .execute(pool)
+                .execute(&mut *conn)
                 .await?;
 
             // Fetch the value of @setting_value.
             let query = "SELECT @setting_value;";
             let row: (Option<String>,) = sqlx::query_as(query)
-                .fetch_one(pool)
+                .fetch_one(&mut *conn)
                 .await?;
 
-            return if let Some(value) = row.0 {
-                Ok(value)
-            } else {
-                Err(sqlx::Error::RowNotFound)
-            }
+            let value = row.0.ok_or(sqlx::Error::RowNotFound)?;
+
+            return self.reveal(&Namespace::User(user_id), &setting_name, value)
+                .map_err(|err| sqlx::Error::Decode(Box::new(err)));
         }
 
         Err(sqlx::Error::RowNotFound)
@@ -189,15 +667,1329 @@ impl KeyValueDBManager {
     {
         if let Some(pool) = &self.pool {
             let query = "SELECT GetAllUserSettings(?) AS settings;";
-            let row: (Option<Value>,) = sqlx::query_as(query)
+            let mut row: (Option<Value>,) = sqlx::query_as(query)
                 .bind(user_id)
                 .fetch_one(pool)
                 .await?;
 
+            if let Some(Value::Object(settings)) = &mut row.0 {
+                let namespace = Namespace::User(user_id);
+
+                for (key, value) in settings.iter_mut() {
+                    if let Value::String(stored) = value {
+                        let revealed = self.reveal(&namespace, key, std::mem::take(stored))
+                            .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+
+                        *value = Value::String(revealed);
+                    }
+                }
+            }
+
             Ok(row.0)
         } else {
             Err(sqlx::Error::RowNotFound)
         }
     }
 
+    /// Get change feed of server, channel & user settings.
+    ///
+    /// Feed starts after the last captured change. Use `ChangeFeed::from_cursor`
+    /// to resume it from persisted cursor.
+    ///
+    /// # Returns
+    /// - Change feed - in case of success.
+    /// - `sqlx::Error` - otherwise.
+    pub async fn changes(&self) -> Result<ChangeFeed, sqlx::Error> {
+        if let Some(pool) = &self.pool {
+            let feed = ChangeFeed::tail(pool.clone()).await?;
+            Ok(feed.tables(&WATCHED_TABLES))
+        } else {
+            Err(sqlx::Error::PoolClosed)
+        }
+    }
+
+    /// Get manager connection pool.
+    ///
+    /// # Returns
+    /// - Manager MySQL connection pool - in case of success.
+    /// - `DbError` - if manager is not connected.
+    fn pool(&self) -> Result<&MySqlPool, DbError> {
+        self.pool.as_ref().ok_or(DbError::Sqlx(sqlx::Error::PoolClosed))
+    }
+
+    /// Invalidate cached key after write.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    fn invalidate(&self, namespace: &Namespace, key: &str) {
+        if let Some(cache) = &self.cache {
+            cache.invalidate(namespace, key);
+        }
+    }
+
+    /// Invalidate cached keys of namespaces changed by other processes.
+    ///
+    /// # Parameters
+    /// - `cache` - given manager cache.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    async fn sync_versions(&self, cache: &KvCache) -> Result<(), DbError> {
+        if !cache.needs_version_check() {
+            return Ok(());
+        }
+
+        let scopes       = cache.scopes();
+        let mut versions = HashMap::new();
+
+        for chunk in scopes.chunks(VERSIONS_BATCH_SIZE) {
+            let query = format!(
+                "SELECT table_name, scope, version FROM {VERSIONS_TABLE} WHERE (table_name, scope) IN ({});",
+                vec!["(?, ?)"; chunk.len()].join(", ")
+            );
+
+            let mut args = MySqlArguments::default();
+
+            for (table, scope) in chunk {
+                args.add(table.as_str()).map_err(sqlx::Error::Encode)?;
+                args.add(scope.as_str()).map_err(sqlx::Error::Encode)?;
+            }
+
+            let rows: Vec<(String, String, i64)> = sqlx::query_as_with(query.as_str(), args)
+                .fetch_all(self.pool()?)
+                .await?;
+
+            versions.extend(rows.into_iter().map(|(table, scope, version)| ((table, scope), version)));
+        }
+
+        cache.apply_versions(&scopes, &versions);
+        Ok(())
+    }
+
+    /// Fetch live keys from database.
+    ///
+    /// Namespace version is read by the same statement, so it matches
+    /// fetched values.
+    ///
+    /// # Parameters
+    /// - `namespace` - given keys namespace.
+    /// - `keys`      - given non-empty keys.
+    ///
+    /// # Returns
+    /// - Namespace version (0 if never changed) & found keys, values and
+    ///   remaining microseconds to live - in case of success.
+    /// - `DbError` - otherwise.
+    async fn fetch(&self, namespace: &Namespace, keys: &[&str])
+        -> Result<(i64, Vec<(String, String, Option<i64>)>), DbError>
+    {
+        let query = format!(
+            r#"
+            SELECT current.version, setting_name, setting_value,
+                TIMESTAMPDIFF(MICROSECOND, NOW(6), expires_at)
+            FROM (
+                SELECT COALESCE(
+                    (SELECT version FROM {VERSIONS_TABLE} WHERE table_name = ? AND scope = ?), 0
+                ) AS version
+            ) AS current
+            LEFT JOIN {} ON {} AND setting_name IN ({});
+            "#,
+            namespace.table(),
+            namespace.condition(),
+            vec!["?"; keys.len()].join(", ")
+        );
+
+        let mut args = MySqlArguments::default();
+        args.add(namespace.table()).map_err(sqlx::Error::Encode)?;
+        args.add(namespace.scope()).map_err(sqlx::Error::Encode)?;
+        namespace.add_scope(&mut args)?;
+
+        for key in keys {
+            args.add(*key).map_err(sqlx::Error::Encode)?;
+        }
+
+        type Row = (i64, Option<String>, Option<String>, Option<i64>);
+
+        let rows: Vec<Row> = sqlx::query_as_with(query.as_str(), args)
+            .fetch_all(self.pool()?)
+            .await?;
+
+        let version = rows.first().map_or(0, |(version, _, _, _)| *version);
+        let found   = rows
+            .into_iter()
+            .filter_map(|(_, name, value, micros)| Some((name?, value?, micros)))
+            .collect();
+
+        Ok((version, found))
+    }
+
+    /// Check written value against settings registry.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given written value.
+    ///
+    /// # Returns
+    /// - `Ok` - if value may be written.
+    /// - `DbError` - otherwise.
+    fn check_write(&self, namespace: &Namespace, key: &str, value: &str) -> Result<(), DbError> {
+        match (&self.registry, SettingScope::of(namespace)) {
+            (Some(registry), Some(_)) => registry.check(namespace, key, value),
+            _                         => Ok(()),
+        }
+    }
+
+    /// Check whether values of key are encrypted.
+    fn encrypts(&self, key: &str) -> bool {
+        self.encryption.as_ref().is_some_and(|encryption| encryption.covers(key))
+    }
+
+    /// Encrypt written value if key is encrypted.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given written value.
+    ///
+    /// # Returns
+    /// - Stored value - in case of success.
+    /// - `DbError::InvalidValue` - if value is longer than `MAX_VALUE_LENGTH`.
+    /// - `DbError` - otherwise.
+    fn conceal(&self, namespace: &Namespace, key: &str, value: &str) -> Result<String, DbError> {
+        // Value column fits envelopes, so limit is checked before encryption.
+        if value.chars().count() > MAX_VALUE_LENGTH {
+            return Err(DbError::InvalidValue(format!(
+                "value of {key:?} is longer than {MAX_VALUE_LENGTH} characters"
+            )));
+        }
+
+        match &self.encryption {
+            Some(encryption) => encryption.conceal(key, value, &namespace.context(key)),
+            None             => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypt stored value if key is encrypted.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `stored`    - given stored value.
+    ///
+    /// # Returns
+    /// - Value - in case of success.
+    /// - `DbError` - otherwise.
+    fn reveal(&self, namespace: &Namespace, key: &str, stored: String) -> Result<String, DbError> {
+        match &self.encryption {
+            Some(encryption) => encryption.reveal(key, stored, &namespace.context(key)),
+            None             => Ok(stored),
+        }
+    }
+
+    /// Get value of key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - Value or `None` if key does not exist - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn get(&self, namespace: &Namespace, key: &str) -> Result<Option<String>, DbError> {
+        Ok(self.mget(namespace, &[key]).await?.remove(0))
+    }
+
+    /// Get values of keys.
+    ///
+    /// # Parameters
+    /// - `namespace` - given keys namespace.
+    /// - `keys`      - given keys.
+    ///
+    /// # Returns
+    /// - Values in order of keys (`None` for missing ones) - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn mget(&self, namespace: &Namespace, keys: &[&str]) -> Result<Vec<Option<String>>, DbError> {
+        namespace.validate()?;
+        keys.iter().try_for_each(|key| validate_key(key))?;
+
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let find = |rows: &[(String, String, Option<i64>)], key: &str| {
+            rows.iter().find(|(name, _, _)| name == key).cloned()
+        };
+
+        let Some(cache) = &self.cache else {
+            let (_, rows) = self.fetch(namespace, keys).await?;
+            let values = keys.iter().map(|key| find(&rows, key).map(|(_, value, _)| value));
+
+            return keys.iter()
+                .zip(values)
+                .map(|(key, value)| value.map(|value| self.reveal(namespace, key, value)).transpose())
+                .collect();
+        };
+
+        self.sync_versions(cache).await?;
+
+        let mut values: Vec<Option<Option<String>>> = keys
+            .iter()
+            .map(|key| cache.get(namespace, key))
+            .collect();
+
+        let missing: Vec<&str> = keys
+            .iter()
+            .zip(&values)
+            .filter(|(_, value)| value.is_none())
+            .map(|(key, _)| *key)
+            .collect();
+
+        if !missing.is_empty() {
+            let epoch           = cache.epoch();
+            let (version, rows) = self.fetch(namespace, &missing).await?;
+
+            for (key, value) in keys.iter().zip(values.iter_mut()) {
+                if value.is_some() {
+                    continue;
+                }
+
+                let row = find(&rows, key);
+                let ttl = row.as_ref()
+                    .and_then(|(_, _, micros)| *micros)
+                    .map(|micros| Duration::from_micros(micros.max(0) as u64));
+
+                let fetched = row.map(|(_, value, _)| value);
+                cache.put(namespace, key, fetched.clone(), ttl, epoch, version);
+                *value = Some(fetched);
+            }
+        }
+
+        // Envelopes are cached, so plaintext is never kept in memory.
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| value.flatten().map(|value| self.reveal(namespace, key, value)).transpose())
+            .collect()
+    }
+
+    /// Set value of key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given value.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn set(&self, namespace: &Namespace, key: &str, value: &str) -> Result<(), DbError> {
+        self.mset(namespace, &[(key, value)]).await
+    }
+
+    /// Set values of keys atomically.
+    ///
+    /// # Parameters
+    /// - `namespace` - given keys namespace.
+    /// - `entries`   - given keys & values.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn mset(&self, namespace: &Namespace, entries: &[(&str, &str)]) -> Result<(), DbError> {
+        self.upsert(namespace, entries, None).await
+    }
+
+    /// Set value of key expiring after given time.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given value.
+    /// - `ttl`       - given time to live.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn set_with_ttl(&self, namespace: &Namespace, key: &str, value: &str, ttl: Duration)
+        -> Result<(), DbError>
+    {
+        self.upsert(namespace, &[(key, value)], Some(ttl)).await
+    }
+
+    /// Insert or update keys, replacing their expiry.
+    ///
+    /// # Parameters
+    /// - `namespace` - given keys namespace.
+    /// - `entries`   - given keys & values.
+    /// - `ttl`       - given time to live (`None` for persistent keys).
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError` - otherwise.
+    async fn upsert(&self, namespace: &Namespace, entries: &[(&str, &str)], ttl: Option<Duration>)
+        -> Result<(), DbError>
+    {
+        namespace.validate()?;
+        entries.iter().try_for_each(|(key, _)| validate_key(key))?;
+        entries.iter().try_for_each(|(key, value)| self.check_write(namespace, key, value))?;
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let expires_at = match ttl {
+            Some(_) => "NOW(6) + INTERVAL ? MICROSECOND",
+            None    => "NULL",
+        };
+
+        let (columns, row) = match namespace.scope_column() {
+            Some(column) => (format!("{column}, setting_name, setting_value"), format!("(?, ?, ?, {expires_at})")),
+            None         => ("setting_name, setting_value".to_string(), format!("(?, ?, {expires_at})")),
+        };
+
+        let query = format!(
+            "INSERT INTO {} ({columns}, expires_at) VALUES {} AS new \
+             ON DUPLICATE KEY UPDATE setting_value = new.setting_value, expires_at = new.expires_at;",
+            namespace.table(),
+            vec![row; entries.len()].join(", ")
+        );
+
+        let mut args = MySqlArguments::default();
+
+        for (key, value) in entries {
+            namespace.add_scope(&mut args)?;
+            args.add(*key).map_err(sqlx::Error::Encode)?;
+            args.add(self.conceal(namespace, key, value)?).map_err(sqlx::Error::Encode)?;
+
+            if let Some(ttl) = ttl {
+                args.add(micros(ttl)).map_err(sqlx::Error::Encode)?;
+            }
+        }
+
+        sqlx::query_with(query.as_str(), args).execute(self.pool()?).await?;
+
+        for (key, _) in entries {
+            self.invalidate(namespace, key);
+        }
+
+        Ok(())
+    }
+
+    /// Delete key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - `true` if key was deleted, `false` if it does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn delete(&self, namespace: &Namespace, key: &str) -> Result<bool, DbError> {
+        namespace.validate()?;
+        validate_key(key)?;
+
+        let query = format!(
+            "DELETE FROM {} WHERE {} AND setting_name = ?;",
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+
+        let result = sqlx::query_with(query.as_str(), args).execute(self.pool()?).await?;
+        self.invalidate(namespace, key);
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Check whether key exists.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - `true` if key exists, `false` otherwise - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn exists(&self, namespace: &Namespace, key: &str) -> Result<bool, DbError> {
+        Ok(self.get(namespace, key).await?.is_some())
+    }
+
+    /// Scan keys with prefix in key order.
+    ///
+    /// # Parameters
+    /// - `namespace` - given keys namespace.
+    /// - `prefix`    - given key prefix (empty for all keys).
+    /// - `cursor`    - given cursor returned with previous page (`None` for the first page).
+    /// - `limit`     - given maximum number of keys in page.
+    ///
+    /// # Returns
+    /// - Page of keys - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn scan(&self, namespace: &Namespace, prefix: &str, cursor: Option<&str>, limit: u32)
+        -> Result<ScanPage, DbError>
+    {
+        namespace.validate()?;
+
+        let query = format!(
+            r#"
+            SELECT setting_name, setting_value FROM {}
+            WHERE {} AND setting_name LIKE ? AND setting_name > ?
+            ORDER BY setting_name
+            LIMIT ?;
+            "#,
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(format!("{}%", escape_like(prefix))).map_err(sqlx::Error::Encode)?;
+        args.add(cursor.unwrap_or_default()).map_err(sqlx::Error::Encode)?;
+        args.add(limit.max(1)).map_err(sqlx::Error::Encode)?;
+
+        let entries: Vec<(String, String)> = sqlx::query_as_with(query.as_str(), args)
+            .fetch_all(self.pool()?)
+            .await?;
+
+        let entries: Vec<(String, String)> = entries
+            .into_iter()
+            .map(|(key, value)| Ok((key.clone(), self.reveal(namespace, &key, value)?)))
+            .collect::<Result<_, DbError>>()?;
+
+        let cursor = match entries.last() {
+            Some((key, _)) if entries.len() == limit.max(1) as usize => Some(key.clone()),
+            _                                                       => None,
+        };
+
+        Ok(ScanPage { entries, cursor })
+    }
+
+    /// Set value of key if its current value equals expected one.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `expected`  - given expected value (`None` if key must not exist).
+    /// - `value`     - given new value.
+    ///
+    /// # Returns
+    /// - `true` if value was set, `false` if current value differs.
+    /// - `DbError` - otherwise.
+    pub async fn compare_and_set(&self, namespace: &Namespace, key: &str, expected: Option<&str>, value: &str)
+        -> Result<bool, DbError>
+    {
+        let Some(expected) = expected else {
+            return self.set_if_absent(namespace, key, value).await;
+        };
+
+        namespace.validate()?;
+        validate_key(key)?;
+        self.check_write(namespace, key, value)?;
+
+        let value = self.conceal(namespace, key, value)?;
+
+        // Envelopes of equal values differ, so stored envelope is compared instead.
+        let expected = if self.encrypts(key) {
+            let Some((_, stored, _)) = self.fetch(namespace, &[key]).await?.1.pop() else {
+                return Ok(false);
+            };
+
+            if self.reveal(namespace, key, stored.clone())? != expected {
+                return Ok(false);
+            }
+
+            stored
+        } else {
+            expected.to_string()
+        };
+
+        let query = format!(
+            "UPDATE {} SET setting_value = ? WHERE {} AND setting_name = ? AND setting_value = ?;",
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+        args.add(value).map_err(sqlx::Error::Encode)?;
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+        args.add(expected).map_err(sqlx::Error::Encode)?;
+
+        // Matched rows are counted, so setting the same value succeeds.
+        let result = sqlx::query_with(query.as_str(), args).execute(self.pool()?).await?;
+        self.invalidate(namespace, key);
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Set value of key if it does not exist.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `value`     - given value.
+    ///
+    /// # Returns
+    /// - `true` if value was set, `false` if key already exists.
+    /// - `DbError` - otherwise.
+    pub async fn set_if_absent(&self, namespace: &Namespace, key: &str, value: &str)
+        -> Result<bool, DbError>
+    {
+        namespace.validate()?;
+        validate_key(key)?;
+        self.check_write(namespace, key, value)?;
+
+        let value = self.conceal(namespace, key, value)?;
+
+        let pool     = self.pool()?;
+        let scope    = namespace.scope_column();
+        let selector = match scope {
+            Some(column) => format!("{column} = ? AND setting_name = ?"),
+            None         => "setting_name = ?".to_string(),
+        };
+
+        // Expired key does not exist for readers, but still occupies its row.
+        let query = format!(
+            "DELETE FROM {} WHERE {selector} AND NOT (setting_value IS NOT NULL AND {NOT_EXPIRED});",
+            namespace.table()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+        sqlx::query_with(query.as_str(), args).execute(pool).await?;
+
+        let query = match scope {
+            Some(column) => format!(
+                "INSERT INTO {} ({column}, setting_name, setting_value) VALUES (?, ?, ?);",
+                namespace.table()
+            ),
+            None => format!(
+                "INSERT INTO {} (setting_name, setting_value) VALUES (?, ?);",
+                namespace.table()
+            ),
+        };
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+        args.add(value).map_err(sqlx::Error::Encode)?;
+
+        // Dead row could have been deleted even if insert fails.
+        let result = sqlx::query_with(query.as_str(), args).execute(pool).await;
+        self.invalidate(namespace, key);
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(sqlx::Error::Database(err))
+                if err.try_downcast_ref::<MySqlDatabaseError>()
+                    .is_some_and(|err| err.number() == ER_DUP_ENTRY) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Add delta to integer value of key atomically.
+    ///
+    /// Missing key is treated as `0`.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `delta`     - given delta.
+    ///
+    /// # Returns
+    /// - New value - in case of success.
+    /// - `DbError::InvalidValue` - if current value is not an integer.
+    /// - `DbError` - otherwise.
+    pub async fn incr(&self, namespace: &Namespace, key: &str, delta: i64) -> Result<i64, DbError> {
+        namespace.validate()?;
+        validate_key(key)?;
+
+        if self.encrypts(key) {
+            return Err(DbError::InvalidValue(format!("{key:?} is encrypted and can not be incremented")));
+        }
+
+        if let (Some(registry), Some(_)) = (&self.registry, SettingScope::of(namespace)) {
+            let def = registry.resolve(namespace, key)?;
+
+            if def.kind != SettingType::Int {
+                return Err(DbError::InvalidValue(format!("{key:?} is not an integer setting")));
+            }
+        }
+
+        // Concurrent upserts of new key may deadlock, one of them is retried.
+        for _ in 1..INCR_ATTEMPTS {
+            match self.try_incr(namespace, key, delta).await {
+                Err(DbError::Sqlx(sqlx::Error::Database(err)))
+                    if err.try_downcast_ref::<MySqlDatabaseError>()
+                        .is_some_and(|err| err.number() == ER_LOCK_DEADLOCK) => continue,
+                result => return result,
+            }
+        }
+
+        self.try_incr(namespace, key, delta).await
+    }
+
+    /// Add delta to integer value of key in single transaction.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `delta`     - given delta.
+    ///
+    /// # Returns
+    /// - New value - in case of success.
+    /// - `DbError` - otherwise.
+    async fn try_incr(&self, namespace: &Namespace, key: &str, delta: i64) -> Result<i64, DbError> {
+        let (columns, row) = match namespace.scope_column() {
+            Some(column) => (format!("{column}, setting_name, setting_value"), "(?, ?, ?)"),
+            None         => ("setting_name, setting_value".to_string(), "(?, ?)"),
+        };
+
+        // Non-integer value is left unchanged & reported after update.
+        let query = format!(
+            r#"
+            INSERT INTO {} ({columns}) VALUES {row} AS new
+            ON DUPLICATE KEY UPDATE
+                setting_value = CASE
+                    WHEN NOT (setting_value IS NOT NULL AND {NOT_EXPIRED})
+                        THEN new.setting_value
+                    WHEN setting_value REGEXP '^-?[0-9]+$'
+                        THEN CAST(setting_value AS SIGNED) + ?
+                    ELSE setting_value
+                END,
+                expires_at = IF({NOT_EXPIRED}, expires_at, NULL);
+            "#,
+            namespace.table()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+        args.add(delta.to_string()).map_err(sqlx::Error::Encode)?;
+        args.add(delta).map_err(sqlx::Error::Encode)?;
+
+        let mut transaction = self.pool()?.begin().await?;
+        sqlx::query_with(query.as_str(), args).execute(&mut *transaction).await?;
+
+        let query = format!(
+            "SELECT setting_value FROM {} WHERE {} AND setting_name = ?;",
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+
+        let value: String = sqlx::query_scalar_with(query.as_str(), args)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        let Ok(value) = value.parse::<i64>() else {
+            return Err(DbError::InvalidValue(format!("{key:?} is not an integer: {value:?}")));
+        };
+
+        transaction.commit().await?;
+        self.invalidate(namespace, key);
+
+        Ok(value)
+    }
+
+    /// Subtract delta from integer value of key atomically.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `delta`     - given delta.
+    ///
+    /// # Returns
+    /// - New value - in case of success.
+    /// - `DbError::InvalidValue` - if current value is not an integer.
+    /// - `DbError` - otherwise.
+    pub async fn decr(&self, namespace: &Namespace, key: &str, delta: i64) -> Result<i64, DbError> {
+        let delta = delta
+            .checked_neg()
+            .ok_or_else(|| DbError::InvalidValue(format!("delta {delta} is out of range")))?;
+
+        self.incr(namespace, key, delta).await
+    }
+
+    /// Get typed setting value, falling back to its default.
+    ///
+    /// # Parameters
+    /// - `namespace` - given settings namespace.
+    /// - `name`      - given setting name.
+    ///
+    /// # Returns
+    /// - Setting value - in case of success.
+    /// - `DbError::UnknownSetting` - if setting is not declared in registry.
+    /// - `DbError` - otherwise.
+    pub async fn get_setting<T: DeserializeOwned>(&self, namespace: &Namespace, name: &str)
+        -> Result<T, DbError>
+    {
+        let registry = self.registry.as_ref().ok_or_else(|| DbError::UnknownSetting(name.to_string()))?;
+        let def      = registry.resolve(namespace, name)?;
+
+        let value = match self.get(namespace, name).await? {
+            Some(raw) => def.decode(&raw)?,
+            None      => def.default.clone(),
+        };
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Set typed setting value.
+    ///
+    /// # Parameters
+    /// - `namespace` - given settings namespace.
+    /// - `name`      - given setting name.
+    /// - `value`     - given setting value.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError::UnknownSetting` - if setting is not declared in registry.
+    /// - `DbError::InvalidValue` - if value does not match setting type.
+    /// - `DbError` - otherwise.
+    pub async fn set_setting<T: Serialize>(&self, namespace: &Namespace, name: &str, value: &T)
+        -> Result<(), DbError>
+    {
+        let registry = self.registry.as_ref().ok_or_else(|| DbError::UnknownSetting(name.to_string()))?;
+        let raw      = registry.resolve(namespace, name)?.encode(&serde_json::to_value(value)?)?;
+
+        self.set(namespace, name, &raw).await
+    }
+
+    /// Get effective settings of user in channel.
+    ///
+    /// User settings override channel settings, which override server
+    /// settings. With registry, defaults fill gaps & values are typed,
+    /// otherwise stored strings are merged as is.
+    ///
+    /// # Parameters
+    /// - `user_id`    - given user identifier.
+    /// - `channel_id` - given channel identifier.
+    ///
+    /// # Returns
+    /// - Effective settings by name with their provenance - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn effective_settings(&self, user_id: i64, channel_id: i64)
+        -> Result<EffectiveSettings, DbError>
+    {
+        let query = format!(
+            r#"
+            SELECT 'server', setting_name, setting_value FROM Server_Settings_KV
+            WHERE setting_value IS NOT NULL AND {NOT_EXPIRED}
+            UNION ALL
+            SELECT 'channel', setting_name, setting_value FROM Channel_Settings_KV
+            WHERE channel_id = ? AND setting_value IS NOT NULL AND {NOT_EXPIRED}
+            UNION ALL
+            SELECT 'user', setting_name, setting_value FROM User_Settings_KV
+            WHERE user_id = ? AND setting_value IS NOT NULL AND {NOT_EXPIRED};
+            "#
+        );
+
+        let rows: Vec<(String, String, String)> = sqlx::query_as(query.as_str())
+            .bind(channel_id)
+            .bind(user_id)
+            .fetch_all(self.pool()?)
+            .await?;
+
+        let stored: Vec<(SettingScope, String, String)> = rows
+            .into_iter()
+            .map(|(layer, name, value)| {
+                let (scope, namespace) = match layer.as_str() {
+                    "server"  => (SettingScope::Server, Namespace::Server),
+                    "channel" => (SettingScope::Channel, Namespace::Channel(channel_id)),
+                    _         => (SettingScope::User, Namespace::User(user_id)),
+                };
+
+                let value = self.reveal(&namespace, &name, value)?;
+                Ok((scope, name, value))
+            })
+            .collect::<Result<_, DbError>>()?;
+
+        if let Some(registry) = &self.registry {
+            return Ok(registry.merge(&stored));
+        }
+
+        let mut layers = stored;
+        layers.sort_by_key(|(scope, _, _)| *scope);
+
+        let settings = layers
+            .into_iter()
+            .map(|(scope, name, value)| {
+                (name, EffectiveSetting { value: Value::String(value), source: SettingSource::from(scope) })
+            })
+            .collect();
+
+        Ok(settings)
+    }
+
+    /// Export all live server, channel & user settings.
+    ///
+    /// Encrypted settings are exported decrypted, so they can be imported
+    /// into database with another keyring.
+    ///
+    /// # Returns
+    /// - Setting records ordered by scope, scope identifier & name - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn export_settings(&self) -> Result<Vec<SettingRecord>, DbError> {
+        let query = format!(
+            r#"
+            SELECT 'server', NULL, setting_name, setting_value, expires_at FROM Server_Settings_KV
+            WHERE setting_value IS NOT NULL AND {NOT_EXPIRED}
+            UNION ALL
+            SELECT 'channel', channel_id, setting_name, setting_value, expires_at FROM Channel_Settings_KV
+            WHERE setting_value IS NOT NULL AND {NOT_EXPIRED}
+            UNION ALL
+            SELECT 'user', user_id, setting_name, setting_value, expires_at FROM User_Settings_KV
+            WHERE setting_value IS NOT NULL AND {NOT_EXPIRED};
+            "#
+        );
+
+        // Layer, scope identifier, name, value & expiry.
+        type Row = (String, Option<i64>, String, String, Option<NaiveDateTime>);
+
+        let rows: Vec<Row> = sqlx::query_as(query.as_str())
+            .fetch_all(self.pool()?)
+            .await?;
+
+        let mut records: Vec<SettingRecord> = rows
+            .into_iter()
+            .map(|(layer, scope_id, name, value, expires_at)| {
+                let scope = match layer.as_str() {
+                    "server"  => SettingScope::Server,
+                    "channel" => SettingScope::Channel,
+                    _         => SettingScope::User,
+                };
+
+                let mut record = SettingRecord { scope, scope_id, name, value, expires_at };
+                record.value   = self.reveal(&record.namespace()?, &record.name, record.value.clone())?;
+
+                Ok(record)
+            })
+            .collect::<Result<_, DbError>>()?;
+
+        records.sort_by(|a, b| (a.scope, a.scope_id, &a.name).cmp(&(b.scope, b.scope_id, &b.name)));
+        Ok(records)
+    }
+
+    /// Import server, channel & user settings in single transaction.
+    ///
+    /// Setting is in conflict if stored value or expiry differs from
+    /// imported one. Nothing is written on dry run or on any error.
+    ///
+    /// # Parameters
+    /// - `records` - given setting records.
+    /// - `policy`  - given conflict policy.
+    /// - `dry_run` - given flag to only compute changes.
+    ///
+    /// # Returns
+    /// - Import report - in case of success.
+    /// - `DbError::ImportConflict` - if setting is in conflict under `ConflictPolicy::Fail`.
+    /// - `DbError` - otherwise.
+    pub async fn import_settings(&self, records: &[SettingRecord], policy: ConflictPolicy, dry_run: bool)
+        -> Result<ImportReport, DbError>
+    {
+        let mut namespaces = Vec::with_capacity(records.len());
+        let mut seen       = HashSet::with_capacity(records.len());
+
+        for record in records {
+            let namespace = record.namespace()?;
+            validate_key(&record.name)?;
+            self.check_write(&namespace, &record.name, &record.value)?;
+
+            if !seen.insert((record.scope, record.scope_id, record.name.as_str())) {
+                return Err(DbError::InvalidValue(format!("duplicate setting {:?}", record.name)));
+            }
+
+            namespaces.push(namespace);
+        }
+
+        let mut transaction = self.pool()?.begin().await?;
+        let mut report      = ImportReport::default();
+
+        for (record, namespace) in records.iter().zip(&namespaces) {
+            let query = format!(
+                "SELECT setting_value, expires_at FROM {} WHERE {} AND setting_name = ? FOR UPDATE;",
+                namespace.table(),
+                namespace.condition()
+            );
+
+            let mut args = MySqlArguments::default();
+            namespace.add_scope(&mut args)?;
+            args.add(&record.name).map_err(sqlx::Error::Encode)?;
+
+            let stored: Option<(String, Option<NaiveDateTime>)> = sqlx::query_as_with(query.as_str(), args)
+                .fetch_optional(&mut *transaction)
+                .await?;
+
+            let stored = match stored {
+                Some((value, expires_at)) => Some((self.reveal(namespace, &record.name, value)?, expires_at)),
+                None                      => None,
+            };
+
+            let action = match &stored {
+                None => ImportAction::Insert,
+                Some((value, expires_at)) if *value == record.value && *expires_at == record.expires_at => {
+                    ImportAction::Unchanged
+                }
+                Some((value, _)) => match policy {
+                    ConflictPolicy::Skip      => ImportAction::Skip,
+                    ConflictPolicy::Overwrite => ImportAction::Update,
+                    ConflictPolicy::Fail      => return Err(DbError::ImportConflict(format!(
+                        "{} setting {:?}: stored {value:?}, imported {:?}",
+                        record.scope.as_str(), record.name, record.value
+                    ))),
+                },
+            };
+
+            report.changes.push(SettingChange {
+                record:   record.clone(),
+                previous: stored.map(|(value, _)| value),
+                action,
+            });
+        }
+
+        if dry_run {
+            transaction.rollback().await?;
+            return Ok(report);
+        }
+
+        for (change, namespace) in report.changes.iter().zip(&namespaces) {
+            if !matches!(change.action, ImportAction::Insert | ImportAction::Update) {
+                continue;
+            }
+
+            let (columns, row) = match namespace.scope_column() {
+                Some(column) => (format!("{column}, setting_name, setting_value, expires_at"), "(?, ?, ?, ?)"),
+                None         => ("setting_name, setting_value, expires_at".to_string(), "(?, ?, ?)"),
+            };
+
+            // Expired key may still occupy its row.
+            let query = format!(
+                "INSERT INTO {} ({columns}) VALUES {row} AS new \
+                 ON DUPLICATE KEY UPDATE setting_value = new.setting_value, expires_at = new.expires_at;",
+                namespace.table()
+            );
+
+            let mut args = MySqlArguments::default();
+            namespace.add_scope(&mut args)?;
+            args.add(&change.record.name).map_err(sqlx::Error::Encode)?;
+            args.add(self.conceal(namespace, &change.record.name, &change.record.value)?)
+                .map_err(sqlx::Error::Encode)?;
+            args.add(change.record.expires_at).map_err(sqlx::Error::Encode)?;
+
+            sqlx::query_with(query.as_str(), args).execute(&mut *transaction).await?;
+        }
+
+        transaction.commit().await?;
+
+        for (change, namespace) in report.changes.iter().zip(&namespaces) {
+            self.invalidate(namespace, &change.record.name);
+        }
+
+        report.applied = true;
+        Ok(report)
+    }
+
+    /// Re-encrypt values of encrypted keys with active master key.
+    ///
+    /// Values sealed with previous master keys or written as plaintext are
+    /// updated in batches, each in its own transaction. Value changed
+    /// concurrently is left to its writer.
+    ///
+    /// # Parameters
+    /// - `batch_size` - given maximum number of values updated at once.
+    ///
+    /// # Returns
+    /// - Number of re-encrypted values - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn rotate_encryption(&self, batch_size: u32) -> Result<u64, DbError> {
+        let Some(encryption) = &self.encryption else {
+            return Ok(0);
+        };
+
+        let names: Vec<&str> = encryption.names().collect();
+
+        if names.is_empty() {
+            return Ok(0);
+        }
+
+        let pool    = self.pool()?;
+        let active  = format!("{ENVELOPE_PREFIX}:{}:%", escape_like(encryption.cipher().active_key()));
+        let layouts = [
+            Namespace::Server,
+            Namespace::Channel(0),
+            Namespace::User(0),
+            Namespace::Custom(String::new()),
+        ];
+
+        let mut rotated = 0;
+
+        for layout in layouts {
+            let scope = layout.scope_column()
+                .map(|column| format!("CAST({column} AS CHAR)"))
+                .unwrap_or_else(|| "''".to_string());
+
+            let query = format!(
+                r#"
+                SELECT {scope}, setting_name, setting_value FROM {}
+                WHERE setting_name IN ({}) AND setting_value NOT LIKE ?
+                LIMIT ?;
+                "#,
+                layout.table(),
+                vec!["?"; names.len()].join(", ")
+            );
+
+            loop {
+                let mut args = MySqlArguments::default();
+
+                for name in &names {
+                    args.add(*name).map_err(sqlx::Error::Encode)?;
+                }
+
+                args.add(&active).map_err(sqlx::Error::Encode)?;
+                args.add(batch_size.max(1)).map_err(sqlx::Error::Encode)?;
+
+                let rows: Vec<(String, String, String)> = sqlx::query_as_with(query.as_str(), args)
+                    .fetch_all(pool)
+                    .await?;
+
+                let mut transaction = pool.begin().await?;
+                let mut updated     = Vec::with_capacity(rows.len());
+
+                for (scope, key, stored) in &rows {
+                    let namespace = match &layout {
+                        Namespace::Server     => Namespace::Server,
+                        Namespace::Channel(_) => Namespace::Channel(scope.parse().unwrap_or_default()),
+                        Namespace::User(_)    => Namespace::User(scope.parse().unwrap_or_default()),
+                        Namespace::Custom(_)  => Namespace::Custom(scope.clone()),
+                    };
+
+                    let value = self.reveal(&namespace, key, stored.clone())?;
+
+                    let query = match namespace.scope_column() {
+                        Some(column) => format!(
+                            "UPDATE {} SET setting_value = ? WHERE {column} = ? AND setting_name = ? AND setting_value = ?;",
+                            namespace.table()
+                        ),
+                        None => format!(
+                            "UPDATE {} SET setting_value = ? WHERE setting_name = ? AND setting_value = ?;",
+                            namespace.table()
+                        ),
+                    };
+
+                    let mut args = MySqlArguments::default();
+                    args.add(self.conceal(&namespace, key, &value)?).map_err(sqlx::Error::Encode)?;
+                    namespace.add_scope(&mut args)?;
+                    args.add(key).map_err(sqlx::Error::Encode)?;
+                    args.add(stored).map_err(sqlx::Error::Encode)?;
+
+                    let result = sqlx::query_with(query.as_str(), args).execute(&mut *transaction).await?;
+
+                    if result.rows_affected() == 1 {
+                        updated.push((namespace, key));
+                    }
+                }
+
+                transaction.commit().await?;
+
+                for (namespace, key) in &updated {
+                    self.invalidate(namespace, key);
+                }
+
+                rotated += updated.len() as u64;
+
+                // Batch of values changed concurrently is not fetched again.
+                if rows.len() < batch_size.max(1) as usize || updated.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        Ok(rotated)
+    }
+
+    /// Set expiry of existing key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `ttl`       - given time to live.
+    ///
+    /// # Returns
+    /// - `true` if expiry was set, `false` if key does not exist.
+    /// - `DbError` - otherwise.
+    pub async fn expire(&self, namespace: &Namespace, key: &str, ttl: Duration) -> Result<bool, DbError> {
+        self.set_expiry(namespace, key, Some(ttl)).await
+    }
+
+    /// Remove expiry of existing key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - `true` if expiry was removed, `false` if key does not exist or has no expiry.
+    /// - `DbError` - otherwise.
+    pub async fn persist(&self, namespace: &Namespace, key: &str) -> Result<bool, DbError> {
+        self.set_expiry(namespace, key, None).await
+    }
+
+    /// Update expiry of existing key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    /// - `ttl`       - given time to live (`None` to remove expiry).
+    ///
+    /// # Returns
+    /// - `true` if expiry was changed, `false` otherwise.
+    /// - `DbError` - otherwise.
+    async fn set_expiry(&self, namespace: &Namespace, key: &str, ttl: Option<Duration>)
+        -> Result<bool, DbError>
+    {
+        namespace.validate()?;
+        validate_key(key)?;
+
+        let (expires_at, condition) = match ttl {
+            Some(_) => ("NOW(6) + INTERVAL ? MICROSECOND", ""),
+            None    => ("NULL", " AND expires_at IS NOT NULL"),
+        };
+
+        let query = format!(
+            "UPDATE {} SET expires_at = {expires_at} WHERE {} AND setting_name = ?{condition};",
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+
+        if let Some(ttl) = ttl {
+            args.add(micros(ttl)).map_err(sqlx::Error::Encode)?;
+        }
+
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+
+        let result = sqlx::query_with(query.as_str(), args).execute(self.pool()?).await?;
+        self.invalidate(namespace, key);
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Get remaining time to live of key.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key namespace.
+    /// - `key`       - given key.
+    ///
+    /// # Returns
+    /// - Time to live or `None` if key does not exist - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn ttl(&self, namespace: &Namespace, key: &str) -> Result<Option<Ttl>, DbError> {
+        namespace.validate()?;
+        validate_key(key)?;
+
+        let query = format!(
+            "SELECT TIMESTAMPDIFF(MICROSECOND, NOW(6), expires_at) FROM {} \
+             WHERE {} AND setting_name = ?;",
+            namespace.table(),
+            namespace.condition()
+        );
+
+        let mut args = MySqlArguments::default();
+        namespace.add_scope(&mut args)?;
+        args.add(key).map_err(sqlx::Error::Encode)?;
+
+        let row: Option<(Option<i64>,)> = sqlx::query_as_with(query.as_str(), args)
+            .fetch_optional(self.pool()?)
+            .await?;
+
+        let ttl = row.map(|(remaining,)| match remaining {
+            Some(micros) => Ttl::Expires(Duration::from_micros(micros.max(0) as u64)),
+            None         => Ttl::Persistent,
+        });
+
+        Ok(ttl)
+    }
+
+    /// Delete expired keys of all namespaces.
+    ///
+    /// # Parameters
+    /// - `batch_size` - given maximum number of keys deleted by single statement.
+    ///
+    /// # Returns
+    /// - Number of deleted keys - in case of success.
+    /// - `DbError` - otherwise.
+    pub async fn reap_expired(&self, batch_size: u32) -> Result<u64, DbError> {
+        reap_expired(self.pool()?, batch_size).await
+    }
+
+    /// Spawn background task deleting expired keys periodically.
+    ///
+    /// Abort returned handle to stop the task.
+    ///
+    /// # Parameters
+    /// - `interval`   - given delay between runs.
+    /// - `batch_size` - given maximum number of keys deleted by single statement.
+    ///
+    /// # Returns
+    /// - Reaper task handle - in case of success.
+    /// - `DbError` - if manager is not connected.
+    pub fn spawn_reaper(&self, interval: Duration, batch_size: u32) -> Result<JoinHandle<()>, DbError> {
+        let pool = self.pool()?.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                if let Err(err) = reap_expired(&pool, batch_size).await {
+                    eprintln!("Error: failed to delete expired keys: {err}");
+                }
+            }
+        });
+
+        Ok(handle)
+    }
+}
+
+/// Delete expired keys of all namespaces.
+///
+/// Keys are deleted in batches, so row locks are held shortly.
+///
+/// # Parameters
+/// - `pool`       - given MySQL connection pool.
+/// - `batch_size` - given maximum number of keys deleted by single statement.
+///
+/// # Returns
+/// - Number of deleted keys - in case of success.
+/// - `DbError` - otherwise.
+async fn reap_expired(pool: &MySqlPool, batch_size: u32) -> Result<u64, DbError> {
+    let batch_size = batch_size.max(1);
+    let mut total  = 0;
+
+    for table in KV_TABLES {
+        let query = format!("DELETE FROM {table} WHERE expires_at <= NOW(6) LIMIT ?;");
+
+        loop {
+            let result = sqlx::query(query.as_str()).bind(batch_size).execute(pool).await?;
+            total += result.rows_affected();
+
+            if result.rows_affected() < batch_size as u64 {
+                break;
+            }
+        }
+    }
+
+    Ok(total)
 }
@@ -24,6 +24,16 @@ pub mod global;
 pub mod area;
 pub mod docdb;
 pub mod kvdb;
+pub mod error;
+pub mod collection;
+pub mod schema;
+pub mod aggregate;
+pub mod search;
+pub mod changefeed;
+pub mod registry;
+pub mod cache;
+pub mod transfer;
+pub mod crypto;
 
 /// MySQL connection config struct.
 #[derive(Debug, Default, Clone)]
@@ -99,6 +109,7 @@ impl ConnectionConfig {
 }
 
 /// CRUD (Create, Read, Update, Delete) operations trait.
+#[allow(async_fn_in_trait)]
 pub trait CrudOps {
     async fn create(pool: &MySqlPool) -> Result<(), sqlx::Error>;
     async fn update(&self, pool: &MySqlPool) -> Result<(), sqlx::Error>;
@@ -187,4 +198,111 @@ pub async fn create_db(pool: &MySqlPool, name: &String) -> Result<(), sqlx::Erro
     sqlx::query(query.as_str()).execute(pool).await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Add column to existing table if it does not exist.
+///
+/// # Parameters
+/// - `pool`       - given MySQL connection pool.
+/// - `table`      - given table name.
+/// - `column`     - given column name.
+/// - `definition` - given column definition (e.g. `BIGINT NOT NULL DEFAULT 1`).
+///
+/// # Returns
+/// - `true` if column was added, `false` if it already exists.
+/// - `sqlx::Error` - otherwise.
+pub async fn add_column(pool: &MySqlPool, table: &str, column: &str, definition: &str)
+    -> Result<bool, sqlx::Error>
+{
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?;
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query)
+        .bind(table)
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+
+    if count > 0 {
+        return Ok(false);
+    }
+
+    let query = format!("ALTER TABLE `{table}` ADD COLUMN `{column}` {definition};");
+    sqlx::query(query.as_str()).execute(pool).await?;
+
+    Ok(true)
+}
+
+/// Add index to existing table if it does not exist.
+///
+/// # Parameters
+/// - `pool`    - given MySQL connection pool.
+/// - `table`   - given table name.
+/// - `index`   - given index name.
+/// - `columns` - given indexed columns (e.g. `user_id, setting_name`).
+///
+/// # Returns
+/// - `true` if index was added, `false` if it already exists.
+/// - `sqlx::Error` - otherwise.
+pub async fn add_index(pool: &MySqlPool, table: &str, index: &str, columns: &str)
+    -> Result<bool, sqlx::Error>
+{
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.STATISTICS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND INDEX_NAME = ?;
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query)
+        .bind(table)
+        .bind(index)
+        .fetch_one(pool)
+        .await?;
+
+    if count > 0 {
+        return Ok(false);
+    }
+
+    let query = format!("ALTER TABLE `{table}` ADD INDEX `{index}` ({columns});");
+    sqlx::query(query.as_str()).execute(pool).await?;
+
+    Ok(true)
+}
+
+/// Drop index of existing table if it exists.
+///
+/// # Parameters
+/// - `pool`  - given MySQL connection pool.
+/// - `table` - given table name.
+/// - `index` - given index name.
+///
+/// # Returns
+/// - `true` if index was dropped, `false` if it does not exist.
+/// - `sqlx::Error` - otherwise.
+pub async fn drop_index(pool: &MySqlPool, table: &str, index: &str)
+    -> Result<bool, sqlx::Error>
+{
+    let query =
+        r#"
+        SELECT COUNT(*) FROM information_schema.STATISTICS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND INDEX_NAME = ?;
+        "#;
+
+    let (count,): (i64,) = sqlx::query_as(query)
+        .bind(table)
+        .bind(index)
+        .fetch_one(pool)
+        .await?;
+
+    if count == 0 {
+        return Ok(false);
+    }
+
+    let query = format!("ALTER TABLE `{table}` DROP INDEX `{index}`;");
+    sqlx::query(query.as_str()).execute(pool).await?;
+
+    Ok(true)
+}
@@ -0,0 +1,340 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Typed settings registry related declarations.
+
+use crate::db::{error::DbError, kvdb::{Namespace, MAX_VALUE_LENGTH}};
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Setting value type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettingType {
+    /// `true` or `false`.
+    Bool,
+    /// 64-bit signed integer.
+    Int,
+    /// One of allowed strings.
+    Enum(Vec<String>),
+    /// Arbitrary string.
+    String,
+    /// Arbitrary JSON value.
+    Json,
+}
+
+/// Settings layer where setting may be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingScope {
+    Server,
+    Channel,
+    User,
+}
+
+impl SettingScope {
+    /// Get scope of settings namespace.
+    ///
+    /// # Parameters
+    /// - `namespace` - given key-value namespace.
+    ///
+    /// # Returns
+    /// - Scope or `None` for custom namespaces.
+    pub fn of(namespace: &Namespace) -> Option<Self> {
+        match namespace {
+            Namespace::Server     => Some(SettingScope::Server),
+            Namespace::Channel(_) => Some(SettingScope::Channel),
+            Namespace::User(_)    => Some(SettingScope::User),
+            Namespace::Custom(_)  => None,
+        }
+    }
+
+    /// Get scope name.
+    ///
+    /// # Returns
+    /// - Lowercase scope name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingScope::Server  => "server",
+            SettingScope::Channel => "channel",
+            SettingScope::User    => "user",
+        }
+    }
+}
+
+/// Layer which effective setting value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingSource {
+    /// Default value of registry.
+    Default,
+    /// Server setting.
+    Server,
+    /// Channel setting.
+    Channel,
+    /// User setting.
+    User,
+}
+
+impl From<SettingScope> for SettingSource {
+    fn from(scope: SettingScope) -> Self {
+        match scope {
+            SettingScope::Server  => SettingSource::Server,
+            SettingScope::Channel => SettingSource::Channel,
+            SettingScope::User    => SettingSource::User,
+        }
+    }
+}
+
+/// Effective setting value with its provenance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectiveSetting {
+    /// Setting value.
+    pub value: Value,
+    /// Layer which value came from.
+    pub source: SettingSource,
+}
+
+/// Effective settings by name.
+pub type EffectiveSettings = BTreeMap<String, EffectiveSetting>;
+
+/// Setting declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingDef {
+    /// Setting name.
+    pub name: String,
+    /// Setting value type.
+    pub kind: SettingType,
+    /// Value used when setting is not stored.
+    pub default: Value,
+    /// Layers where setting may be stored.
+    pub scopes: Vec<SettingScope>,
+}
+
+impl SettingDef {
+    /// Construct new SettingDef object allowed in all scopes.
+    ///
+    /// # Parameters
+    /// - `name`    - given setting name.
+    /// - `kind`    - given setting value type.
+    /// - `default` - given default value.
+    ///
+    /// # Returns
+    /// - New `SettingDef` object.
+    pub fn new(name: &str, kind: SettingType, default: impl Into<Value>) -> Self {
+        Self {
+            name:    name.to_string(),
+            kind,
+            default: default.into(),
+            scopes:  vec![SettingScope::Server, SettingScope::Channel, SettingScope::User],
+        }
+    }
+
+    /// Restrict layers where setting may be stored.
+    pub fn scopes(mut self, scopes: &[SettingScope]) -> Self {
+        self.scopes = scopes.to_vec();
+        self
+    }
+
+    /// Encode value into stored string.
+    ///
+    /// # Parameters
+    /// - `value` - given value.
+    ///
+    /// # Returns
+    /// - Stored string - in case of success.
+    /// - `DbError::InvalidValue` - if value does not match setting type.
+    pub fn encode(&self, value: &Value) -> Result<String, DbError> {
+        let raw = match (&self.kind, value) {
+            (SettingType::Bool, Value::Bool(value))  => value.to_string(),
+            (SettingType::Int, Value::Number(value)) if value.is_i64() => value.to_string(),
+            (SettingType::Enum(allowed), Value::String(value)) if allowed.contains(value) => value.clone(),
+            (SettingType::String, Value::String(value)) => value.clone(),
+            (SettingType::Json, value) => value.to_string(),
+            (kind, value) => {
+                return Err(DbError::InvalidValue(format!(
+                    "{:?} expects {kind:?}, got {value}", self.name
+                )));
+            }
+        };
+
+        if raw.chars().count() > MAX_VALUE_LENGTH {
+            return Err(DbError::InvalidValue(format!(
+                "{:?} is longer than {MAX_VALUE_LENGTH} characters", self.name
+            )));
+        }
+
+        Ok(raw)
+    }
+
+    /// Decode stored string.
+    ///
+    /// # Parameters
+    /// - `raw` - given stored string.
+    ///
+    /// # Returns
+    /// - Value - in case of success.
+    /// - `DbError::InvalidValue` - if string does not match setting type.
+    pub fn decode(&self, raw: &str) -> Result<Value, DbError> {
+        let value = match &self.kind {
+            SettingType::Bool => raw.parse::<bool>().ok().map(Value::from),
+            SettingType::Int  => raw.parse::<i64>().ok().map(Value::from),
+            SettingType::Enum(allowed) => {
+                allowed.iter().any(|value| value == raw).then(|| Value::from(raw))
+            }
+            SettingType::String => Some(Value::from(raw)),
+            SettingType::Json   => serde_json::from_str(raw).ok(),
+        };
+
+        value.ok_or_else(|| DbError::InvalidValue(format!(
+            "stored {:?} is not {:?}: {raw:?}", self.name, self.kind
+        )))
+    }
+}
+
+/// Registry of known settings.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsRegistry {
+    /// Setting declarations by name.
+    defs: HashMap<String, SettingDef>,
+}
+
+impl SettingsRegistry {
+    /// Construct new empty SettingsRegistry object.
+    ///
+    /// # Returns
+    /// - New `SettingsRegistry` object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare setting, replacing previous declaration with the same name.
+    ///
+    /// # Parameters
+    /// - `def` - given setting declaration.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of success.
+    /// - `DbError::InvalidValue` - if default value does not match setting type.
+    pub fn register(&mut self, def: SettingDef) -> Result<(), DbError> {
+        def.encode(&def.default)?;
+        self.defs.insert(def.name.clone(), def);
+
+        Ok(())
+    }
+
+    /// Get setting declaration.
+    ///
+    /// # Parameters
+    /// - `name` - given setting name.
+    ///
+    /// # Returns
+    /// - Setting declaration or `None` if setting is unknown.
+    pub fn get(&self, name: &str) -> Option<&SettingDef> {
+        self.defs.get(name)
+    }
+
+    /// Get all setting declarations.
+    ///
+    /// # Returns
+    /// - Iterator over setting declarations.
+    pub fn iter(&self) -> impl Iterator<Item = &SettingDef> {
+        self.defs.values()
+    }
+
+    /// Get declaration of setting stored in namespace.
+    ///
+    /// # Parameters
+    /// - `namespace` - given settings namespace.
+    /// - `name`      - given setting name.
+    ///
+    /// # Returns
+    /// - Setting declaration - in case of success.
+    /// - `DbError::UnknownSetting` - if setting is not declared.
+    /// - `DbError::InvalidScope` - if setting can not be stored in namespace.
+    pub fn resolve(&self, namespace: &Namespace, name: &str) -> Result<&SettingDef, DbError> {
+        let def = self.get(name).ok_or_else(|| DbError::UnknownSetting(name.to_string()))?;
+
+        match SettingScope::of(namespace) {
+            Some(scope) if def.scopes.contains(&scope) => Ok(def),
+            Some(scope) => Err(DbError::InvalidScope(format!(
+                "{name:?} can not be stored in {} scope", scope.as_str()
+            ))),
+            None => Err(DbError::InvalidScope(format!(
+                "{name:?} can not be stored in {namespace:?}"
+            ))),
+        }
+    }
+
+    /// Check stored string of setting.
+    ///
+    /// # Parameters
+    /// - `namespace` - given settings namespace.
+    /// - `name`      - given setting name.
+    /// - `raw`       - given stored string.
+    ///
+    /// # Returns
+    /// - `Ok` - in case of valid setting.
+    /// - `DbError` - otherwise.
+    pub fn check(&self, namespace: &Namespace, name: &str, raw: &str) -> Result<(), DbError> {
+        let def = self.resolve(namespace, name)?;
+        let _   = def.decode(raw)?;
+
+        Ok(())
+    }
+
+    /// Merge stored settings layers.
+    ///
+    /// More specific layer overrides less specific one (user over channel
+    /// over server) & defaults fill gaps. Stored values of unknown settings,
+    /// settings not allowed in their layer or values not matching setting
+    /// type are skipped, so the next layer applies.
+    ///
+    /// # Parameters
+    /// - `stored` - given stored settings as (layer, name, stored string).
+    ///
+    /// # Returns
+    /// - Effective settings of all declared settings.
+    pub fn merge(&self, stored: &[(SettingScope, String, String)]) -> EffectiveSettings {
+        let mut settings: EffectiveSettings = self
+            .iter()
+            .map(|def| {
+                let setting = EffectiveSetting { value: def.default.clone(), source: SettingSource::Default };
+                (def.name.clone(), setting)
+            })
+            .collect();
+
+        let mut layers: Vec<&(SettingScope, String, String)> = stored.iter().collect();
+        layers.sort_by_key(|(scope, _, _)| *scope);
+
+        for (scope, name, raw) in layers {
+            let Some(def) = self.get(name) else {
+                continue;
+            };
+
+            if !def.scopes.contains(scope) {
+                continue;
+            }
+
+            if let Ok(value) = def.decode(raw) {
+                settings.insert(name.clone(), EffectiveSetting { value, source: (*scope).into() });
+            }
+        }
+
+        settings
+    }
+}
+
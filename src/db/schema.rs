@@ -0,0 +1,131 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! JSON Schema document validation related declarations.
+
+use std::{error::Error, fmt, sync::Arc};
+use serde::{Deserialize, Serialize};
+use crate::db::error::DbError;
+use serde_json::Value;
+
+/// Single JSON Schema violation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// JSON Pointer of failing document value (e.g. `/bio`).
+    pub path: String,
+    /// JSON Pointer of failing schema keyword (e.g. `/properties/bio/maxLength`).
+    pub schema_path: String,
+    /// Human-readable error message.
+    pub message: String,
+}
+
+/// Document does not match collection JSON Schema.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationError {
+    /// All schema violations of the document.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationError {
+    /// Get failing document paths.
+    ///
+    /// # Returns
+    /// - Vector of JSON Pointers of failing document values.
+    pub fn paths(&self) -> Vec<&str> {
+        self.issues.iter().map(|issue| issue.path.as_str()).collect()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "document does not match schema:")?;
+
+        for issue in &self.issues {
+            write!(f, " {:?}: {};", issue.path, issue.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Compiled JSON Schema of document collection.
+///
+/// String formats (e.g. `"format": "uri"`) are validated as well.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// Schema JSON representation.
+    json: Value,
+    /// Compiled schema validator.
+    validator: Arc<jsonschema::Validator>,
+}
+
+impl Schema {
+    /// Construct new Schema object.
+    ///
+    /// # Parameters
+    /// - `json` - given JSON Schema.
+    ///
+    /// # Returns
+    /// - New `Schema` object - in case of success.
+    /// - `DbError::InvalidSchema` - otherwise.
+    pub fn new(json: Value) -> Result<Self, DbError> {
+        let validator = jsonschema::options()
+            .should_validate_formats(true)
+            .build(&json)
+            .map_err(|err| DbError::InvalidSchema(err.to_string()))?;
+
+        Ok(Self {
+            json,
+            validator: Arc::new(validator),
+        })
+    }
+
+    /// Get schema JSON representation.
+    ///
+    /// # Returns
+    /// - JSON Schema.
+    #[inline(always)]
+    pub fn json(&self) -> &Value {
+        &self.json
+    }
+
+    /// Validate document.
+    ///
+    /// # Parameters
+    /// - `doc` - given document.
+    ///
+    /// # Returns
+    /// - `Ok` - if document matches schema.
+    /// - `DbError::Validation` - otherwise.
+    pub fn validate(&self, doc: &Value) -> Result<(), DbError> {
+        let issues: Vec<ValidationIssue> = self.validator
+            .iter_errors(doc)
+            .map(|err| ValidationIssue {
+                path:        err.instance_path().to_string(),
+                schema_path: err.schema_path().to_string(),
+                message:     err.to_string(),
+            })
+            .collect();
+
+        if !issues.is_empty() {
+            return Err(DbError::Validation(ValidationError { issues }));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,532 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Full-text search over messages & user profiles related declarations.
+//!
+//! Search uses InnoDB `FULLTEXT` indexes, so words shorter than
+//! `innodb_ft_min_token_size` (3 by default) & stopwords are not indexed.
+
+use sqlx::{mysql::MySqlArguments, Arguments, MySqlPool};
+use serde::{Deserialize, Serialize};
+use crate::db::area::Area;
+use chrono::NaiveDateTime;
+
+/// Name of full-text index on message text.
+pub const MESSAGE_INDEX: &str = "ft_message_text";
+
+/// Name of full-text index on profile bio & location.
+pub const PROFILE_INDEX: &str = "ft_profile_bio_location";
+
+/// Default maximum number of search hits.
+pub const DEFAULT_LIMIT: u64 = 20;
+
+/// Number of words in highlighted snippet.
+const SNIPPET_WORDS: usize = 20;
+
+/// Number of words preceding first match in highlighted snippet.
+const SNIPPET_CONTEXT: usize = 5;
+
+/// Full-text search mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchMode {
+    /// Natural language search (e.g. `database replication`).
+    #[default]
+    NaturalLanguage,
+    /// Boolean search with operators (e.g. `+database -mysql repl*`).
+    Boolean,
+}
+
+impl SearchMode {
+    /// Get SQL representation of the mode.
+    ///
+    /// # Returns
+    /// - `AGAINST` search modifier.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SearchMode::NaturalLanguage => "IN NATURAL LANGUAGE MODE",
+            SearchMode::Boolean         => "IN BOOLEAN MODE",
+        }
+    }
+}
+
+/// Message search query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageQuery {
+    /// Searched text.
+    pub text: String,
+    /// Search mode.
+    pub mode: SearchMode,
+    /// Channel identifier filter.
+    pub channel_id: Option<i64>,
+    /// Sender identifier filter.
+    pub user_id: Option<i64>,
+    /// Messages sent at or after given time.
+    pub since: Option<NaiveDateTime>,
+    /// Messages sent before given time.
+    pub until: Option<NaiveDateTime>,
+    /// Maximum number of hits.
+    pub limit: u64,
+}
+
+impl MessageQuery {
+    /// Construct new MessageQuery object.
+    ///
+    /// # Parameters
+    /// - `text` - given searched text.
+    ///
+    /// # Returns
+    /// - New `MessageQuery` object without filters.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text:       text.to_string(),
+            mode:       SearchMode::default(),
+            channel_id: None,
+            user_id:    None,
+            since:      None,
+            until:      None,
+            limit:      DEFAULT_LIMIT,
+        }
+    }
+
+    /// Set search mode.
+    pub fn mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Search only messages of given channel.
+    pub fn channel(mut self, channel_id: i64) -> Self {
+        self.channel_id = Some(channel_id);
+        self
+    }
+
+    /// Search only messages of given sender.
+    pub fn user(mut self, user_id: i64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Search only messages sent at or after given time.
+    pub fn since(mut self, time: NaiveDateTime) -> Self {
+        self.since = Some(time);
+        self
+    }
+
+    /// Search only messages sent before given time.
+    pub fn until(mut self, time: NaiveDateTime) -> Self {
+        self.until = Some(time);
+        self
+    }
+
+    /// Set maximum number of hits.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// Profile search query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileQuery {
+    /// Searched text.
+    pub text: String,
+    /// Search mode.
+    pub mode: SearchMode,
+    /// User identifier filter.
+    pub user_id: Option<i64>,
+    /// Maximum number of hits.
+    pub limit: u64,
+}
+
+impl ProfileQuery {
+    /// Construct new ProfileQuery object.
+    ///
+    /// # Parameters
+    /// - `text` - given searched text.
+    ///
+    /// # Returns
+    /// - New `ProfileQuery` object without filters.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text:    text.to_string(),
+            mode:    SearchMode::default(),
+            user_id: None,
+            limit:   DEFAULT_LIMIT,
+        }
+    }
+
+    /// Set search mode.
+    pub fn mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Search only profile of given user.
+    pub fn user(mut self, user_id: i64) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Set maximum number of hits.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// Found message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MessageHit {
+    /// Area of the message.
+    #[sqlx(skip)]
+    pub area: Area,
+    /// Message identifier.
+    pub message_id: i64,
+    /// Channel identifier.
+    pub channel_id: Option<i64>,
+    /// Sender identifier.
+    pub user_id: Option<i64>,
+    /// Message text.
+    pub message_text: Option<String>,
+    /// Time of sending.
+    pub timestamp: Option<NaiveDateTime>,
+    /// Relevance score (greater is more relevant).
+    pub score: f64,
+    /// HTML-escaped fragment of message text with matches in `<em>` tags.
+    #[sqlx(skip)]
+    pub snippet: String,
+}
+
+/// Found user profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProfileHit {
+    /// Area of the profile.
+    #[sqlx(skip)]
+    pub area: Area,
+    /// Profile identifier.
+    pub profile_id: i64,
+    /// User identifier.
+    pub user_id: Option<i64>,
+    /// Biography of the user.
+    pub bio: Option<String>,
+    /// Location of the user.
+    pub location: Option<String>,
+    /// Relevance score (greater is more relevant).
+    pub score: f64,
+    /// HTML-escaped fragment of bio & location with matches in `<em>` tags.
+    #[sqlx(skip)]
+    pub snippet: String,
+}
+
+/// Create full-text indexes if not exist.
+///
+/// # Parameters
+/// - `pool` - given MySQL connection pool of area database.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+pub async fn create_search_indexes(pool: &MySqlPool) -> Result<(), sqlx::Error> {
+    let indexes = [
+        ("Message", MESSAGE_INDEX, "message_text"),
+        ("User_Profiles", PROFILE_INDEX, "bio, location"),
+    ];
+
+    for (table, index, columns) in indexes {
+        let query =
+            r#"
+            SELECT COUNT(*) FROM information_schema.STATISTICS
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND INDEX_NAME = ?;
+            "#;
+
+        let (count,): (i64,) = sqlx::query_as(query)
+            .bind(table)
+            .bind(index)
+            .fetch_one(pool)
+            .await?;
+
+        if count == 0 {
+            let query = format!("ALTER TABLE {table} ADD FULLTEXT INDEX {index} ({columns});");
+            sqlx::query(query.as_str()).execute(pool).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Search messages of area database.
+///
+/// # Parameters
+/// - `pool`  - given MySQL connection pool of area database.
+/// - `area`  - given area of the database.
+/// - `query` - given search query.
+///
+/// # Returns
+/// - Vector of hits ordered by relevance - in case of success.
+/// - `sqlx::Error` - otherwise.
+pub async fn search_messages(pool: &MySqlPool, area: Area, query: &MessageQuery)
+    -> Result<Vec<MessageHit>, sqlx::Error>
+{
+    let matching = format!("MATCH(message_text) AGAINST (? {})", query.mode.as_sql());
+    let mut sql  = format!(
+        r#"
+        SELECT message_id, channel_id, user_id, message_text, timestamp,
+            CAST({matching} AS DOUBLE) AS score
+        FROM Message
+        WHERE {matching}
+        "#
+    );
+
+    let mut args = MySqlArguments::default();
+    args.add(&query.text).map_err(sqlx::Error::Encode)?;
+    args.add(&query.text).map_err(sqlx::Error::Encode)?;
+
+    if let Some(channel_id) = query.channel_id {
+        sql.push_str(" AND channel_id = ?");
+        args.add(channel_id).map_err(sqlx::Error::Encode)?;
+    }
+
+    if let Some(user_id) = query.user_id {
+        sql.push_str(" AND user_id = ?");
+        args.add(user_id).map_err(sqlx::Error::Encode)?;
+    }
+
+    if let Some(since) = query.since {
+        sql.push_str(" AND timestamp >= ?");
+        args.add(since).map_err(sqlx::Error::Encode)?;
+    }
+
+    if let Some(until) = query.until {
+        sql.push_str(" AND timestamp < ?");
+        args.add(until).map_err(sqlx::Error::Encode)?;
+    }
+
+    sql.push_str(" ORDER BY score DESC, message_id LIMIT ?;");
+    args.add(query.limit).map_err(sqlx::Error::Encode)?;
+
+    let mut hits = sqlx::query_as_with::<_, MessageHit, _>(sql.as_str(), args)
+        .fetch_all(pool)
+        .await?;
+
+    for hit in &mut hits {
+        hit.area    = area;
+        hit.snippet = highlight(hit.message_text.as_deref().unwrap_or(""), &query.text, query.mode);
+    }
+
+    Ok(hits)
+}
+
+/// Search user profiles of area database by bio & location.
+///
+/// Fields encrypted by `DocDBManager::with_encryption` are not searchable.
+///
+/// # Parameters
+/// - `pool`  - given MySQL connection pool of area database.
+/// - `area`  - given area of the database.
+/// - `query` - given search query.
+///
+/// # Returns
+/// - Vector of hits ordered by relevance - in case of success.
+/// - `sqlx::Error` - otherwise.
+pub async fn search_profiles(pool: &MySqlPool, area: Area, query: &ProfileQuery)
+    -> Result<Vec<ProfileHit>, sqlx::Error>
+{
+    let matching = format!("MATCH(bio, location) AGAINST (? {})", query.mode.as_sql());
+    let mut sql  = format!(
+        r#"
+        SELECT profile_id, user_id, bio, location,
+            CAST({matching} AS DOUBLE) AS score
+        FROM User_Profiles
+        WHERE {matching}
+        "#
+    );
+
+    let mut args = MySqlArguments::default();
+    args.add(&query.text).map_err(sqlx::Error::Encode)?;
+    args.add(&query.text).map_err(sqlx::Error::Encode)?;
+
+    if let Some(user_id) = query.user_id {
+        sql.push_str(" AND user_id = ?");
+        args.add(user_id).map_err(sqlx::Error::Encode)?;
+    }
+
+    sql.push_str(" ORDER BY score DESC, profile_id LIMIT ?;");
+    args.add(query.limit).map_err(sqlx::Error::Encode)?;
+
+    let mut hits = sqlx::query_as_with::<_, ProfileHit, _>(sql.as_str(), args)
+        .fetch_all(pool)
+        .await?;
+
+    for hit in &mut hits {
+        let text = format!(
+            "{} — {}",
+            hit.bio.as_deref().unwrap_or(""),
+            hit.location.as_deref().unwrap_or("")
+        );
+
+        hit.area    = area;
+        hit.snippet = highlight(&text, &query.text, query.mode);
+    }
+
+    Ok(hits)
+}
+
+/// Searched term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    /// Lowercase word.
+    word: String,
+    /// Does term match words starting with it (`word*`)?
+    is_prefix: bool,
+}
+
+/// Extract highlighted terms from search text.
+///
+/// Excluded words of boolean search (e.g. `-mysql`) are not highlighted.
+///
+/// # Parameters
+/// - `text` - given search text.
+/// - `mode` - given search mode.
+///
+/// # Returns
+/// - Vector of terms.
+fn terms(text: &str, mode: SearchMode) -> Vec<Term> {
+    let mut terms = Vec::new();
+
+    for token in text.split_whitespace() {
+        if mode == SearchMode::Boolean && token.starts_with('-') {
+            continue;
+        }
+
+        let mut word = String::new();
+
+        for c in token.chars().chain([' ']) {
+            if c.is_alphanumeric() || c == '_' {
+                word.extend(c.to_lowercase());
+                continue;
+            }
+
+            if !word.is_empty() {
+                let is_prefix = mode == SearchMode::Boolean && c == '*';
+                terms.push(Term { word: std::mem::take(&mut word), is_prefix });
+            }
+        }
+    }
+
+    terms
+}
+
+/// Escape HTML special characters.
+///
+/// # Parameters
+/// - `text` - given text.
+///
+/// # Returns
+/// - Escaped text.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&'  => escaped.push_str("&amp;"),
+            '<'  => escaped.push_str("&lt;"),
+            '>'  => escaped.push_str("&gt;"),
+            '"'  => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _    => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Highlight matches of search text.
+///
+/// Snippet contains words around the first match, matched words are
+/// wrapped in `<em>` tags and the rest of text is HTML-escaped.
+///
+/// # Parameters
+/// - `text`   - given found text.
+/// - `search` - given search text.
+/// - `mode`   - given search mode.
+///
+/// # Returns
+/// - Highlighted snippet.
+pub fn highlight(text: &str, search: &str, mode: SearchMode) -> String {
+    let terms = terms(search, mode);
+
+    // Byte spans of words in text.
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (c.is_alphanumeric() || c == '_', start) {
+            (true, None)     => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if words.is_empty() {
+        return escape_html(text);
+    }
+
+    let is_match = |&(s, e): &(usize, usize)| {
+        let word = text[s..e].to_lowercase();
+
+        terms.iter().any(|term| {
+            if term.is_prefix { word.starts_with(&term.word) } else { word == term.word }
+        })
+    };
+
+    let first = words.iter().position(is_match).unwrap_or(0);
+    let from  = first.saturating_sub(SNIPPET_CONTEXT);
+    let to    = words.len().min(from + SNIPPET_WORDS);
+
+    let mut snippet = String::new();
+    let mut offset  = if from == 0 { 0 } else { words[from].0 };
+
+    if from > 0 {
+        snippet.push('…');
+    }
+
+    for word in &words[from..to] {
+        snippet.push_str(&escape_html(&text[offset..word.0]));
+
+        let escaped = escape_html(&text[word.0..word.1]);
+
+        if is_match(word) {
+            snippet.push_str(&format!("<em>{escaped}</em>"));
+        }
+        else {
+            snippet.push_str(&escaped);
+        }
+
+        offset = word.1;
+    }
+
+    if to < words.len() {
+        snippet.push('…');
+    }
+    else {
+        snippet.push_str(&escape_html(&text[offset..]));
+    }
+
+    snippet
+}
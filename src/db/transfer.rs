@@ -0,0 +1,203 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Settings import & export related declarations.
+//!
+//! Server, channel & user settings are exported as flat records, which
+//! can be written as JSON array or CSV table and imported into another
+//! database.
+
+use std::io::{Read, Write};
+use serde::{Deserialize, Serialize};
+use crate::db::{error::DbError, kvdb::Namespace, registry::SettingScope};
+use chrono::NaiveDateTime;
+
+/// Serialization format of setting records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferFormat {
+    /// JSON array of records.
+    Json,
+    /// CSV table with header row.
+    Csv,
+}
+
+/// Action taken on existing setting which differs from imported one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep stored setting.
+    #[default]
+    Skip,
+    /// Replace stored setting with imported one.
+    Overwrite,
+    /// Abort whole import.
+    Fail,
+}
+
+/// Single exported setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingRecord {
+    /// Settings layer.
+    pub scope: SettingScope,
+    /// Channel or user identifier (`None` for server settings).
+    pub scope_id: Option<i64>,
+    /// Name of the setting.
+    pub name: String,
+    /// Value of the setting.
+    pub value: String,
+    /// Time when setting expires (`None` for persistent settings).
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl SettingRecord {
+    /// Construct new persistent SettingRecord object.
+    ///
+    /// # Parameters
+    /// - `namespace` - given settings namespace.
+    /// - `name`      - given setting name.
+    /// - `value`     - given setting value.
+    ///
+    /// # Returns
+    /// - New `SettingRecord` object or `None` for custom namespaces.
+    pub fn new(namespace: &Namespace, name: &str, value: &str) -> Option<Self> {
+        let scope_id = match namespace {
+            Namespace::Server      => None,
+            Namespace::Channel(id) => Some(*id),
+            Namespace::User(id)    => Some(*id),
+            Namespace::Custom(_)   => return None,
+        };
+
+        Some(Self {
+            scope:      SettingScope::of(namespace)?,
+            scope_id,
+            name:       name.to_string(),
+            value:      value.to_string(),
+            expires_at: None,
+        })
+    }
+
+    /// Get namespace of the setting.
+    ///
+    /// # Returns
+    /// - Settings namespace - in case of success.
+    /// - `DbError` - if scope identifier does not match scope.
+    pub fn namespace(&self) -> Result<Namespace, DbError> {
+        match (self.scope, self.scope_id) {
+            (SettingScope::Server, None)      => Ok(Namespace::Server),
+            (SettingScope::Channel, Some(id)) => Ok(Namespace::Channel(id)),
+            (SettingScope::User, Some(id))    => Ok(Namespace::User(id)),
+            (scope, id) => Err(DbError::InvalidScope(
+                format!("{} setting {:?} with scope id {id:?}", scope.as_str(), self.name)
+            )),
+        }
+    }
+}
+
+/// Result of importing single setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportAction {
+    /// Setting did not exist and is inserted.
+    Insert,
+    /// Stored setting differs and is overwritten.
+    Update,
+    /// Stored setting is equal to imported one.
+    Unchanged,
+    /// Stored setting differs and is kept.
+    Skip,
+}
+
+/// Planned or applied change of single setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingChange {
+    /// Imported setting.
+    pub record: SettingRecord,
+    /// Stored value before import (`None` if setting did not exist).
+    pub previous: Option<String>,
+    /// Action taken on the setting.
+    pub action: ImportAction,
+}
+
+/// Settings import report.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Changes in order of imported records.
+    pub changes: Vec<SettingChange>,
+    /// Whether changes were written (`false` for dry run).
+    pub applied: bool,
+}
+
+impl ImportReport {
+    /// Count changes with given action.
+    ///
+    /// # Parameters
+    /// - `action` - given import action.
+    ///
+    /// # Returns
+    /// - Number of changes.
+    pub fn count(&self, action: ImportAction) -> usize {
+        self.changes.iter().filter(|change| change.action == action).count()
+    }
+}
+
+/// Write setting records.
+///
+/// # Parameters
+/// - `writer`  - given output.
+/// - `records` - given setting records.
+/// - `format`  - given serialization format.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `DbError` - otherwise.
+pub fn write_records<W: Write>(writer: W, records: &[SettingRecord], format: TransferFormat)
+    -> Result<(), DbError>
+{
+    match format {
+        TransferFormat::Json => serde_json::to_writer_pretty(writer, records)?,
+        TransferFormat::Csv  => {
+            let mut writer = csv::Writer::from_writer(writer);
+
+            for record in records {
+                writer.serialize(record)?;
+            }
+
+            writer.flush().map_err(csv::Error::from)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Read setting records.
+///
+/// # Parameters
+/// - `reader` - given input.
+/// - `format` - given serialization format.
+///
+/// # Returns
+/// - Setting records - in case of success.
+/// - `DbError` - otherwise.
+pub fn read_records<R: Read>(reader: R, format: TransferFormat)
+    -> Result<Vec<SettingRecord>, DbError>
+{
+    let records = match format {
+        TransferFormat::Json => serde_json::from_reader(reader)?,
+        TransferFormat::Csv  => csv::Reader::from_reader(reader)
+            .deserialize()
+            .collect::<Result<_, _>>()?,
+    };
+
+    Ok(records)
+}
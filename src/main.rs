@@ -14,40 +14,71 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-mod chat;
-mod db;
-mod task1;
-mod task2;
-mod task3;
-mod task4;
+use dbproject::db::{
+    area::{Area, AreaDB},
+    blockchain::BlockchainDBManager,
+    docdb::DocDBManager,
+    global::GlobalDB,
+    kvdb::KeyValueDBManager,
+    ConnectionConfig,
+};
 
 #[tokio::main]
 async fn main() {
-    let task = 4;
-
-    match task {
-        1 => {
-            if let Err(err) = task1::setup_db().await {
-                eprintln!("Error: {err}");
-            }
-        }
-        2 => {
-            if let Err(err) = task2::setup_db().await {
-                eprintln!("Error: {err}");
-            }
-        }
-        3 => {
-            if let Err(err) = task3::setup_db().await {
-                eprintln!("Error: {err}");
-            }
-        }
-        4 => {
-            if let Err(err) = task4::setup_db().await {
-                eprintln!("Error: {err}");
-            }
-        }
-        _ => {
-            eprintln!("Incorrect task: {task}");
-        }
+    if let Err(err) = setup_db().await {
+        eprintln!("Error: {err}");
     }
-}
\ No newline at end of file
+}
+
+/// Set up all project databases.
+///
+/// # Returns
+/// - `Ok` - in case of success.
+/// - `sqlx::Error` - otherwise.
+async fn setup_db() -> Result<(), sqlx::Error> {
+    let config = ConnectionConfig::new(
+        "test".to_string(),
+        "12345".to_string(),
+        "localhost".to_string(),
+        3306,
+        "".to_string(),
+    );
+
+    // Set global database manager.
+    let mut global_db = GlobalDB::new();
+    global_db.connect(&config).await?;
+
+    let mut rus_area_config  = config.clone();
+    rus_area_config.database = "AreaDB_Russia".to_string();
+
+    // Set area-specific database managers.
+    global_db.insert(AreaDB::new(rus_area_config, Area::Russia)).await?;
+
+    let mut usa_area_config  = config.clone();
+    usa_area_config.database = "AreaDB_USA".to_string();
+
+    global_db.insert(AreaDB::new(usa_area_config, Area::Usa)).await?;
+
+    let mut doc_db_config  = config.clone();
+    doc_db_config.database = "DocumentDB_JSON".to_string();
+
+    let mut doc_db_manager = DocDBManager::new();
+    doc_db_manager.connect(doc_db_config).await?;
+    doc_db_manager.set_procedures().await?;
+
+    let mut kv_db_config  = config.clone();
+    kv_db_config.database = "KeyValueDB".to_string();
+
+    let mut kv_db_manager = KeyValueDBManager::new();
+    kv_db_manager.connect(kv_db_config).await?;
+    kv_db_manager.set_procedures().await?;
+
+    let mut blockchain_db_config  = config.clone();
+    blockchain_db_config.database = "BlockchainDB".to_string();
+
+    let mut blockchain_db_manager = BlockchainDBManager::new();
+    blockchain_db_manager.connect(blockchain_db_config).await?;
+    blockchain_db_manager.set_procedures().await?;
+
+    Ok(())
+}
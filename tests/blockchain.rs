@@ -0,0 +1,324 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Blockchain database manager integration tests.
+
+mod common;
+
+use common::TestDb;
+use dbproject::db::blockchain::{Block, BlockFault, BlockchainDBManager, HashVersion};
+use sqlx::MySqlPool;
+use std::sync::Arc;
+
+fn block() -> Block {
+    Block {
+        block_id:            7,
+        previous_block_hash: Some("ab".to_string()),
+        message_id:          None,
+        user_id:             Some(1),
+        channel_id:          Some(2),
+        message_text:        Some("c".to_string()),
+        timestamp:           chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0),
+        block_hash:          None,
+        hash_version:        HashVersion::Canonical as i64,
+    }
+}
+
+/// Append legacy block & log its transaction the way former `SendMessage`
+/// procedure did: block is linked to the greatest hash of existing blocks.
+/// Timestamp is fixed, so hashes are reproducible.
+async fn append_block(pool: &MySqlPool, channel_id: i64, text: &str) -> String {
+    let query =
+        r#"
+        INSERT INTO Message_Block (block_id, previous_block_hash, user_id, channel_id, message_text, timestamp, block_hash)
+        SELECT COALESCE(MAX(block_id), 0) + 1, COALESCE(MAX(block_hash), ''), 1, ?, ?, '2025-01-01 00:00:00',
+            SHA2(CONCAT(COALESCE(MAX(block_hash), ''), ?, '2025-01-01 00:00:00'), 256)
+        FROM Message_Block;
+        "#;
+
+    sqlx::query(query)
+        .bind(channel_id)
+        .bind(text)
+        .bind(text)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let (hash,): (String,) = sqlx::query_as("SELECT block_hash FROM Message_Block ORDER BY block_id DESC LIMIT 1;")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+    sqlx::query("INSERT INTO Transaction_Log (user_id, action_type, channel_id, timestamp, block_hash) VALUES (1, 'SEND_MESSAGE', ?, NOW(), ?);")
+        .bind(channel_id)
+        .bind(&hash)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    hash
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn send_message_logs_transactions_and_blocks() {
+    let mut test_db = require_mysql!();
+
+    let mut blockchain_db = BlockchainDBManager::new();
+    blockchain_db.connect(test_db.database("BlockchainDB")).await.unwrap();
+    blockchain_db.set_procedures().await.unwrap();
+
+    blockchain_db.send_message(1, 1, "Some test message1").await.unwrap();
+    blockchain_db.send_message(1, 1, "Some test message2").await.unwrap();
+    blockchain_db.send_message(2, 2, "Some test message3").await.unwrap();
+
+    let transactions = blockchain_db.get_user_transactions(1).await.unwrap();
+    assert_eq!(transactions.len(), 2);
+
+    for transaction in &transactions {
+        assert_eq!(transaction.action_type, "SEND_MESSAGE");
+        assert_eq!(transaction.channel_id, 1);
+        assert_eq!(transaction.block_hash.len(), 64);
+    }
+
+    assert_eq!(blockchain_db.get_message_count(1).await.unwrap(), 2);
+    assert_eq!(blockchain_db.get_message_count(2).await.unwrap(), 1);
+    assert_eq!(blockchain_db.get_message_count(3).await.unwrap(), 0);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn verify_chain_reports_tampered_and_missing_blocks() {
+    let mut test_db = require_mysql!();
+
+    let config = test_db.database("BlockchainDB_Verify");
+    let mut blockchain_db = BlockchainDBManager::new();
+    blockchain_db.connect(config.clone()).await.unwrap();
+
+    let pool = TestDb::pool(&config).await;
+
+    for block_id in 1..=6 {
+        append_block(&pool, 2 - block_id % 2, &format!("message {block_id}")).await;
+    }
+
+    // Block 6 is linked to hash of block 4, which is greater than hash of block 5.
+    let block = blockchain_db.get_block(6).await.unwrap().unwrap();
+    assert_eq!(block.previous_block_hash, blockchain_db.get_block(4).await.unwrap().unwrap().block_hash);
+
+    let report = blockchain_db.verify_chain(None).await.unwrap();
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!(report.checked, 6);
+    assert_eq!(blockchain_db.verify_chain(Some(1)).await.unwrap().checked, 3);
+
+    // Tampered message no longer matches its hash.
+    sqlx::query("UPDATE Message_Block SET message_text = 'forged' WHERE block_id = 3;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = blockchain_db.verify_chain(None).await.unwrap();
+    let broken = report.first_broken.unwrap();
+    assert_eq!(broken.block_id, 3);
+    assert!(matches!(broken.fault, BlockFault::HashMismatch { .. }));
+    assert!(blockchain_db.verify_chain(Some(2)).await.unwrap().is_valid());
+
+    sqlx::query("UPDATE Message_Block SET message_text = 'message 3' WHERE block_id = 3;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Removed block leaves gap, broken link & orphan transaction.
+    sqlx::query("DELETE FROM Message_Block WHERE block_id = 2;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = blockchain_db.verify_chain(None).await.unwrap();
+    assert_eq!(report.checked, 5);
+    assert_eq!(report.missing_blocks, [2..=2]);
+    assert_eq!(report.orphan_transactions.len(), 1);
+    assert_eq!(report.orphan_transactions[0].channel_id, 2);
+
+    let broken = report.first_broken.unwrap();
+    assert_eq!(broken.block_id, 3);
+    assert!(matches!(broken.fault, BlockFault::BrokenLink { .. }));
+
+    let report = blockchain_db.verify_chain(Some(1)).await.unwrap();
+    assert!(report.orphan_transactions.is_empty());
+    assert_eq!(report.first_broken.unwrap().block_id, 3);
+}
+
+#[test]
+fn canonical_hash_covers_every_field_unambiguously() {
+    let original = block();
+    let hash     = original.compute_hash().unwrap().unwrap();
+
+    assert_eq!(hash.len(), 64);
+    assert_eq!(original.compute_hash().unwrap().unwrap(), hash);
+
+    let changes: [fn(&mut Block); 7] = [
+        |block| block.block_id += 1,
+        |block| block.previous_block_hash = None,
+        |block| block.message_id = Some(0),
+        |block| block.user_id = Some(3),
+        |block| block.channel_id = None,
+        |block| block.message_text = Some(String::new()),
+        |block| block.timestamp = None,
+    ];
+
+    for change in changes {
+        let mut changed = block();
+        change(&mut changed);
+        assert_ne!(changed.compute_hash().unwrap().unwrap(), hash);
+    }
+
+    // Field boundaries are part of encoding.
+    let mut shifted = block();
+    shifted.previous_block_hash = Some("a".to_string());
+    shifted.message_text        = Some("bc".to_string());
+    assert_ne!(shifted.compute_hash().unwrap().unwrap(), hash);
+
+    let mut legacy = block();
+    legacy.hash_version = HashVersion::Legacy as i64;
+    assert_ne!(legacy.compute_hash().unwrap().unwrap(), hash);
+
+    legacy.message_text = None;
+    assert_eq!(legacy.compute_hash().unwrap(), None);
+
+    let mut unknown = block();
+    unknown.hash_version = 9;
+    assert_eq!(unknown.compute_hash(), Err(9));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn sent_messages_extend_legacy_chain_with_canonical_blocks() {
+    let mut test_db = require_mysql!();
+
+    let config = test_db.database("BlockchainDB_Hashing");
+    let mut blockchain_db = BlockchainDBManager::new();
+    blockchain_db.connect(config.clone()).await.unwrap();
+    blockchain_db.set_procedures().await.unwrap();
+
+    let pool = TestDb::pool(&config).await;
+
+    append_block(&pool, 1, "legacy 1").await;
+    let legacy_hash = append_block(&pool, 1, "legacy 2").await;
+
+    for i in 0..5 {
+        blockchain_db.send_message(i, 1 + i % 2, &format!("message {i}")).await.unwrap();
+    }
+
+    let block = blockchain_db.get_block(3).await.unwrap().unwrap();
+    assert_eq!(block.hash_version, HashVersion::Canonical as i64);
+    assert_eq!(block.previous_block_hash, Some(legacy_hash));
+    assert_eq!(block.compute_hash().unwrap(), block.block_hash);
+
+    for block_id in 4..=7 {
+        let previous = blockchain_db.get_block(block_id - 1).await.unwrap().unwrap();
+        let block    = blockchain_db.get_block(block_id).await.unwrap().unwrap();
+        assert_eq!(block.previous_block_hash, previous.block_hash);
+    }
+
+    // Procedure hashes blocks as `send_message`, including multi-byte text.
+    sqlx::query("CALL SendMessage(?, ?, ?);")
+        .bind(5)
+        .bind(1)
+        .bind("привет, мир")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let block = blockchain_db.get_block(8).await.unwrap().unwrap();
+    assert_eq!(block.hash_version, HashVersion::Canonical as i64);
+    assert_eq!(block.message_text.as_deref(), Some("привет, мир"));
+    assert_eq!(block.compute_hash().unwrap(), block.block_hash);
+
+    let report = blockchain_db.verify_chain(None).await.unwrap();
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!(report.checked, 8);
+
+    // Canonical hash covers author, unlike legacy one.
+    sqlx::query("UPDATE Message_Block SET user_id = 99 WHERE block_id = 5;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let broken = blockchain_db.verify_chain(None).await.unwrap().first_broken.unwrap();
+    assert_eq!(broken.block_id, 5);
+    assert!(matches!(broken.fault, BlockFault::HashMismatch { .. }));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn concurrent_appends_form_single_linear_chain() {
+    let mut test_db = require_mysql!();
+
+    const TASKS: i64 = 64;
+
+    let config = test_db.database("BlockchainDB_Concurrency");
+    let mut blockchain_db = BlockchainDBManager::new();
+    blockchain_db.connect(config.clone()).await.unwrap();
+    blockchain_db.set_procedures().await.unwrap();
+
+    let blockchain_db = Arc::new(blockchain_db);
+    let pool          = TestDb::pool(&config).await;
+    let mut tasks     = Vec::new();
+
+    for i in 0..TASKS {
+        let blockchain_db = Arc::clone(&blockchain_db);
+        let pool          = pool.clone();
+
+        tasks.push(tokio::spawn(async move {
+            // Procedure appends must be serialized with appends made in Rust.
+            if i % 8 == 0 {
+                sqlx::query("CALL SendMessage(?, ?, ?);")
+                    .bind(i)
+                    .bind(1 + i % 4)
+                    .bind(format!("message {i}"))
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            } else {
+                blockchain_db.send_message(i, 1 + i % 4, &format!("message {i}")).await.unwrap();
+            }
+        }));
+    }
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT block_id FROM Message_Block ORDER BY block_id;")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(ids, (1..=TASKS).collect::<Vec<_>>());
+
+    let mut previous_hash = String::new();
+
+    for block_id in 1..=TASKS {
+        let block = blockchain_db.get_block(block_id).await.unwrap().unwrap();
+        assert_eq!(block.previous_block_hash, Some(previous_hash));
+        assert_eq!(block.hash_version, HashVersion::Canonical as i64);
+        previous_hash = block.block_hash.unwrap();
+    }
+
+    let report = blockchain_db.verify_chain(None).await.unwrap();
+    assert!(report.is_valid(), "{report:?}");
+    assert_eq!(report.checked, TASKS as u64);
+    assert!(report.orphan_transactions.is_empty());
+}
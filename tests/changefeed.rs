@@ -0,0 +1,242 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Change feed integration tests.
+
+mod common;
+
+use std::time::{Duration, Instant};
+use chrono::NaiveDate;
+use common::TestDb;
+use dbproject::db::{
+    changefeed::{self, ChangeEvent, ChangeFeed, ChangeOp},
+    docdb::DocDBManager,
+    kvdb::KeyValueDBManager,
+};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Setting {
+    user_id: i64,
+    setting_name: String,
+    setting_value: String,
+}
+
+#[test]
+fn change_events_decode_rows() {
+    let event = ChangeEvent {
+        id:         1,
+        table:      "User_Settings_KV".to_string(),
+        op:         ChangeOp::Update,
+        key:        json!({ "user_id": 1, "setting_name": "theme" }),
+        old:        Some(json!({ "user_id": 1, "setting_name": "theme", "setting_value": "dark" })),
+        new:        None,
+        created_at: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+    };
+
+    let old: Option<Setting> = event.old_as().unwrap();
+    assert_eq!(old.unwrap().setting_value, "dark");
+    assert_eq!(event.new_as::<Setting>().unwrap(), None);
+
+    assert_eq!("DELETE".parse::<ChangeOp>(), Ok(ChangeOp::Delete));
+    assert_eq!(ChangeOp::Insert.to_string(), "INSERT");
+    assert!("TRUNCATE".parse::<ChangeOp>().is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn settings_changes_are_streamed_in_order() {
+    let mut test_db = require_mysql!();
+    let config = test_db.database("KeyValueDB_Changes");
+
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(config.clone()).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+
+    let pool = TestDb::pool(&config).await;
+    let feed = kv_db.changes().await.unwrap().poll_interval(Duration::from_millis(50));
+
+    let queries = [
+        "INSERT INTO User_Settings_KV (user_id, setting_name, setting_value) VALUES (77, 'theme', 'dark');",
+        "UPDATE User_Settings_KV SET setting_value = 'light' WHERE user_id = 77;",
+        "UPDATE User_Settings_KV SET setting_value = 'light' WHERE user_id = 77;",
+        "DELETE FROM User_Settings_KV WHERE user_id = 77;",
+    ];
+
+    for query in queries {
+        sqlx::query(query).execute(&pool).await.unwrap();
+    }
+
+    let mut stream = Box::pin(feed.into_stream());
+    let mut events = Vec::new();
+
+    // Update without changes is not captured.
+    for _ in 0..3 {
+        let event = tokio::time::timeout(Duration::from_secs(10), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        events.push(event);
+    }
+
+    let ops: Vec<ChangeOp> = events.iter().map(|event| event.op).collect();
+    assert_eq!(ops, [ChangeOp::Insert, ChangeOp::Update, ChangeOp::Delete]);
+    assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+    assert!(events.iter().all(|event| event.table == "User_Settings_KV"));
+    assert_eq!(events[0].key, json!({ "user_id": 77, "setting_name": "theme" }));
+
+    let new: Setting = events[1].new_as().unwrap().unwrap();
+    assert_eq!(new, Setting { user_id: 77, setting_name: "theme".into(), setting_value: "light".into() });
+    assert_eq!(events[2].new, None);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn document_changes_resume_from_cursor() {
+    let mut test_db = require_mysql!();
+    let config = test_db.database("DocumentDB_Changes");
+
+    let mut doc_db = DocDBManager::new();
+    doc_db.connect(config.clone()).await.unwrap();
+    doc_db.set_procedures().await.unwrap();
+
+    let pool     = TestDb::pool(&config).await;
+    let mut feed = doc_db.changes().await.unwrap();
+    assert!(feed.poll().await.unwrap().is_empty());
+
+    sqlx::query("UPDATE Message SET message_text = 'edited' WHERE message_id = 1;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let events = feed.poll().await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].table, "Message");
+    assert_eq!(events[0].key, json!({ "message_id": 1 }));
+    assert_eq!(events[0].new.as_ref().unwrap()["message_text"], "edited");
+
+    let cursor = feed.cursor();
+
+    sqlx::query("UPDATE User_Profiles SET bio = 'resumed' WHERE profile_id = 1;")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Restarted consumer continues after persisted cursor.
+    let mut resumed = ChangeFeed::new(pool.clone()).from_cursor(cursor);
+    let events      = resumed.poll().await.unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].table, "User_Profiles");
+    assert_eq!(events[0].op, ChangeOp::Update);
+    assert_eq!(events[0].new.as_ref().unwrap()["profile_data"]["bio"], "resumed");
+
+    // Table filter still advances cursor over other tables.
+    let mut messages = ChangeFeed::new(pool.clone()).from_cursor(cursor).tables(&["Message"]);
+    assert!(messages.poll().await.unwrap().is_empty());
+    assert_eq!(messages.cursor(), events[0].id);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn holes_of_rolled_back_and_running_transactions() {
+    let mut test_db = require_mysql!();
+    let config = test_db.database("DocumentDB_Holes");
+
+    let mut doc_db = DocDBManager::new();
+    doc_db.connect(config.clone()).await.unwrap();
+
+    let pool = TestDb::pool(&config).await;
+
+    sqlx::query("CREATE TABLE Notes (note_id BIGINT AUTO_INCREMENT PRIMARY KEY, body TEXT);")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    changefeed::install(&pool, &["Notes"]).await.unwrap();
+
+    let mut feed = ChangeFeed::tail(pool.clone()).await.unwrap().gap_timeout(Duration::from_millis(100));
+    let insert   = "INSERT INTO Notes (body) VALUES (?);";
+
+    async fn poll_for(feed: &mut ChangeFeed, duration: Duration) -> Vec<ChangeEvent> {
+        let started    = Instant::now();
+        let mut events = Vec::new();
+
+        while started.elapsed() < duration {
+            events.extend(feed.poll().await.unwrap());
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        events
+    }
+
+    // Hole of running transaction at the beginning of outbox is not skipped.
+    let mut transaction = pool.begin().await.unwrap();
+    sqlx::query(insert).bind("first").execute(&mut *transaction).await.unwrap();
+
+    sqlx::query(insert).bind("second").execute(&pool).await.unwrap();
+    assert!(poll_for(&mut feed, Duration::from_millis(500)).await.is_empty());
+    assert_eq!(feed.cursor(), 0);
+
+    transaction.commit().await.unwrap();
+
+    let bodies: Vec<String> = poll_for(&mut feed, Duration::from_millis(500))
+        .await
+        .iter()
+        .map(|event| event.new.as_ref().unwrap()["body"].as_str().unwrap().to_string())
+        .collect();
+
+    assert_eq!(bodies, ["first", "second"]);
+
+    // Rolled back write leaves unlocked hole, which delays feed only briefly.
+    let mut transaction = pool.begin().await.unwrap();
+    sqlx::query(insert).bind("rolled back").execute(&mut *transaction).await.unwrap();
+    transaction.rollback().await.unwrap();
+
+    sqlx::query(insert).bind("committed").execute(&pool).await.unwrap();
+
+    let started    = Instant::now();
+    let mut events = Vec::new();
+
+    while events.is_empty() && started.elapsed() < Duration::from_secs(5) {
+        events = feed.poll().await.unwrap();
+    }
+
+    assert!(started.elapsed() < Duration::from_secs(1), "{:?}", started.elapsed());
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].new.as_ref().unwrap()["body"], "committed");
+
+    // Hole of running transaction is waited for past gap timeout & not skipped.
+    let mut transaction = pool.begin().await.unwrap();
+    sqlx::query(insert).bind("late").execute(&mut *transaction).await.unwrap();
+
+    sqlx::query(insert).bind("early").execute(&pool).await.unwrap();
+    assert!(poll_for(&mut feed, Duration::from_millis(500)).await.is_empty());
+
+    transaction.commit().await.unwrap();
+
+    let bodies: Vec<String> = poll_for(&mut feed, Duration::from_millis(500))
+        .await
+        .iter()
+        .map(|event| event.new.as_ref().unwrap()["body"].as_str().unwrap().to_string())
+        .collect();
+
+    assert_eq!(bodies, ["late", "early"]);
+}
@@ -0,0 +1,281 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Encryption at rest integration tests.
+
+mod common;
+
+use common::TestDb;
+use dbproject::{
+    chat::{ProfileData, UserSettingKV},
+    db::{
+        area::Area,
+        collection::DocumentPatch,
+        crypto::{is_envelope, key_id, Cipher, LocalKeyring, MasterKey},
+        docdb::{profile_schema, DocDBManager},
+        error::DbError,
+        kvdb::{KeyValueDBManager, Namespace, MAX_VALUE_LENGTH},
+        search::{self, ProfileQuery},
+    },
+};
+use serde_json::json;
+
+fn keyring() -> LocalKeyring {
+    LocalKeyring::new("k1", MasterKey::generate()).unwrap()
+}
+
+#[test]
+fn envelopes_round_trip_and_are_bound_to_context() {
+    let cipher   = Cipher::new(keyring());
+    let envelope = cipher.encrypt("s3cr3t", "user/1/api_token").unwrap();
+
+    assert!(is_envelope(&envelope));
+    assert_eq!(key_id(&envelope), Some("k1"));
+    assert!(!envelope.contains("s3cr3t"));
+    assert_ne!(cipher.encrypt("s3cr3t", "user/1/api_token").unwrap(), envelope);
+
+    assert_eq!(cipher.decrypt(&envelope, "user/1/api_token").unwrap(), "s3cr3t");
+    assert!(matches!(cipher.decrypt(&envelope, "user/2/api_token"), Err(DbError::Crypto(_))));
+
+    let mut tampered = envelope.clone();
+    let last         = if tampered.ends_with('A') { "B" } else { "A" };
+    tampered.replace_range(tampered.len() - 1.., last);
+    assert!(matches!(cipher.decrypt(&tampered, "user/1/api_token"), Err(DbError::Crypto(_))));
+
+    assert!(matches!(cipher.decrypt("enc1:k1:oops", "user/1/api_token"), Err(DbError::Crypto(_))));
+    assert!(!is_envelope("plain value"));
+    assert_eq!(key_id("plain value"), None);
+
+    let other = Cipher::new(LocalKeyring::new("k1", MasterKey::generate()).unwrap());
+    assert!(matches!(other.decrypt(&envelope, "user/1/api_token"), Err(DbError::Crypto(_))));
+}
+
+#[test]
+fn keyring_rotates_and_persists_keys() {
+    let mut keyring = keyring();
+    let old         = Cipher::new(keyring.clone()).encrypt("value", "ctx").unwrap();
+
+    keyring.rotate("k2", MasterKey::generate()).unwrap();
+    assert!(keyring.rotate("k2", MasterKey::generate()).is_err());
+    assert!(keyring.rotate("bad:id", MasterKey::generate()).is_err());
+
+    let cipher = Cipher::new(keyring.clone());
+    assert_eq!(cipher.active_key(), "k2");
+    assert!(cipher.needs_rotation(&old));
+    assert!(cipher.needs_rotation("plain value"));
+    assert!(!cipher.needs_rotation(&cipher.encrypt("value", "ctx").unwrap()));
+    assert_eq!(cipher.decrypt(&old, "ctx").unwrap(), "value");
+
+    let path = std::env::temp_dir().join(format!("dbproject_keyring_{:08x}.json", rand::random::<u32>()));
+    keyring.save(&path).unwrap();
+
+    let loaded = LocalKeyring::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(Cipher::new(loaded).decrypt(&old, "ctx").unwrap(), "value");
+    assert!(matches!(LocalKeyring::load(&path), Err(DbError::Io(_))));
+
+    let key = MasterKey::generate();
+    assert_eq!(MasterKey::from_base64(&key.to_base64()).unwrap(), key);
+    assert!(MasterKey::from_bytes(&[0; 16]).is_err());
+    assert_eq!(format!("{key:?}"), "MasterKey(..)");
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn kv_values_are_encrypted_at_rest_and_rotated() {
+    let mut test_db = require_mysql!();
+
+    let config      = test_db.database("KeyValueDB_Encrypted");
+    let mut keyring = keyring();
+
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(config.clone()).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+
+    let pool  = TestDb::pool(&config).await;
+    let kv_db = kv_db.with_encryption(Cipher::new(keyring.clone()), &["api_token", "webhook_secret"]);
+    let ns    = Namespace::User(20300);
+
+    kv_db.set(&ns, "api_token", "tok_123").await.unwrap();
+    kv_db.set(&ns, "theme", "dark").await.unwrap();
+
+    let stored = |key: &'static str| {
+        let pool = pool.clone();
+
+        async move {
+            let query = "SELECT setting_value FROM User_Settings_KV WHERE user_id = 20300 AND setting_name = ?;";
+            let (value,): (String,) = sqlx::query_as(query).bind(key).fetch_one(&pool).await.unwrap();
+            value
+        }
+    };
+
+    assert_eq!(key_id(&stored("api_token").await), Some("k1"));
+    assert_eq!(stored("theme").await, "dark");
+
+    assert_eq!(kv_db.get(&ns, "api_token").await.unwrap().as_deref(), Some("tok_123"));
+    assert_eq!(kv_db.get_user_setting(20300, "api_token".to_string()).await.unwrap(), "tok_123");
+    assert_eq!(
+        kv_db.get_all_user_settings(20300).await.unwrap(),
+        Some(json!({ "api_token": "tok_123", "theme": "dark" }))
+    );
+
+    assert!(kv_db.compare_and_set(&ns, "api_token", Some("tok_123"), "tok_456").await.unwrap());
+    assert!(!kv_db.compare_and_set(&ns, "api_token", Some("tok_123"), "tok_789").await.unwrap());
+    assert_eq!(kv_db.get(&ns, "api_token").await.unwrap().as_deref(), Some("tok_456"));
+    assert!(matches!(kv_db.incr(&ns, "api_token", 1).await, Err(DbError::InvalidValue(_))));
+
+    // Envelopes of values up to maximum length fit value column.
+    let long   = "ж".repeat(MAX_VALUE_LENGTH);
+    let result = kv_db.set(&ns, "webhook_secret", &format!("{long}ж")).await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+
+    kv_db.set(&ns, "webhook_secret", &long).await.unwrap();
+    assert_eq!(kv_db.get(&ns, "webhook_secret").await.unwrap(), Some(long.clone()));
+
+    // Legacy plaintext & values of previous key are re-encrypted.
+    kv_db.add_user_setting(&UserSettingKV {
+        user_id:        20301,
+        settings_name:  "webhook_secret".to_string(),
+        settings_value: "whsec".to_string(),
+    }).await.unwrap();
+
+    sqlx::query("INSERT INTO Server_Settings_KV (setting_name, setting_value) VALUES ('webhook_secret', 'plain');")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    keyring.rotate("k2", MasterKey::generate()).unwrap();
+    let kv_db = kv_db.with_encryption(Cipher::new(keyring), &["api_token", "webhook_secret"]);
+
+    assert_eq!(kv_db.get(&Namespace::Server, "webhook_secret").await.unwrap().as_deref(), Some("plain"));
+    assert_eq!(kv_db.rotate_encryption(1).await.unwrap(), 4);
+    assert_eq!(kv_db.rotate_encryption(1).await.unwrap(), 0);
+
+    assert_eq!(key_id(&stored("api_token").await), Some("k2"));
+    assert_eq!(kv_db.get(&ns, "api_token").await.unwrap().as_deref(), Some("tok_456"));
+    assert_eq!(kv_db.get(&ns, "webhook_secret").await.unwrap(), Some(long));
+    assert_eq!(kv_db.get(&Namespace::User(20301), "webhook_secret").await.unwrap().as_deref(), Some("whsec"));
+    assert_eq!(kv_db.get(&Namespace::Server, "webhook_secret").await.unwrap().as_deref(), Some("plain"));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_fields_are_encrypted_at_rest_and_rotated() {
+    let mut test_db = require_mysql!();
+
+    let config      = test_db.database("DocumentDB_Encrypted");
+    let mut keyring = keyring();
+
+    let mut doc_db = DocDBManager::new();
+    doc_db.connect(config.clone()).await.unwrap();
+    doc_db.set_procedures().await.unwrap();
+
+    let pool   = TestDb::pool(&config).await;
+    let doc_db = doc_db.with_encryption(Cipher::new(keyring.clone()), &["location"]);
+
+    let data = ProfileData {
+        bio:      "Hello".to_string(),
+        location: Some("Oslo".to_string()),
+        ..Default::default()
+    };
+
+    let profile_id = doc_db.create_profile(20400, &data).await.unwrap();
+    assert_eq!(doc_db.get_profile(profile_id).await.unwrap().unwrap().data().unwrap(), data);
+
+    let columns = "SELECT profile_data->>'$.bio', profile_data->>'$.location' FROM User_Profiles WHERE profile_id = ?;";
+    let (bio, location): (String, String) = sqlx::query_as(columns)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(bio, "Hello");
+    assert_eq!(key_id(&location), Some("k1"));
+
+    keyring.rotate("k2", MasterKey::generate()).unwrap();
+    let doc_db = doc_db.with_encryption(Cipher::new(keyring.clone()), &["location"]);
+
+    assert_eq!(doc_db.rotate_encryption(10).await.unwrap(), 1);
+    assert_eq!(doc_db.rotate_encryption(10).await.unwrap(), 0);
+
+    let (_, location): (String, String) = sqlx::query_as(columns)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(key_id(&location), Some("k2"));
+    assert_eq!(doc_db.get_profile_by_user(20400).await.unwrap().unwrap().location, "Oslo");
+
+    // CHECK constraint leaves encrypted fields to validation of plaintext.
+    let mut doc_db   = doc_db.with_encryption(Cipher::new(keyring), &["bio", "location"]);
+    let mut profiles = doc_db.profiles().unwrap();
+    doc_db.set_schema(&mut profiles, profile_schema()).await.unwrap();
+
+    let data = ProfileData { bio: "x".repeat(500), ..Default::default() };
+    assert!(doc_db.update_profile(profile_id, &data).await.unwrap());
+    assert_eq!(doc_db.get_profile(profile_id).await.unwrap().unwrap().data().unwrap(), data);
+
+    let data = ProfileData { bio: "x".repeat(501), ..Default::default() };
+    assert!(matches!(doc_db.update_profile(profile_id, &data).await, Err(DbError::Validation(_))));
+
+    // Patch, versioned & revision methods see plaintext as well.
+    let patch   = DocumentPatch::Merge(json!({ "location": "Bergen" }));
+    let patched = doc_db.patch(profile_id, &patch).await.unwrap().unwrap();
+    assert_eq!(patched, json!({ "bio": "x".repeat(500), "location": "Bergen" }));
+
+    let patch  = DocumentPatch::Merge(json!({ "bio": "x".repeat(501) }));
+    let result = doc_db.patch(profile_id, &patch).await;
+    assert!(matches!(result, Err(DbError::Validation(_))));
+
+    let versioned = doc_db.get_versioned(profile_id).await.unwrap().unwrap();
+    assert_eq!(versioned.data, patched);
+
+    let doc = json!({ "bio": "Hi", "location": "Oslo" });
+    doc_db.update_if_version(profile_id, versioned.version, &doc).await.unwrap();
+
+    let (bio, location): (String, String) = sqlx::query_as(columns)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert!(is_envelope(&bio) && is_envelope(&location));
+
+    // Encrypted fields are kept out of profile columns & full-text search.
+    let query = "SELECT bio, location FROM User_Profiles WHERE profile_id = ?;";
+    let (bio, location): (Option<String>, Option<String>) = sqlx::query_as(query)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!((bio, location), (None, None));
+
+    search::create_search_indexes(&pool).await.unwrap();
+    let hits = search::search_profiles(&pool, Area::Unknown, &ProfileQuery::new("Oslo").user(20400)).await.unwrap();
+    assert!(hits.is_empty());
+
+    let revisions = doc_db.revisions(profile_id).await.unwrap();
+    assert!(revisions.iter().all(|revision| !json!(revision.doc).to_string().contains("enc1:")));
+    assert_eq!(revisions.last().unwrap().doc, Some(patched.clone()));
+
+    let last = revisions.last().unwrap().revision;
+    assert_eq!(doc_db.restore_revision(profile_id, last, None).await.unwrap(), patched);
+    assert!(doc_db.diff_revisions(profile_id, last, None).await.unwrap().0.is_empty());
+    assert_eq!(doc_db.get_profile(profile_id).await.unwrap().unwrap().location, "Bergen");
+}
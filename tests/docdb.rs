@@ -0,0 +1,803 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Document-oriented database manager integration tests.
+
+mod common;
+
+use common::TestDb;
+use dbproject::{
+    chat::{ProfileData, UserProfile},
+    db::{
+        aggregate::{Pipeline, SortOrder},
+        collection::{etag, Collection, Document, DocumentPatch, Filter, IfMatch, IndexKind},
+        docdb::{profile_schema, DocDBManager},
+        error::DbError,
+        schema::Schema,
+        CrudOps,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct City {
+    name: String,
+    country: String,
+    population: i64,
+}
+
+fn city(name: &str, country: &str, population: i64) -> City {
+    City { name: name.to_string(), country: country.to_string(), population }
+}
+
+async fn setup(test_db: &mut TestDb) -> (DocDBManager, MySqlPool) {
+    let config = test_db.database("DocumentDB_JSON");
+
+    let mut doc_db = DocDBManager::new();
+    doc_db.connect(config.clone()).await.unwrap();
+    doc_db.set_procedures().await.unwrap();
+
+    (doc_db, TestDb::pool(&config).await)
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_data_procedures_store_and_read_fields() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    let added = UserProfile {
+        user_id:             4242,
+        bio:                 "Very cool user".to_string(),
+        profile_picture_url: "https://example.com/image.png".to_string(),
+        location:            "Moscow".to_string(),
+        ..Default::default()
+    };
+
+    let profile_id = doc_db.add_user_profile_data(&added).await.unwrap();
+
+    let (user_id,): (i64,) = sqlx::query_as("SELECT user_id FROM User_Profiles WHERE profile_id = ?;")
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(user_id, 4242);
+
+    let stored = doc_db.get_user_profile_data(profile_id).await.unwrap();
+    assert_eq!(stored.profile_id, profile_id);
+    assert_eq!(stored.bio, added.bio);
+    assert_eq!(stored.profile_picture_url, added.profile_picture_url);
+    assert_eq!(stored.location, added.location);
+
+    assert!(matches!(
+        doc_db.get_user_profile_data(999999).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+}
+
+#[test]
+fn profile_data_keeps_extra_fields() {
+    let doc  = json!({ "bio": "Hi", "location": null, "theme": "dark" });
+    let data = serde_json::from_value::<ProfileData>(doc).unwrap();
+
+    assert_eq!(data.bio, "Hi");
+    assert_eq!(data.location, None);
+    assert_eq!(data.extra["theme"], json!("dark"));
+    assert_eq!(serde_json::to_value(&data).unwrap(), json!({ "bio": "Hi", "theme": "dark" }));
+
+    let profile = UserProfile::from_data(1, 2, json!({ "bio": "Hi", "location": "Paris" })).unwrap();
+    assert_eq!(profile.location, "Paris");
+    assert_eq!(profile.profile_picture_url, "");
+    assert!(UserProfile::from_data(1, 2, json!({ "bio": 5 })).is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_api_keeps_columns_in_sync_with_data() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    let mut data = ProfileData {
+        bio:      "First bio".to_string(),
+        location: Some("Oslo".to_string()),
+        ..Default::default()
+    };
+
+    data.extra.insert("theme".to_string(), json!("dark"));
+
+    let profile_id = doc_db.create_profile(5150, &data).await.unwrap();
+    let profile    = doc_db.get_profile_by_user(5150).await.unwrap().unwrap();
+
+    assert_eq!(profile.profile_id, profile_id);
+    assert_eq!(profile.data().unwrap(), data);
+    assert!(doc_db.get_profile_by_user(999999).await.unwrap().is_none());
+
+    let columns = "SELECT bio, profile_picture_url, location FROM User_Profiles WHERE profile_id = ?;";
+    let row: (Option<String>, Option<String>, Option<String>) = sqlx::query_as(columns)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(row, (Some("First bio".to_string()), None, Some("Oslo".to_string())));
+
+    data.bio      = "Second bio".to_string();
+    data.location = None;
+    assert!(doc_db.update_profile(profile_id, &data).await.unwrap());
+
+    let row: (Option<String>, Option<String>, Option<String>) = sqlx::query_as(columns)
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(row, (Some("Second bio".to_string()), None, None));
+    assert_eq!(doc_db.get_profile(profile_id).await.unwrap().unwrap().bio, "Second bio");
+
+    let mut profile = doc_db.get_profile(profile_id).await.unwrap().unwrap();
+    profile.profile_data = json!({ "bio": "Third bio" });
+    profile.update(&pool).await.unwrap();
+
+    let (bio,): (String,) = sqlx::query_as("SELECT bio FROM User_Profiles WHERE profile_id = ?;")
+        .bind(profile_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(bio, "Third bio");
+
+    assert!(doc_db.delete_profile(profile_id).await.unwrap());
+    assert!(!doc_db.delete_profile(profile_id).await.unwrap());
+    assert!(doc_db.get_profile(profile_id).await.unwrap().is_none());
+    assert!(!doc_db.update_profile(profile_id, &data).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn update_trigger_copies_columns_into_profile_data() {
+    let mut test_db = require_mysql!();
+    let (_doc_db, pool) = setup(&mut test_db).await;
+
+    let (profile_id,): (i64,) = sqlx::query_as("SELECT MAX(profile_id) FROM User_Profiles;")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let query = "UPDATE User_Profiles SET bio = ?, profile_picture_url = ?, location = ? \
+                 WHERE profile_id = ?;";
+
+    sqlx::query(query)
+        .bind("Updated bio")
+        .bind("https://example.com/updated.png")
+        .bind("Boston")
+        .bind(profile_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (profile_data,): (serde_json::Value,) =
+        sqlx::query_as("SELECT profile_data FROM User_Profiles WHERE profile_id = ?;")
+            .bind(profile_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(profile_data["bio"], json!("Updated bio"));
+    assert_eq!(profile_data["profile_picture_url"], json!("https://example.com/updated.png"));
+    assert_eq!(profile_data["location"], json!("Boston"));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn count_messages_by_channel_groups_message_documents() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    // Random messages use channel identifiers below 10000.
+    for channel_id in [20001, 20001, 20002] {
+        sqlx::query("INSERT INTO Message (channel_id, message_data) VALUES (?, ?);")
+            .bind(channel_id)
+            .bind(json!({ "channel_id": channel_id }))
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    let counts = doc_db.count_messages_by_channel().await.unwrap();
+
+    assert!(counts.contains(&("20001".to_string(), 2)));
+    assert!(counts.contains(&("20002".to_string(), 1)));
+
+    // Grouping reads channel index instead of scanning message documents.
+    let explain = doc_db.explain_count_messages_by_channel().await.unwrap();
+    let plan    = explain.plan.to_string();
+
+    assert!(plan.contains(r#""key":"jx_channel_id""#), "{plan}");
+    assert!(!plan.contains(r#""access_type":"ALL""#), "{plan}");
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn collection_stores_and_finds_documents() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let cities = doc_db.create_collection("cities").await.unwrap();
+
+    let moscow = cities.insert(&city("Moscow", "Russia", 13_000_000)).await.unwrap();
+    let kazan  = cities.insert(&city("Kazan", "Russia", 1_300_000)).await.unwrap();
+    let boston = cities.insert(&city("Boston", "USA", 650_000)).await.unwrap();
+
+    assert_eq!(cities.get::<City>(moscow).await.unwrap(), Some(city("Moscow", "Russia", 13_000_000)));
+    assert_eq!(cities.get::<City>(999_999).await.unwrap(), None);
+
+    let russian: Vec<Document<City>> = cities
+        .find(&[Filter::eq("$.country", "Russia")])
+        .await
+        .unwrap();
+
+    assert_eq!(russian.iter().map(|doc| doc.id).collect::<Vec<_>>(), vec![moscow, kazan]);
+
+    let large: Vec<Document<City>> = cities
+        .find(&[Filter::eq("$.country", "Russia"), Filter::gt("$.population", 2_000_000)])
+        .await
+        .unwrap();
+
+    assert_eq!(large.len(), 1);
+    assert_eq!(large[0].data.name, "Moscow");
+
+    assert!(cities.replace(boston, &city("Boston", "USA", 675_000)).await.unwrap());
+    assert_eq!(cities.get::<City>(boston).await.unwrap().unwrap().population, 675_000);
+    assert!(!cities.replace(999_999, &city("Nowhere", "", 0)).await.unwrap());
+
+    assert!(cities.delete(kazan).await.unwrap());
+    assert!(!cities.delete(kazan).await.unwrap());
+    assert_eq!(cities.find::<Value>(&[]).await.unwrap().len(), 2);
+
+    doc_db.drop_collection("cities").await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn messages_collection_uses_message_data() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let messages = doc_db.messages().unwrap();
+    let id = messages
+        .insert(&json!({ "channel_id": 20001, "message_text": "hello" }))
+        .await
+        .unwrap();
+
+    let found: Vec<Document<Value>> = messages
+        .find(&[Filter::eq("$.channel_id", 20001)])
+        .await
+        .unwrap();
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, id);
+    assert_eq!(found[0].data["message_text"], json!("hello"));
+}
+
+#[tokio::test]
+async fn collection_rejects_invalid_names_and_paths() {
+    let doc_db = DocDBManager::new();
+
+    assert!(matches!(doc_db.collection("bad name"), Err(DbError::InvalidName(_))));
+    assert!(matches!(doc_db.collection("1st"), Err(DbError::InvalidName(_))));
+
+    // Paths are validated before query is sent, so pool is never connected.
+    let pool       = MySqlPool::connect_lazy("mysql://localhost/unused").unwrap();
+    let collection = Collection::new(pool, "Collection_cities", "id", "doc").unwrap();
+
+    for path in ["location", "$.", "$..name", "$[x]", "$.name'; DROP TABLE x"] {
+        let result = collection.find::<Value>(&[Filter::eq(path, 1)]).await;
+        assert!(matches!(result, Err(DbError::InvalidPath(_))), "{path}");
+
+        let result = collection.create_index("name", path, IndexKind::Integer).await;
+        assert!(matches!(result, Err(DbError::InvalidPath(_))), "{path}");
+    }
+
+    let result = collection.create_index("bad name", "$.name", IndexKind::Integer).await;
+    assert!(matches!(result, Err(DbError::InvalidName(_))));
+}
+
+#[test]
+fn document_patch_applies_merge_and_json_patch() {
+    let mut doc = json!({ "bio": "old", "location": "Moscow", "tags": ["a"] });
+
+    DocumentPatch::Merge(json!({ "bio": "new", "location": null }))
+        .apply(&mut doc)
+        .unwrap();
+
+    assert_eq!(doc, json!({ "bio": "new", "tags": ["a"] }));
+
+    DocumentPatch::json(json!([
+        { "op": "test",    "path": "/bio",    "value": "new" },
+        { "op": "add",     "path": "/tags/-", "value": "b" },
+        { "op": "move",    "from": "/bio",    "path": "/about" },
+        { "op": "replace", "path": "/tags/0", "value": "z" },
+    ]))
+        .unwrap()
+        .apply(&mut doc)
+        .unwrap();
+
+    assert_eq!(doc, json!({ "about": "new", "tags": ["z", "b"] }));
+}
+
+#[test]
+fn document_patch_failed_test_leaves_document_unchanged() {
+    let mut doc  = json!({ "bio": "old", "tags": [] });
+    let original = doc.clone();
+
+    let patch = DocumentPatch::json(json!([
+        { "op": "remove", "path": "/tags" },
+        { "op": "test",   "path": "/bio", "value": "stale" },
+    ]))
+        .unwrap();
+
+    assert!(matches!(patch.apply(&mut doc), Err(DbError::TestFailed(path)) if path == "/bio"));
+    assert_eq!(doc, original);
+
+    let patch = DocumentPatch::json(json!([{ "op": "remove", "path": "/missing" }])).unwrap();
+    assert!(matches!(patch.apply(&mut doc), Err(DbError::Patch(_))));
+    assert_eq!(doc, original);
+
+    assert!(matches!(DocumentPatch::json(json!([{ "op": "unknown" }])), Err(DbError::Json(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn collection_patch_is_atomic() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let cities = doc_db.create_collection("cities").await.unwrap();
+    let kazan  = cities.insert(&city("Kazan", "Russia", 1_300_000)).await.unwrap();
+
+    let patch = DocumentPatch::json(json!([
+        { "op": "test",    "path": "/population", "value": 1_300_000 },
+        { "op": "replace", "path": "/population", "value": 1_310_000 },
+    ]))
+        .unwrap();
+
+    let patched = cities.patch(kazan, &patch).await.unwrap().unwrap();
+    assert_eq!(patched["population"], json!(1_310_000));
+
+    // Same patch is now stale: test fails and document is left unchanged.
+    let result = cities.patch(kazan, &patch).await;
+    assert!(matches!(result, Err(DbError::TestFailed(_))));
+    assert_eq!(cities.get::<City>(kazan).await.unwrap().unwrap().population, 1_310_000);
+
+    assert_eq!(cities.patch(999_999, &patch).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_patch_syncs_profile_columns() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    let (profile_id,): (i64,) = sqlx::query_as("SELECT MAX(profile_id) FROM User_Profiles;")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let patch   = DocumentPatch::Merge(json!({ "bio": "Patched bio", "theme": "dark" }));
+    let patched = doc_db.patch(profile_id, &patch).await.unwrap().unwrap();
+
+    assert_eq!(patched["bio"], json!("Patched bio"));
+    assert_eq!(patched["theme"], json!("dark"));
+
+    let (bio, profile_data): (Option<String>, Value) =
+        sqlx::query_as("SELECT bio, profile_data FROM User_Profiles WHERE profile_id = ?;")
+            .bind(profile_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    assert_eq!(bio.as_deref(), Some("Patched bio"));
+    assert_eq!(profile_data, patched);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn collection_indexes_are_used_by_find() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let cities = doc_db.create_collection("cities").await.unwrap();
+
+    for i in 0..50 {
+        let country = if i % 2 == 0 { "Russia" } else { "USA" };
+        cities.insert(&city(&format!("city_{i}"), country, i * 1000)).await.unwrap();
+    }
+
+    let filters = [Filter::eq("$.country", "USA"), Filter::gte("$.population", 40_000)];
+    let before  = cities.find::<City>(&filters).await.unwrap();
+
+    let population = cities.create_index("population", "$.population", IndexKind::Integer).await.unwrap();
+    let country    = cities.create_index("country", "$.country", IndexKind::String { length: 32 }).await.unwrap();
+
+    assert_eq!(cities.indexes().await.unwrap(), vec![population, country]);
+
+    let after = cities.find::<City>(&filters).await.unwrap();
+    assert_eq!(after, before);
+    assert_eq!(after.len(), 5);
+
+    let explain = cities.explain(&filters).await.unwrap();
+    assert_eq!(explain.indexes, vec!["country".to_string(), "population".to_string()]);
+    assert!(explain.plan.to_string().contains("jx_"));
+
+    // Non-equality string filters are not answered by index.
+    let explain = cities.explain(&[Filter::gt("$.country", "Russia")]).await.unwrap();
+    assert!(explain.indexes.is_empty());
+
+    assert!(cities.drop_index("country").await.unwrap());
+    assert!(!cities.drop_index("country").await.unwrap());
+    assert_eq!(cities.indexes().await.unwrap().len(), 1);
+    assert_eq!(cities.find::<City>(&filters).await.unwrap(), before);
+}
+
+#[test]
+fn schema_reports_failing_paths() {
+    let schema = Schema::new(profile_schema()).unwrap();
+
+    schema.validate(&json!({ "bio": "Hello", "profile_picture_url": "https://example.com/a.png" })).unwrap();
+
+    let doc = json!({ "bio": "x".repeat(501), "profile_picture_url": "not a uri", "location": 1 });
+
+    let Err(DbError::Validation(err)) = schema.validate(&doc) else {
+        panic!("document must be invalid");
+    };
+
+    let mut paths = err.paths();
+    paths.sort();
+
+    assert_eq!(paths, vec!["/bio", "/location", "/profile_picture_url"]);
+    assert!(err.issues.iter().any(|issue| issue.schema_path == "/properties/bio/maxLength"));
+
+    assert!(matches!(Schema::new(json!({ "type": 1 })), Err(DbError::InvalidSchema(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn collection_schema_rejects_invalid_documents() {
+    let mut test_db = require_mysql!();
+    let (mut doc_db, pool) = setup(&mut test_db).await;
+
+    let mut profiles = doc_db.profiles().unwrap();
+    let installed    = doc_db.set_schema(&mut profiles, profile_schema()).await.unwrap();
+
+    let (profile_id,): (i64,) = sqlx::query_as("SELECT MAX(profile_id) FROM User_Profiles;")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let patch  = DocumentPatch::Merge(json!({ "bio": "x".repeat(501) }));
+    let result = doc_db.patch(profile_id, &patch).await;
+
+    assert!(matches!(&result, Err(DbError::Validation(err)) if err.paths() == vec!["/bio"]));
+
+    let patch = DocumentPatch::Merge(json!({ "bio": "Valid bio" }));
+    assert!(doc_db.patch(profile_id, &patch).await.is_ok());
+
+    if installed {
+        // Server rejects invalid documents written bypassing Rust validation.
+        let result = sqlx::query("UPDATE User_Profiles SET profile_data = ? WHERE profile_id = ?;")
+            .bind(json!({ "bio": 42 }))
+            .bind(profile_id)
+            .execute(&pool)
+            .await;
+
+        assert!(result.is_err());
+
+        // Failed change keeps previous schema & its constraint.
+        let strict = json!({ "type": "object", "required": ["bio", "nickname"] });
+        assert!(doc_db.set_schema(&mut profiles, strict).await.is_err());
+
+        assert_eq!(profiles.schema().unwrap().json(), &profile_schema());
+        assert_eq!(doc_db.profiles().unwrap().schema().unwrap().json(), &profile_schema());
+
+        let (stored,): (Value,) = sqlx::query_as("SELECT json_schema FROM Document_Schemas WHERE table_name = 'User_Profiles';")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(stored, profile_schema());
+
+        let result = sqlx::query("UPDATE User_Profiles SET profile_data = ? WHERE profile_id = ?;")
+            .bind(json!({ "bio": 42 }))
+            .bind(profile_id)
+            .execute(&pool)
+            .await;
+
+        assert!(result.is_err());
+
+        // Replaced constraint is dropped.
+        assert!(doc_db.set_schema(&mut profiles, profile_schema()).await.unwrap());
+
+        let query =
+            r#"
+            SELECT COUNT(*) FROM information_schema.TABLE_CONSTRAINTS
+            WHERE CONSTRAINT_SCHEMA = DATABASE() AND TABLE_NAME = 'User_Profiles'
+                AND CONSTRAINT_NAME IN ('User_Profiles_schema', 'User_Profiles_schema2');
+            "#;
+
+        let (checks,): (i64,) = sqlx::query_as(query).fetch_one(&pool).await.unwrap();
+        assert_eq!(checks, 1);
+    }
+
+    // Schema is restored on reconnect.
+    let mut reconnected = DocDBManager::new();
+    reconnected.connect(test_db.database("DocumentDB_JSON")).await.unwrap();
+    assert!(reconnected.profiles().unwrap().schema().is_some());
+
+    assert!(doc_db.remove_schema(&mut profiles).await.unwrap());
+    assert!(profiles.schema().is_none());
+    assert!(doc_db.profiles().unwrap().schema().is_none());
+}
+
+#[tokio::test]
+async fn pipeline_compiles_to_sql() {
+    let pool       = MySqlPool::connect_lazy("mysql://localhost/unused").unwrap();
+    let collection = Collection::new(pool, "Collection_orders", "id", "doc").unwrap();
+
+    let pipeline = Pipeline::new()
+        .unwind("$.items")
+        .filter(Filter::eq("$.status", "paid"))
+        .group("product", "$.items.product")
+        .count("orders")
+        .sum("revenue", "$.items.price")
+        .sort("$.revenue", SortOrder::Desc)
+        .limit(3);
+
+    let (query, args) = pipeline.compile(&collection).unwrap();
+
+    assert!(query.contains("JSON_TABLE(d.`doc`, '$.items[*]'"));
+    assert!(query.contains("JSON_EXTRACT(d.`doc`, '$.status') = CAST(? AS JSON)"));
+    assert!(query.contains("'product', JSON_EXTRACT(u0.value, '$.product')"));
+    assert!(query.contains("GROUP BY JSON_EXTRACT(u0.value, '$.product')"));
+    assert!(query.ends_with("ORDER BY JSON_EXTRACT(t.result, '$.revenue') DESC LIMIT 3"));
+    assert_eq!(args, vec!["\"paid\"".to_string()]);
+
+    let invalid = Pipeline::new().group("bad name", "$.status");
+    assert!(matches!(invalid.compile(&collection), Err(DbError::InvalidName(_))));
+
+    let invalid = Pipeline::new().sum("total", "$.price'); DROP TABLE x; --");
+    assert!(matches!(invalid.compile(&collection), Err(DbError::InvalidPath(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn pipeline_groups_and_unwinds_documents() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let orders = doc_db.create_collection("orders").await.unwrap();
+
+    let docs = [
+        json!({ "status": "paid", "items": [{ "product": "tea", "price": 3 }, { "product": "cake", "price": 5 }] }),
+        json!({ "status": "paid", "items": [{ "product": "tea", "price": 4 }] }),
+        json!({ "status": "new",  "items": [{ "product": "cake", "price": 7 }] }),
+    ];
+
+    for doc in &docs {
+        orders.insert(doc).await.unwrap();
+    }
+
+    let pipeline = Pipeline::new()
+        .unwind("$.items")
+        .filter(Filter::eq("$.status", "paid"))
+        .group("product", "$.items.product")
+        .count("sold")
+        .sum("revenue", "$.items.price")
+        .max("max_price", "$.items.price")
+        .sort("$.revenue", SortOrder::Desc);
+
+    let rows = orders.aggregate(&pipeline).await.unwrap();
+
+    assert_eq!(rows, vec![
+        json!({ "product": "tea",  "sold": 2, "revenue": 7.0, "max_price": 4.0 }),
+        json!({ "product": "cake", "sold": 1, "revenue": 5.0, "max_price": 5.0 }),
+    ]);
+
+    // Without group stage rows are documents with unwound elements.
+    let pipeline = Pipeline::new()
+        .unwind("$.items")
+        .filter(Filter::gte("$.items.price", 5))
+        .sort("$.items.price", SortOrder::Asc)
+        .limit(1);
+
+    let rows = orders.aggregate(&pipeline).await.unwrap();
+    assert_eq!(rows, vec![json!({ "status": "paid", "items": { "product": "cake", "price": 5 } })]);
+
+    // Accumulators without keys aggregate whole collection.
+    let rows = orders.aggregate(&Pipeline::new().count("orders")).await.unwrap();
+    assert_eq!(rows, vec![json!({ "orders": 3 })]);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn aggregate_messages_counts_by_channel() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let messages = doc_db.messages().unwrap();
+
+    for channel_id in [30001, 30001, 30002] {
+        messages.insert(&json!({ "channel_id": channel_id })).await.unwrap();
+    }
+
+    let pipeline = Pipeline::new()
+        .filter(Filter::gt("$.channel_id", 30000))
+        .group("channel_id", "$.channel_id")
+        .count("messages")
+        .sort("$.messages", SortOrder::Desc);
+
+    let rows = doc_db.aggregate_messages(&pipeline).await.unwrap();
+
+    assert_eq!(rows, vec![
+        json!({ "channel_id": 30001, "messages": 2 }),
+        json!({ "channel_id": 30002, "messages": 1 }),
+    ]);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_revisions_are_listed_diffed_and_restored() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    let (profile_id, original): (i64, Value) =
+        sqlx::query_as("SELECT profile_id, profile_data FROM User_Profiles ORDER BY profile_id DESC LIMIT 1;")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+    let profiles = doc_db.profiles().unwrap().with_author(Some("alice"));
+    let patch    = DocumentPatch::Merge(json!({ "bio": "First edit" }));
+    let first    = profiles.patch(profile_id, &patch).await.unwrap().unwrap();
+
+    let patch  = DocumentPatch::Merge(json!({ "location": "Paris" }));
+    let second = doc_db.patch(profile_id, &patch).await.unwrap().unwrap();
+
+    let revisions = doc_db.revisions(profile_id).await.unwrap();
+
+    assert_eq!(revisions.len(), 2);
+    assert_eq!((revisions[0].revision, revisions[0].author.as_deref()), (1, Some("alice")));
+    assert_eq!((revisions[1].revision, revisions[1].author.as_deref()), (2, None));
+    assert_eq!(revisions[0].doc.as_ref(), Some(&original));
+    assert_eq!(revisions[1].doc.as_ref(), Some(&first));
+
+    let diff = doc_db.diff_revisions(profile_id, 1, None).await.unwrap();
+    let mut doc = original.clone();
+    DocumentPatch::Json(diff).apply(&mut doc).unwrap();
+    assert_eq!(doc, second);
+
+    let restored = doc_db.restore_revision(profile_id, 1, Some("bob")).await.unwrap();
+    assert_eq!(restored, original);
+    assert_eq!(doc_db.profiles().unwrap().get::<Value>(profile_id).await.unwrap(), Some(original));
+
+    let revisions = doc_db.revisions(profile_id).await.unwrap();
+    assert_eq!(revisions.len(), 3);
+    assert_eq!(revisions[2].author.as_deref(), Some("bob"));
+    assert_eq!(revisions[2].doc.as_ref(), Some(&second));
+
+    assert!(doc_db.revision(profile_id, 4).await.unwrap().is_none());
+    assert!(matches!(
+        doc_db.restore_revision(profile_id, 4, None).await,
+        Err(DbError::Sqlx(sqlx::Error::RowNotFound))
+    ));
+}
+
+#[test]
+fn if_match_parses_etags() {
+    assert_eq!(etag(3), "\"3\"");
+    assert_eq!(IfMatch::parse("\"3\"").unwrap(), IfMatch::Version(3));
+    assert_eq!(IfMatch::parse(" W/\"7\" ").unwrap(), IfMatch::Version(7));
+    assert_eq!(IfMatch::parse("*").unwrap(), IfMatch::Any);
+
+    for header in ["3", "\"three\"", "\"3", ""] {
+        assert!(matches!(IfMatch::parse(header), Err(DbError::InvalidETag(_))), "{header}");
+    }
+
+    assert!(IfMatch::Any.matches(1));
+    assert!(IfMatch::Version(2).matches(2));
+    assert!(!IfMatch::Version(2).matches(3));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profile_updates_are_version_checked() {
+    let mut test_db = require_mysql!();
+    let (doc_db, pool) = setup(&mut test_db).await;
+
+    let (profile_id,): (i64,) = sqlx::query_as("SELECT MAX(profile_id) FROM User_Profiles;")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    let current = doc_db.get_versioned(profile_id).await.unwrap().unwrap();
+    assert_eq!(current.version, 1);
+
+    let mut doc = current.data.clone();
+    doc["bio"]  = json!("Edited by first client");
+
+    let version = doc_db.update_if_version(profile_id, current.version, &doc).await.unwrap();
+    assert_eq!(version, 2);
+
+    // Second client still holds version 1.
+    let mut stale = current.data.clone();
+    stale["bio"]  = json!("Edited by second client");
+
+    let result = doc_db.update_if_version(profile_id, current.version, &stale).await;
+    assert!(matches!(result, Err(DbError::Conflict { expected: 1, actual: 2 })));
+    assert_eq!(doc_db.get_versioned(profile_id).await.unwrap().unwrap().data, doc);
+
+    // Raw updates (e.g. by procedures) change version too.
+    sqlx::query("UPDATE User_Profiles SET location = 'Rome' WHERE profile_id = ?;")
+        .bind(profile_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let current = doc_db.get_versioned(profile_id).await.unwrap().unwrap();
+    assert_eq!((current.version, current.etag()), (3, "\"3\"".to_string()));
+
+    let result = doc_db.update_if_match(profile_id, "\"2\"", &stale).await;
+    assert!(matches!(result, Err(DbError::Conflict { expected: 2, actual: 3 })));
+
+    assert_eq!(doc_db.update_if_match(profile_id, "\"3\"", &stale).await.unwrap(), "\"4\"");
+    assert_eq!(doc_db.update_if_match(profile_id, "*", &doc).await.unwrap(), "\"5\"");
+
+    assert!(matches!(
+        doc_db.update_if_version(999_999, 1, &doc).await,
+        Err(DbError::Sqlx(sqlx::Error::RowNotFound))
+    ));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn collection_documents_are_versioned() {
+    let mut test_db = require_mysql!();
+    let (doc_db, _pool) = setup(&mut test_db).await;
+
+    let cities = doc_db.create_collection("cities").await.unwrap();
+    let kazan  = cities.insert(&city("Kazan", "Russia", 1_300_000)).await.unwrap();
+
+    assert_eq!(cities.get_versioned::<City>(kazan).await.unwrap().unwrap().version, 1);
+
+    assert!(cities.replace(kazan, &city("Kazan", "Russia", 1_310_000)).await.unwrap());
+    assert_eq!(cities.get_versioned::<City>(kazan).await.unwrap().unwrap().version, 2);
+
+    let result = cities.update_if_version(kazan, 1, &city("Kazan", "Russia", 0)).await;
+    assert!(matches!(result, Err(DbError::Conflict { expected: 1, actual: 2 })));
+
+    let version = cities.update_if_version(kazan, 2, &city("Kazan", "Russia", 1_320_000)).await.unwrap();
+    assert_eq!(version, 3);
+
+    let patch = DocumentPatch::Merge(json!({ "population": 1_330_000 }));
+    cities.patch(kazan, &patch).await.unwrap();
+    assert_eq!(cities.get_versioned::<City>(kazan).await.unwrap().unwrap().version, 4);
+
+    // Collections without version column do not support conditional updates.
+    let messages = doc_db.messages().unwrap();
+    assert!(matches!(messages.get_versioned::<Value>(1).await, Err(DbError::InvalidName(_))));
+}
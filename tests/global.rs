@@ -0,0 +1,502 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Global database manager integration tests.
+
+mod common;
+
+use chrono::NaiveDate;
+use common::TestDb;
+use dbproject::{
+    chat::{ProfileData, User},
+    db::{
+        area::{Area, AreaDB},
+        crypto::{is_envelope, Cipher, LocalKeyring, MasterKey},
+        docdb::DocDBManager,
+        global::GlobalDB,
+        kvdb::{KeyValueDBManager, Namespace},
+        ConnectionConfig,
+    },
+};
+
+/// Global database with Russia & USA areas.
+struct Setup {
+    /// Global database manager.
+    global_db: GlobalDB,
+    /// Russia area database config.
+    russia: ConnectionConfig,
+    /// USA area database config.
+    usa: ConnectionConfig,
+    /// Core database config.
+    core: ConnectionConfig,
+}
+
+async fn setup(test_db: &mut TestDb) -> Setup {
+    let core = test_db.database("CoreDB");
+
+    let mut global_db = GlobalDB::new().with_core_db(&core.database);
+    global_db.connect(&test_db.server()).await.unwrap();
+
+    let russia = test_db.database("AreaDB_Russia");
+    global_db.insert(AreaDB::new(russia.clone(), Area::Russia)).await.unwrap();
+
+    let usa = test_db.database("AreaDB_USA");
+    global_db.insert(AreaDB::new(usa.clone(), Area::Usa)).await.unwrap();
+
+    Setup { global_db, russia, usa, core }
+}
+
+fn user(username: &str) -> User {
+    User {
+        username:      username.to_string(),
+        password_hash: "hashed_password".to_string(),
+        email:         format!("{username}@example.com"),
+        ..Default::default()
+    }
+}
+
+async fn find_user(global_db: &GlobalDB, area: &Area, username: &str) -> Option<User> {
+    global_db
+        .list_users_by_area(area, 0, 1000)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|user| user.username == username)
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn procedures_manage_users_of_area() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    global_db.add_user(&user("test_user"), &Area::Usa).await.unwrap();
+
+    let mut added = find_user(global_db, &Area::Usa, "test_user").await.unwrap();
+    assert_eq!(added.email, "test_user@example.com");
+    assert!(find_user(global_db, &Area::Russia, "test_user").await.is_none());
+
+    added.username = "updated_user".to_string();
+    added.email    = "updated_user@example.com".to_string();
+    global_db.update_user(&added, &Area::Usa).await.unwrap();
+
+    let updated = find_user(global_db, &Area::Usa, "updated_user").await.unwrap();
+    assert_eq!(updated.user_id, added.user_id);
+    assert_eq!(updated.email, "updated_user@example.com");
+
+    global_db.delete_user(updated.user_id, &Area::Usa).await.unwrap();
+    assert!(find_user(global_db, &Area::Usa, "updated_user").await.is_none());
+
+    assert!(global_db.add_user(&user("nowhere"), &Area::Unknown).await.is_err());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn list_users_by_area_paginates() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    for i in 0..3 {
+        global_db.add_user(&user(&format!("page_user_{i}")), &Area::Russia).await.unwrap();
+    }
+
+    // One random user is created on area connect.
+    let all = global_db.list_users_by_area(&Area::Russia, 0, 100).await.unwrap();
+    assert_eq!(all.len(), 4);
+
+    let page = global_db.list_users_by_area(&Area::Russia, 1, 2).await.unwrap();
+    assert_eq!(page, all[1..3].to_vec());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn reports_return_typed_results() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    let russia = TestDb::pool(&setup.russia).await;
+    let usa    = TestDb::pool(&setup.usa).await;
+
+    global_db.add_user(&user("report_user"), &Area::Russia).await.unwrap();
+
+    let counts = global_db.get_user_count().await.unwrap();
+    let count  = |area| counts.iter().find(|count| count.area == area).unwrap().count;
+    assert_eq!(count(Area::Russia), 2);
+    assert_eq!(count(Area::Usa), 1);
+
+    let counts = global_db.get_message_count().await.unwrap();
+    assert_eq!(counts.len(), 2);
+    assert!(counts.iter().all(|count| count.count == 1));
+
+    // Russia channel has no messages, USA channel has one.
+    sqlx::query("DELETE FROM Message;").execute(&russia).await.unwrap();
+    sqlx::query("UPDATE Message SET channel_id = (SELECT MIN(channel_id) FROM Channel);")
+        .execute(&usa)
+        .await
+        .unwrap();
+
+    let channels = global_db.get_channels_with_no_messages().await.unwrap();
+    assert_eq!(channels.len(), 1);
+    assert_eq!(channels[0].area, Area::Russia);
+
+    let timestamp = NaiveDate::from_ymd_opt(2030, 1, 1)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap();
+
+    let query = "INSERT INTO Logs (event_type, user_id, timestamp) \
+                 SELECT 'login', user_id, ? FROM User WHERE username = 'report_user';";
+    sqlx::query(query).bind(timestamp).execute(&russia).await.unwrap();
+
+    let activities = global_db.get_last_activity().await.unwrap();
+    let activity   = activities
+        .iter()
+        .find(|activity| activity.username == "report_user")
+        .unwrap();
+
+    assert_eq!(activity.area, Area::Russia);
+    assert_eq!(activity.last_activity, Some(timestamp));
+
+    // Random reactions use user identifiers below 10000.
+    let query = "INSERT INTO Reactions (message_id, user_id, reaction_type) VALUES (1, 999999, 'like');";
+    sqlx::query(query).execute(&russia).await.unwrap();
+    sqlx::query(query).execute(&russia).await.unwrap();
+    sqlx::query(query).execute(&usa).await.unwrap();
+
+    let reactions = global_db.get_total_reactions().await.unwrap();
+    let total     = reactions.iter().find(|reactions| reactions.user_id == 999999).unwrap();
+    assert_eq!(total.total_reactions, 3);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn move_user_commits_on_both_areas() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    global_db.add_user(&user("mover"), &Area::Russia).await.unwrap();
+    let moved = find_user(global_db, &Area::Russia, "mover").await.unwrap();
+
+    let russia = TestDb::pool(&setup.russia).await;
+    let usa    = TestDb::pool(&setup.usa).await;
+
+    // Membership collides with keys of membership seeded into target area.
+    let (channel_user_id, channel_id): (i64, i64) =
+        sqlx::query_as("SELECT channel_user_id, channel_id FROM Channel_Users LIMIT 1;")
+            .fetch_one(&usa)
+            .await
+            .unwrap();
+
+    sqlx::query("DELETE FROM Channel_Users WHERE channel_user_id = ?;")
+        .bind(channel_user_id)
+        .execute(&russia)
+        .await
+        .unwrap();
+
+    sqlx::query("INSERT INTO Channel_Users (channel_user_id, channel_id, user_id, joined_at, role) VALUES (?, ?, ?, '2025-01-01', 'admin');")
+        .bind(channel_user_id)
+        .bind(channel_id)
+        .bind(moved.user_id)
+        .execute(&russia)
+        .await
+        .unwrap();
+
+    let query =
+        r#"
+        INSERT INTO Channel_Users (channel_id, user_id, joined_at, role) VALUES (7, ?, '2025-01-01', 'member');
+        INSERT INTO Bans (channel_id, user_id, banned_at, reason) VALUES (7, ?, '2025-01-02', 'spam');
+        INSERT INTO Message (channel_id, user_id, message_text, timestamp, message_data)
+        VALUES (7, ?, 'hello', '2025-01-03 10:00:00', '{"tags": ["moved"]}');
+        INSERT INTO User_Settings_KV (user_id, setting_name, setting_value) VALUES (?, 'theme', 'dark');
+        "#;
+
+    for statement in query.split(';').filter(|statement| !statement.trim().is_empty()) {
+        sqlx::query(statement).bind(moved.user_id).execute(&russia).await.unwrap();
+    }
+
+    let new_user_id = global_db
+        .move_user(moved.user_id, &Area::Russia, &Area::Usa)
+        .await
+        .unwrap();
+
+    assert!(find_user(global_db, &Area::Russia, "mover").await.is_none());
+
+    let target = find_user(global_db, &Area::Usa, "mover").await.unwrap();
+    assert_eq!(target.user_id, new_user_id);
+    assert_eq!(target.email, moved.email);
+
+    // Related rows are moved under new user identifier.
+    let query =
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM Channel_Users WHERE user_id = ? AND channel_id IN (?, 7)),
+            (SELECT COUNT(*) FROM Bans WHERE user_id = ? AND reason = 'spam' AND banned_at = '2025-01-02'),
+            (SELECT COUNT(*) FROM Message WHERE user_id = ? AND message_text = 'hello'
+                AND timestamp = '2025-01-03 10:00:00' AND message_data->>'$.tags[0]' = 'moved'),
+            (SELECT COUNT(*) FROM User_Settings_KV WHERE user_id = ? AND setting_value = 'dark');
+        "#;
+
+    let count = |pool, id| {
+        sqlx::query_as::<_, (i64, i64, i64, i64)>(query)
+            .bind(id)
+            .bind(channel_id)
+            .bind(id)
+            .bind(id)
+            .bind(id)
+            .fetch_one(pool)
+    };
+
+    assert_eq!(count(&usa, new_user_id).await.unwrap(), (2, 1, 1, 1));
+    assert_eq!(count(&russia, moved.user_id).await.unwrap(), (0, 0, 0, 0));
+
+    // Missing user rolls back whole transaction.
+    let before = global_db.get_user_count().await.unwrap();
+    assert!(global_db.move_user(999999, &Area::Usa, &Area::Russia).await.is_err());
+    assert_eq!(global_db.get_user_count().await.unwrap(), before);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn move_user_remaps_message_references() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    global_db.add_user(&user("author"), &Area::Russia).await.unwrap();
+    global_db.add_user(&user("reader"), &Area::Russia).await.unwrap();
+
+    let author = find_user(global_db, &Area::Russia, "author").await.unwrap();
+    let reader = find_user(global_db, &Area::Russia, "reader").await.unwrap();
+
+    let russia = TestDb::pool(&setup.russia).await;
+    let usa    = TestDb::pool(&setup.usa).await;
+
+    // Profile with revision recorded by document database.
+    let mut doc_db = DocDBManager::new();
+    doc_db.connect(setup.russia.clone()).await.unwrap();
+
+    let mut data = ProfileData { bio: "First bio".to_string(), ..Default::default() };
+    let profile_id = doc_db.create_profile(author.user_id, &data).await.unwrap();
+
+    data.bio = "Second bio".to_string();
+    assert!(doc_db.update_profile(profile_id, &data).await.unwrap());
+    assert!(!doc_db.revisions(profile_id).await.unwrap().is_empty());
+
+    let message = |user_id: i64, text: &str| {
+        sqlx::query("INSERT INTO Message (channel_id, user_id, message_text, timestamp) VALUES (7, ?, ?, NOW());")
+            .bind(user_id)
+            .bind(text.to_string())
+            .execute(&russia)
+    };
+
+    let own     = message(author.user_id, "moved message").await.unwrap().last_insert_id();
+    let foreign = message(reader.user_id, "staying message").await.unwrap().last_insert_id();
+
+    let reactions = [
+        (own, author.user_id, "own_reaction"),
+        (own, reader.user_id, "reader_reaction"),
+        (foreign, author.user_id, "stray_reaction"),
+    ];
+
+    for (message_id, user_id, reaction_type) in reactions {
+        sqlx::query("INSERT INTO Reactions (message_id, user_id, timestamp, reaction_type) VALUES (?, ?, NOW(), ?);")
+            .bind(message_id)
+            .bind(user_id)
+            .bind(reaction_type)
+            .execute(&russia)
+            .await
+            .unwrap();
+    }
+
+    sqlx::query("INSERT INTO Logs (event_type, user_id, channel_id, timestamp, details) VALUES ('join', ?, 7, NOW(), 'moved log');")
+        .bind(author.user_id)
+        .execute(&russia)
+        .await
+        .unwrap();
+
+    let new_user_id = global_db
+        .move_user(author.user_id, &Area::Russia, &Area::Usa)
+        .await
+        .unwrap();
+
+    // Reaction of moved user references moved message in target area.
+    let query =
+        r#"
+        SELECT Reactions.reaction_type, Message.message_text FROM Reactions
+        JOIN Message ON Message.message_id = Reactions.message_id
+        WHERE Reactions.user_id = ?
+        AND Reactions.reaction_type IN ('own_reaction', 'reader_reaction', 'stray_reaction');
+        "#;
+
+    let moved: Vec<(String, String)> = sqlx::query_as(query)
+        .bind(new_user_id)
+        .fetch_all(&usa)
+        .await
+        .unwrap();
+
+    assert_eq!(moved, vec![("own_reaction".to_string(), "moved message".to_string())]);
+
+    // Channel references are kept as is.
+    let (channel_id,): (i64,) = sqlx::query_as("SELECT channel_id FROM Logs WHERE user_id = ? AND details = 'moved log';")
+        .bind(new_user_id)
+        .fetch_one(&usa)
+        .await
+        .unwrap();
+
+    assert_eq!(channel_id, 7);
+
+    // Reactions referencing messages across areas & profile revisions are deleted.
+    let query =
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM Reactions WHERE reaction_type IN ('own_reaction', 'reader_reaction', 'stray_reaction')),
+            (SELECT COUNT(*) FROM Message WHERE message_id = ?),
+            (SELECT COUNT(*) FROM Document_Revisions WHERE table_name = 'User_Profiles' AND document_id = ?);
+        "#;
+
+    let counts: (i64, i64, i64) = sqlx::query_as(query)
+        .bind(foreign)
+        .bind(profile_id)
+        .fetch_one(&russia)
+        .await
+        .unwrap();
+
+    assert_eq!(counts, (0, 1, 0));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn move_user_re_encrypts_user_settings() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+    let global_db = &setup.global_db;
+
+    global_db.add_user(&user("secretive"), &Area::Russia).await.unwrap();
+    let moved = find_user(global_db, &Area::Russia, "secretive").await.unwrap();
+
+    let cipher = Cipher::new(LocalKeyring::new("k1", MasterKey::generate()).unwrap());
+
+    let kv_db = |config: ConnectionConfig| {
+        let cipher = cipher.clone();
+
+        async move {
+            let mut kv_db = KeyValueDBManager::new();
+            kv_db.connect(config).await.unwrap();
+            kv_db.with_encryption(cipher, &["api_token"])
+        }
+    };
+
+    let russia_kv = kv_db(setup.russia.clone()).await;
+    russia_kv.set(&Namespace::User(moved.user_id), "api_token", "tok_123").await.unwrap();
+
+    // Envelopes can not be moved without cipher.
+    assert!(global_db.move_user(moved.user_id, &Area::Russia, &Area::Usa).await.is_err());
+    assert!(find_user(global_db, &Area::Russia, "secretive").await.is_some());
+
+    let mut encrypted = GlobalDB::new().with_core_db(&setup.core.database).with_encryption(cipher.clone());
+    encrypted.connect(&test_db.server()).await.unwrap();
+    encrypted.insert(AreaDB::new(setup.russia.clone(), Area::Russia)).await.unwrap();
+    encrypted.insert(AreaDB::new(setup.usa.clone(), Area::Usa)).await.unwrap();
+
+    let new_user_id = encrypted
+        .move_user(moved.user_id, &Area::Russia, &Area::Usa)
+        .await
+        .unwrap();
+
+    let usa = TestDb::pool(&setup.usa).await;
+
+    let query = "SELECT setting_value FROM User_Settings_KV WHERE user_id = ? AND setting_name = 'api_token';";
+    let (stored,): (String,) = sqlx::query_as(query)
+        .bind(new_user_id)
+        .fetch_one(&usa)
+        .await
+        .unwrap();
+
+    assert!(is_envelope(&stored));
+
+    let usa_kv = kv_db(setup.usa.clone()).await;
+    let value  = usa_kv.get(&Namespace::User(new_user_id), "api_token").await.unwrap();
+    assert_eq!(value.as_deref(), Some("tok_123"));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn recover_resolves_in_doubt_transactions() {
+    let mut test_db = require_mysql!();
+    let setup = setup(&mut test_db).await;
+
+    let russia = TestDb::pool(&setup.russia).await;
+    let core   = TestDb::pool(&setup.core).await;
+
+    let suffix = format!("{:08x}", rand::random::<u32>());
+    let cases  = [
+        (format!("dbp-test-commit-{suffix}"), "COMMITTING", "in_doubt_commit"),
+        (format!("dbp-test-abort-{suffix}"), "ACTIVE", "in_doubt_abort"),
+    ];
+
+    for (xid, state, username) in &cases {
+        sqlx::query("INSERT INTO Xa_Transaction_Log VALUES (?, ?, 'Russia', NOW(), NOW());")
+            .bind(xid)
+            .bind(state)
+            .execute(&core)
+            .await
+            .unwrap();
+
+        // Prepared branch survives its connection, as after coordinator crash.
+        let mut conn = russia.acquire().await.unwrap();
+
+        let query = format!(
+            "XA START '{xid}','Russia'; \
+             INSERT INTO User (username) VALUES ('{username}'); \
+             XA END '{xid}','Russia'; \
+             XA PREPARE '{xid}','Russia';"
+        );
+
+        sqlx::raw_sql(query.as_str()).execute(&mut *conn).await.unwrap();
+        conn.close().await.unwrap();
+    }
+
+    // Coordinator skipping recovery leaves branches prepared.
+    let mut skipping = GlobalDB::new().with_core_db(&setup.core.database).without_recovery();
+    skipping.connect(&test_db.server()).await.unwrap();
+    skipping.insert(AreaDB::new(setup.russia.clone(), Area::Russia)).await.unwrap();
+    assert!(find_user(&skipping, &Area::Russia, "in_doubt_commit").await.is_none());
+
+    // Recovery runs on connect of restarted coordinator.
+    let mut restarted = GlobalDB::new().with_core_db(&setup.core.database);
+    restarted.connect(&test_db.server()).await.unwrap();
+    restarted.insert(AreaDB::new(setup.russia.clone(), Area::Russia)).await.unwrap();
+    restarted.insert(AreaDB::new(setup.usa.clone(), Area::Usa)).await.unwrap();
+
+    assert!(find_user(&restarted, &Area::Russia, "in_doubt_commit").await.is_some());
+    assert!(find_user(&restarted, &Area::Russia, "in_doubt_abort").await.is_none());
+    assert_eq!(restarted.recover().await.unwrap(), 0);
+
+    // Committed transaction is finalized once none of its branches is prepared.
+    for ((xid, _, _), expected) in cases.iter().zip(["COMMITTED", "ROLLED_BACK"]) {
+        let (state,): (String,) = sqlx::query_as("SELECT state FROM Xa_Transaction_Log WHERE xid = ?;")
+            .bind(xid)
+            .fetch_one(&core)
+            .await
+            .unwrap();
+
+        assert_eq!(state, expected);
+    }
+}
@@ -0,0 +1,555 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Key-value database manager integration tests.
+
+mod common;
+
+use dbproject::{
+    chat::UserSettingKV,
+    db::{
+        cache::{CacheConfig, CacheStats},
+        error::DbError,
+        kvdb::{KeyValueDBManager, Namespace, Ttl},
+        registry::{SettingDef, SettingScope, SettingSource, SettingType, SettingsRegistry},
+    },
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn registry() -> SettingsRegistry {
+    let theme = SettingType::Enum(vec!["light".to_string(), "dark".to_string()]);
+    let mut registry = SettingsRegistry::new();
+
+    let defs = [
+        SettingDef::new("theme", theme, "light"),
+        SettingDef::new("notifications", SettingType::Bool, true),
+        SettingDef::new("slow_mode_seconds", SettingType::Int, 0)
+            .scopes(&[SettingScope::Server, SettingScope::Channel]),
+        SettingDef::new("nickname", SettingType::String, "").scopes(&[SettingScope::User]),
+        SettingDef::new("layout", SettingType::Json, json!({ "sidebar": true })),
+    ];
+
+    for def in defs {
+        registry.register(def).unwrap();
+    }
+
+    registry
+}
+
+async fn setup(test_db: &mut common::TestDb, name: &str) -> KeyValueDBManager {
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(test_db.database(name)).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+    kv_db
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn user_setting_procedures_upsert_and_read() {
+    let mut test_db = require_mysql!();
+
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(test_db.database("KeyValueDB")).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+
+    let mut setting = UserSettingKV {
+        user_id:        1337,
+        settings_name:  "theme".to_string(),
+        settings_value: "dark".to_string(),
+    };
+
+    kv_db.add_user_setting(&setting).await.unwrap();
+    assert_eq!(kv_db.get_user_setting(1337, "theme".to_string()).await.unwrap(), "dark");
+
+    setting.settings_value = "light".to_string();
+    kv_db.add_user_setting(&setting).await.unwrap();
+    assert_eq!(kv_db.get_user_setting(1337, "theme".to_string()).await.unwrap(), "light");
+
+    assert!(kv_db.get_user_setting(1337, "missing".to_string()).await.is_err());
+
+    let settings = kv_db.get_all_user_settings(1337).await.unwrap();
+    assert_eq!(settings, Some(json!({ "theme": "light" })));
+    assert_eq!(kv_db.get_all_user_settings(7331).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn namespaces_are_isolated() {
+    let mut test_db = require_mysql!();
+    let kv_db = setup(&mut test_db, "KeyValueDB_Namespaces").await;
+
+    let namespaces = [
+        Namespace::Server,
+        Namespace::Channel(501),
+        Namespace::User(501),
+        Namespace::Custom("sessions".to_string()),
+    ];
+
+    for (i, namespace) in namespaces.iter().enumerate() {
+        kv_db.set(namespace, "shared_key", &format!("value_{i}")).await.unwrap();
+    }
+
+    for (i, namespace) in namespaces.iter().enumerate() {
+        assert_eq!(kv_db.get(namespace, "shared_key").await.unwrap(), Some(format!("value_{i}")));
+        assert!(kv_db.exists(namespace, "shared_key").await.unwrap());
+    }
+
+    assert_eq!(kv_db.get(&Namespace::User(502), "shared_key").await.unwrap(), None);
+    assert_eq!(kv_db.get(&Namespace::Custom("tokens".to_string()), "shared_key").await.unwrap(), None);
+
+    assert!(kv_db.delete(&Namespace::User(501), "shared_key").await.unwrap());
+    assert!(!kv_db.delete(&Namespace::User(501), "shared_key").await.unwrap());
+    assert!(!kv_db.exists(&Namespace::User(501), "shared_key").await.unwrap());
+    assert!(kv_db.exists(&Namespace::Channel(501), "shared_key").await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn batches_and_scans_keys() {
+    let mut test_db = require_mysql!();
+    let kv_db = setup(&mut test_db, "KeyValueDB_Scan").await;
+    let server = Namespace::Server;
+
+    let entries = [
+        ("chat.max_length", "2000"),
+        ("chat.slow_mode", "off"),
+        ("chat_history", "30d"),
+        ("voice.bitrate", "64"),
+    ];
+
+    kv_db.mset(&server, &entries).await.unwrap();
+    kv_db.mset(&server, &[("chat.slow_mode", "on")]).await.unwrap();
+
+    let values = kv_db.mget(&server, &["voice.bitrate", "missing", "chat.slow_mode"]).await.unwrap();
+    assert_eq!(values, [Some("64".to_string()), None, Some("on".to_string())]);
+
+    // Underscore of prefix is not a wildcard.
+    let page = kv_db.scan(&server, "chat_", None, 10).await.unwrap();
+    assert_eq!(page.entries, [("chat_history".to_string(), "30d".to_string())]);
+
+    let mut keys   = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let page = kv_db.scan(&server, "chat.", cursor.as_deref(), 1).await.unwrap();
+        keys.extend(page.entries.into_iter().map(|(key, _)| key));
+
+        match page.cursor {
+            Some(next) => cursor = Some(next),
+            None       => break,
+        }
+    }
+
+    assert_eq!(keys, ["chat.max_length", "chat.slow_mode"]);
+}
+
+#[tokio::test]
+async fn rejects_invalid_namespaces_and_keys() {
+    let kv_db = KeyValueDBManager::new();
+
+    let result = kv_db.get(&Namespace::Custom("bad name".to_string()), "key").await;
+    assert!(matches!(result, Err(DbError::InvalidName(_))));
+
+    let result = kv_db.set(&Namespace::Server, "", "value").await;
+    assert!(matches!(result, Err(DbError::InvalidName(_))));
+
+    let result = kv_db.get(&Namespace::Server, &"k".repeat(256)).await;
+    assert!(matches!(result, Err(DbError::InvalidName(_))));
+
+    let result = kv_db.get(&Namespace::Server, "key").await;
+    assert!(matches!(result, Err(DbError::Sqlx(sqlx::Error::PoolClosed))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn expired_keys_are_invisible_and_reaped() {
+    let mut test_db = require_mysql!();
+    let kv_db = setup(&mut test_db, "KeyValueDB_Ttl").await;
+
+    let typing  = Namespace::Custom("typing".to_string());
+    let session = Namespace::User(601);
+
+    kv_db.set_with_ttl(&typing, "channel_1", "alice", Duration::from_millis(200)).await.unwrap();
+    kv_db.set_with_ttl(&session, "token", "secret", Duration::from_secs(3600)).await.unwrap();
+
+    match kv_db.ttl(&session, "token").await.unwrap() {
+        Some(Ttl::Expires(ttl)) => assert!(ttl > Duration::from_secs(3500)),
+        other                   => panic!("unexpected ttl: {other:?}"),
+    }
+
+    assert!(kv_db.persist(&session, "token").await.unwrap());
+    assert!(!kv_db.persist(&session, "token").await.unwrap());
+    assert_eq!(kv_db.ttl(&session, "token").await.unwrap(), Some(Ttl::Persistent));
+    assert_eq!(kv_db.ttl(&session, "missing").await.unwrap(), None);
+
+    assert!(kv_db.expire(&session, "token", Duration::from_millis(200)).await.unwrap());
+    assert_eq!(kv_db.get(&typing, "channel_1").await.unwrap(), Some("alice".to_string()));
+
+    tokio::time::sleep(Duration::from_millis(400)).await;
+
+    assert_eq!(kv_db.get(&typing, "channel_1").await.unwrap(), None);
+    assert!(!kv_db.exists(&session, "token").await.unwrap());
+
+    // Legacy procedures also ignore expired keys.
+    assert!(kv_db.get_user_setting(601, "token".to_string()).await.is_err());
+    assert_eq!(kv_db.get_all_user_settings(601).await.unwrap(), None);
+    assert!(!kv_db.expire(&session, "token", Duration::from_secs(60)).await.unwrap());
+
+    // Plain set replaces expired key & clears its expiry.
+    kv_db.set(&typing, "channel_2", "bob").await.unwrap();
+    kv_db.set_with_ttl(&typing, "channel_2", "bob", Duration::ZERO).await.unwrap();
+    kv_db.set(&typing, "channel_2", "bob").await.unwrap();
+    assert_eq!(kv_db.ttl(&typing, "channel_2").await.unwrap(), Some(Ttl::Persistent));
+
+    let reaper = kv_db.spawn_reaper(Duration::from_millis(50), 1).unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    reaper.abort();
+
+    assert_eq!(kv_db.reap_expired(100).await.unwrap(), 0);
+    assert_eq!(kv_db.get(&typing, "channel_2").await.unwrap(), Some("bob".to_string()));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn compare_and_set_and_counters_are_atomic() {
+    let mut test_db = require_mysql!();
+    let kv_db   = setup(&mut test_db, "KeyValueDB_Atomic").await;
+    let server  = Namespace::Server;
+    let channel = Namespace::Channel(701);
+
+    assert!(kv_db.set_if_absent(&server, "motd", "hello").await.unwrap());
+    assert!(!kv_db.set_if_absent(&server, "motd", "ignored").await.unwrap());
+    assert!(!kv_db.compare_and_set(&server, "motd", Some("stale"), "new").await.unwrap());
+    assert!(kv_db.compare_and_set(&server, "motd", Some("hello"), "new").await.unwrap());
+    assert!(!kv_db.compare_and_set(&server, "motd", None, "other").await.unwrap());
+    assert_eq!(kv_db.get(&server, "motd").await.unwrap(), Some("new".to_string()));
+
+    // Expired key is absent.
+    kv_db.set_with_ttl(&server, "lock", "owner_1", Duration::ZERO).await.unwrap();
+    assert!(kv_db.set_if_absent(&server, "lock", "owner_2").await.unwrap());
+    assert_eq!(kv_db.ttl(&server, "lock").await.unwrap(), Some(Ttl::Persistent));
+
+    let increments = (0..20).map(|_| kv_db.incr(&channel, "message_count", 1));
+    let results    = futures_util::future::join_all(increments).await;
+
+    let mut values: Vec<i64> = results.into_iter().map(Result::unwrap).collect();
+    values.sort_unstable();
+    assert_eq!(values, (1..=20).collect::<Vec<_>>());
+
+    assert_eq!(kv_db.decr(&channel, "message_count", 5).await.unwrap(), 15);
+    assert_eq!(kv_db.decr(&Namespace::User(701), "rate_limit", 1).await.unwrap(), -1);
+
+    let result = kv_db.incr(&server, "motd", 1).await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+    assert_eq!(kv_db.get(&server, "motd").await.unwrap(), Some("new".to_string()));
+}
+
+#[test]
+fn registry_encodes_and_decodes_typed_values() {
+    let registry = registry();
+    let user     = Namespace::User(1);
+
+    let theme = registry.resolve(&user, "theme").unwrap();
+    assert_eq!(theme.encode(&json!("dark")).unwrap(), "dark");
+    assert!(matches!(theme.encode(&json!("blue")), Err(DbError::InvalidValue(_))));
+
+    let notifications = registry.resolve(&user, "notifications").unwrap();
+    assert_eq!(notifications.decode("false").unwrap(), json!(false));
+    assert!(notifications.decode("yes").is_err());
+
+    let layout = registry.resolve(&user, "layout").unwrap();
+    assert_eq!(layout.decode(&layout.encode(&json!({ "sidebar": false })).unwrap()).unwrap(), json!({ "sidebar": false }));
+
+    assert!(matches!(registry.resolve(&user, "missing"), Err(DbError::UnknownSetting(_))));
+    assert!(matches!(registry.resolve(&user, "slow_mode_seconds"), Err(DbError::InvalidScope(_))));
+    assert!(matches!(registry.resolve(&Namespace::Server, "nickname"), Err(DbError::InvalidScope(_))));
+
+    let mut registry = registry;
+    let result       = registry.register(SettingDef::new("limit", SettingType::Int, "ten"));
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+}
+
+#[tokio::test]
+async fn registry_rejects_invalid_writes() {
+    let kv_db = KeyValueDBManager::new().with_registry(registry());
+    let user  = Namespace::User(1);
+
+    let result = kv_db.set(&user, "unknown", "value").await;
+    assert!(matches!(result, Err(DbError::UnknownSetting(_))));
+
+    let result = kv_db.set(&user, "notifications", "maybe").await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+
+    let result = kv_db.set_setting(&user, "theme", &"blue").await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+
+    let result = kv_db.incr(&user, "theme", 1).await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+
+    let setting = UserSettingKV { user_id: 1, settings_name: "unknown".to_string(), ..Default::default() };
+    assert!(matches!(kv_db.add_user_setting(&setting).await, Err(sqlx::Error::Encode(_))));
+
+    let result = KeyValueDBManager::new().get_setting::<bool>(&user, "notifications").await;
+    assert!(matches!(result, Err(DbError::UnknownSetting(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn typed_settings_fall_back_to_defaults() {
+    let mut test_db = require_mysql!();
+    let config = test_db.database("KeyValueDB_Registry");
+
+    let mut kv_db = KeyValueDBManager::new().with_registry(registry());
+    kv_db.connect(config).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+
+    let user    = Namespace::User(801);
+    let channel = Namespace::Channel(801);
+
+    assert_eq!(kv_db.get_setting::<String>(&user, "theme").await.unwrap(), "light");
+    assert!(kv_db.get_setting::<bool>(&user, "notifications").await.unwrap());
+
+    kv_db.set_setting(&user, "theme", &"dark").await.unwrap();
+    kv_db.set_setting(&channel, "slow_mode_seconds", &30).await.unwrap();
+
+    assert_eq!(kv_db.get_setting::<String>(&user, "theme").await.unwrap(), "dark");
+    assert_eq!(kv_db.get_setting::<i64>(&channel, "slow_mode_seconds").await.unwrap(), 30);
+    assert_eq!(kv_db.incr(&channel, "slow_mode_seconds", 5).await.unwrap(), 35);
+
+    // Custom namespaces are not settings.
+    kv_db.set(&Namespace::Custom("sessions".to_string()), "token", "secret").await.unwrap();
+}
+
+#[test]
+fn registry_merges_layers_with_provenance() {
+    let registry = registry();
+
+    let stored = [
+        (SettingScope::User, "theme".to_string(), "dark".to_string()),
+        (SettingScope::Server, "theme".to_string(), "light".to_string()),
+        (SettingScope::Channel, "slow_mode_seconds".to_string(), "10".to_string()),
+        (SettingScope::Server, "slow_mode_seconds".to_string(), "5".to_string()),
+        // Invalid, out of scope & unknown values are skipped.
+        (SettingScope::User, "notifications".to_string(), "maybe".to_string()),
+        (SettingScope::User, "slow_mode_seconds".to_string(), "0".to_string()),
+        (SettingScope::User, "unknown".to_string(), "value".to_string()),
+    ];
+
+    let settings = registry.merge(&stored);
+    let setting  = |name: &str| settings.get(name).map(|setting| (setting.value.clone(), setting.source));
+
+    assert_eq!(setting("theme"), Some((json!("dark"), SettingSource::User)));
+    assert_eq!(setting("slow_mode_seconds"), Some((json!(10), SettingSource::Channel)));
+    assert_eq!(setting("notifications"), Some((json!(true), SettingSource::Default)));
+    assert_eq!(setting("layout"), Some((json!({ "sidebar": true }), SettingSource::Default)));
+    assert_eq!(setting("unknown"), None);
+    assert_eq!(settings.len(), 5);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn effective_settings_resolve_user_channel_and_server_layers() {
+    let mut test_db = require_mysql!();
+    let kv_db = setup(&mut test_db, "KeyValueDB_Effective").await;
+
+    kv_db.set(&Namespace::Server, "theme", "light").await.unwrap();
+    kv_db.set(&Namespace::Server, "notifications", "false").await.unwrap();
+    kv_db.set(&Namespace::Channel(901), "theme", "dark").await.unwrap();
+    kv_db.set(&Namespace::User(901), "notifications", "true").await.unwrap();
+    kv_db.set(&Namespace::Channel(902), "slow_mode_seconds", "60").await.unwrap();
+
+    // Without registry stored strings are merged.
+    let settings = kv_db.effective_settings(901, 901).await.unwrap();
+    assert_eq!(settings["theme"].value, json!("dark"));
+    assert_eq!(settings["theme"].source, SettingSource::Channel);
+    assert_eq!(settings["notifications"].value, json!("true"));
+    assert_eq!(settings["notifications"].source, SettingSource::User);
+    assert!(!settings.contains_key("slow_mode_seconds"));
+
+    let kv_db    = kv_db.with_registry(registry());
+    let settings = kv_db.effective_settings(901, 901).await.unwrap();
+    assert_eq!(settings["notifications"].value, json!(true));
+    assert_eq!(settings["notifications"].source, SettingSource::User);
+    assert_eq!(settings["slow_mode_seconds"].value, json!(0));
+    assert_eq!(settings["slow_mode_seconds"].source, SettingSource::Default);
+    assert_eq!(settings["theme"].source, SettingSource::Channel);
+
+    let settings = kv_db.effective_settings(901, 902).await.unwrap();
+    assert_eq!(settings["slow_mode_seconds"].value, json!(60));
+    assert_eq!(settings["theme"].source, SettingSource::Server);
+}
+
+#[test]
+fn cache_config_and_stats_report_limits_and_ratio() {
+    let config = CacheConfig::new(0, Duration::from_secs(5));
+    assert_eq!(config.capacity.get(), 1);
+    assert_eq!(config.ttl, Duration::from_secs(5));
+
+    let config = config.version_check_interval(Duration::ZERO);
+    assert_eq!(config.version_check_interval, Duration::ZERO);
+
+    assert_eq!(CacheStats::default().hit_ratio(), 0.0);
+
+    let stats = CacheStats { hits: 3, misses: 1, ..Default::default() };
+    assert_eq!(stats.hit_ratio(), 0.75);
+
+    assert!(KeyValueDBManager::new().cache_stats().is_none());
+    assert!(KeyValueDBManager::new().with_cache(CacheConfig::default()).cache_stats().is_some());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn cached_reads_are_invalidated_by_local_and_remote_writes() {
+    let mut test_db = require_mysql!();
+
+    let config = CacheConfig::new(2, Duration::from_secs(60))
+        .version_check_interval(Duration::ZERO);
+
+    let writer = setup(&mut test_db, "KeyValueDBCache").await;
+    let cached = setup(&mut test_db, "KeyValueDBCache").await.with_cache(config);
+    let ns     = Namespace::Custom("cache".to_string());
+
+    writer.set(&ns, "a", "1").await.unwrap();
+
+    // First read misses, second one is answered from cache.
+    assert_eq!(cached.get(&ns, "a").await.unwrap().as_deref(), Some("1"));
+    assert_eq!(cached.get(&ns, "a").await.unwrap().as_deref(), Some("1"));
+
+    let stats = cached.cache_stats().unwrap();
+    assert_eq!((stats.hits, stats.misses, stats.size), (1, 1, 1));
+
+    // Missing keys are cached too.
+    assert_eq!(cached.get(&ns, "b").await.unwrap(), None);
+    assert_eq!(cached.get(&ns, "b").await.unwrap(), None);
+
+    // Local write invalidates key at once.
+    cached.set(&ns, "b", "2").await.unwrap();
+    assert_eq!(cached.get(&ns, "b").await.unwrap().as_deref(), Some("2"));
+
+    // Write of another manager is noticed through namespace version.
+    writer.set(&ns, "a", "3").await.unwrap();
+    assert_eq!(cached.get(&ns, "a").await.unwrap().as_deref(), Some("3"));
+
+    // Writes to other namespaces of the same table keep cached keys.
+    writer.set(&Namespace::Custom("other".to_string()), "a", "5").await.unwrap();
+    writer.set(&Namespace::User(42), "a", "5").await.unwrap();
+
+    let hits = cached.cache_stats().unwrap().hits;
+    assert_eq!(cached.get(&ns, "a").await.unwrap().as_deref(), Some("3"));
+    assert_eq!(cached.cache_stats().unwrap().hits, hits + 1);
+
+    // Capacity limit evicts least recently used key.
+    writer.set(&ns, "c", "4").await.unwrap();
+    assert_eq!(cached.mget(&ns, &["a", "b", "c"]).await.unwrap(), [
+        Some("3".to_string()), Some("2".to_string()), Some("4".to_string()),
+    ]);
+
+    let stats = cached.cache_stats().unwrap();
+    assert!(stats.evictions > 0);
+    assert!(stats.invalidations > 0);
+    assert_eq!(stats.size, 2);
+
+    // Legacy API reads and invalidates through the cache as well.
+    let setting = UserSettingKV {
+        user_id:        42,
+        settings_name:  "theme".to_string(),
+        settings_value: "dark".to_string(),
+    };
+
+    cached.add_user_setting(&setting).await.unwrap();
+    assert_eq!(cached.get_user_setting(42, "theme".to_string()).await.unwrap(), "dark");
+    assert!(cached.get_user_setting(42, "missing".to_string()).await.is_err());
+
+    cached.clear_cache();
+    assert_eq!(cached.cache_stats().unwrap().size, 0);
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn users_and_channels_hold_many_settings() {
+    let mut test_db = require_mysql!();
+    let kv_db = setup(&mut test_db, "KeyValueDB_ManyKeys").await;
+
+    let names = ["theme", "language", "timezone"];
+
+    for (i, name) in names.iter().enumerate() {
+        let setting = UserSettingKV {
+            user_id:        20100,
+            settings_name:  name.to_string(),
+            settings_value: format!("value_{i}"),
+        };
+
+        kv_db.add_user_setting(&setting).await.unwrap();
+        kv_db.set(&Namespace::Channel(20100), name, &format!("value_{i}")).await.unwrap();
+    }
+
+    let settings = kv_db.get_all_user_settings(20100).await.unwrap();
+    assert_eq!(settings, Some(json!({
+        "theme":    "value_0",
+        "language": "value_1",
+        "timezone": "value_2",
+    })));
+
+    for (i, name) in names.iter().enumerate() {
+        assert_eq!(kv_db.get_user_setting(20100, name.to_string()).await.unwrap(), format!("value_{i}"));
+    }
+
+    let values = kv_db.mget(&Namespace::Channel(20100), &names).await.unwrap();
+    assert!(values.iter().all(Option::is_some));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn legacy_kv_tables_are_migrated() {
+    let mut test_db = require_mysql!();
+
+    let config = test_db.database("KeyValueDB_Legacy");
+    let server = common::TestDb::pool(&test_db.server()).await;
+
+    sqlx::query(format!("CREATE DATABASE {};", config.database).as_str())
+        .execute(&server)
+        .await
+        .unwrap();
+
+    let pool = common::TestDb::pool(&config).await;
+
+    for (table, column) in [("Channel_Settings_KV", "channel_id"), ("User_Settings_KV", "user_id")] {
+        let query = format!(
+            "CREATE TABLE {table} ({column} BIGINT AUTO_INCREMENT UNIQUE, setting_name VARCHAR(255), \
+             setting_value VARCHAR(255), PRIMARY KEY({column}, setting_name));"
+        );
+        sqlx::query(query.as_str()).execute(&pool).await.unwrap();
+
+        let query = format!("INSERT INTO {table} ({column}, setting_name, setting_value) VALUES (20200, 'theme', 'dark');");
+        sqlx::query(query.as_str()).execute(&pool).await.unwrap();
+    }
+
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(config).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+
+    // Existing settings are kept and more keys may be added.
+    for namespace in [Namespace::Channel(20200), Namespace::User(20200)] {
+        assert_eq!(kv_db.get(&namespace, "theme").await.unwrap().as_deref(), Some("dark"));
+
+        kv_db.set(&namespace, "language", "en").await.unwrap();
+        assert_eq!(kv_db.get(&namespace, "language").await.unwrap().as_deref(), Some("en"));
+    }
+
+    let settings = kv_db.get_all_user_settings(20200).await.unwrap();
+    assert_eq!(settings, Some(json!({ "theme": "dark", "language": "en" })));
+}
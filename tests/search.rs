@@ -0,0 +1,157 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Full-text search integration tests.
+
+mod common;
+
+use chrono::NaiveDate;
+use common::TestDb;
+use dbproject::db::{
+    area::{Area, AreaDB},
+    global::GlobalDB,
+    search::{highlight, MessageQuery, ProfileQuery, SearchMode},
+};
+use sqlx::MySqlPool;
+
+async fn setup(test_db: &mut TestDb) -> (GlobalDB, MySqlPool, MySqlPool) {
+    let core = test_db.database("CoreDB");
+
+    let mut global_db = GlobalDB::new().with_core_db(&core.database);
+    global_db.connect(&test_db.server()).await.unwrap();
+
+    let russia = test_db.database("AreaDB_Russia");
+    global_db.insert(AreaDB::new(russia.clone(), Area::Russia)).await.unwrap();
+
+    let usa = test_db.database("AreaDB_USA");
+    global_db.insert(AreaDB::new(usa.clone(), Area::Usa)).await.unwrap();
+
+    (global_db, TestDb::pool(&russia).await, TestDb::pool(&usa).await)
+}
+
+async fn add_message(pool: &MySqlPool, channel_id: i64, user_id: i64, day: u32, text: &str) {
+    let timestamp = NaiveDate::from_ymd_opt(2025, 3, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+
+    sqlx::query("INSERT INTO Message (channel_id, user_id, message_text, timestamp) VALUES (?, ?, ?, ?);")
+        .bind(channel_id)
+        .bind(user_id)
+        .bind(text)
+        .bind(timestamp)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[test]
+fn highlight_marks_matched_words() {
+    let text = "Replication lag <b>spikes</b> during database backups";
+
+    assert_eq!(
+        highlight(text, "database replication", SearchMode::NaturalLanguage),
+        "<em>Replication</em> lag &lt;b&gt;spikes&lt;/b&gt; during <em>database</em> backups"
+    );
+
+    assert_eq!(
+        highlight("Replication during backups", "+back* -replication", SearchMode::Boolean),
+        "Replication during <em>backups</em>"
+    );
+
+    let long = (1..=60).map(|i| format!("word{i}")).collect::<Vec<_>>().join(" ");
+
+    assert_eq!(
+        highlight(&long, "word30", SearchMode::NaturalLanguage),
+        format!(
+            "…{} <em>word30</em> {}…",
+            (25..30).map(|i| format!("word{i}")).collect::<Vec<_>>().join(" "),
+            (31..45).map(|i| format!("word{i}")).collect::<Vec<_>>().join(" "),
+        )
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn messages_are_searched_by_area_and_globally() {
+    let mut test_db = require_mysql!();
+    let (global_db, russia, usa) = setup(&mut test_db).await;
+
+    add_message(&russia, 20001, 1, 1, "Database replication is lagging again").await;
+    add_message(&russia, 20002, 2, 2, "Replication replication replication everywhere").await;
+    add_message(&usa, 20001, 3, 3, "Who owns the replication dashboard?").await;
+    add_message(&usa, 20001, 3, 4, "Lunch is ready").await;
+
+    let query = MessageQuery::new("replication");
+    let hits  = global_db.search_messages(&query).await.unwrap();
+
+    assert_eq!(hits.len(), 3);
+    assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+    assert!(hits.iter().any(|hit| hit.area == Area::Usa));
+    assert!(hits.iter().all(|hit| hit.snippet.contains("<em>")));
+
+    // Filters by channel, user & date.
+    let query = MessageQuery::new("replication").channel(20001);
+    assert_eq!(global_db.search_messages(&query).await.unwrap().len(), 2);
+
+    let query = MessageQuery::new("replication").user(3);
+    assert_eq!(global_db.search_messages(&query).await.unwrap()[0].area, Area::Usa);
+
+    let since = NaiveDate::from_ymd_opt(2025, 3, 2).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let until = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let query = MessageQuery::new("replication").since(since).until(until);
+    let hits  = global_db.search_messages(&query).await.unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].user_id, Some(2));
+
+    // Boolean mode & limit.
+    let query = MessageQuery::new("+replication -database").mode(SearchMode::Boolean).limit(1);
+    let hits  = global_db.search_messages(&query).await.unwrap();
+
+    assert_eq!(hits.len(), 1);
+    assert!(!hits[0].message_text.as_deref().unwrap().contains("Database"));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn profiles_are_searched_by_bio_and_location() {
+    let mut test_db = require_mysql!();
+    let (global_db, russia, usa) = setup(&mut test_db).await;
+
+    let profiles = [
+        (&russia, 501, "Backend engineer who loves databases", "Kazan"),
+        (&usa, 502, "Frontend engineer", "Boston"),
+    ];
+
+    for (pool, user_id, bio, location) in profiles {
+        sqlx::query("INSERT INTO User_Profiles (user_id, bio, location) VALUES (?, ?, ?);")
+            .bind(user_id)
+            .bind(bio)
+            .bind(location)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    let hits = global_db.search_profiles(&ProfileQuery::new("engineer")).await.unwrap();
+    assert_eq!(hits.len(), 2);
+
+    let hits = global_db.search_profiles(&ProfileQuery::new("boston")).await.unwrap();
+    assert_eq!((hits.len(), hits[0].user_id, hits[0].area), (1, Some(502), Area::Usa));
+    assert!(hits[0].snippet.contains("<em>Boston</em>"));
+
+    let query = ProfileQuery::new("engineer").user(501);
+    let hits  = global_db.search_profiles(&query).await.unwrap();
+    assert_eq!((hits.len(), hits[0].area), (1, Area::Russia));
+}
@@ -0,0 +1,152 @@
+// DBProject - non-relational databases tasks.
+// Copyright (C) 2025 Alexander (@alkuzin).
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Settings import & export integration tests.
+
+mod common;
+
+use dbproject::db::{
+    error::DbError,
+    kvdb::{KeyValueDBManager, Namespace},
+    registry::SettingScope,
+    transfer::{read_records, write_records, ConflictPolicy, ImportAction, SettingRecord, TransferFormat},
+};
+
+fn record(namespace: &Namespace, name: &str, value: &str) -> SettingRecord {
+    SettingRecord::new(namespace, name, value).unwrap()
+}
+
+async fn setup(test_db: &mut common::TestDb, name: &str) -> KeyValueDBManager {
+    let mut kv_db = KeyValueDBManager::new();
+    kv_db.connect(test_db.database(name)).await.unwrap();
+    kv_db.set_procedures().await.unwrap();
+    kv_db
+}
+
+#[test]
+fn records_round_trip_through_json_and_csv() {
+    let expires_at = chrono::NaiveDate::from_ymd_opt(2030, 1, 2)
+        .unwrap()
+        .and_hms_micro_opt(3, 4, 5, 600_000)
+        .unwrap();
+
+    let mut records = vec![
+        record(&Namespace::Server, "motd", "hello, \"world\""),
+        record(&Namespace::Channel(20001), "slow_mode_seconds", "30"),
+        record(&Namespace::User(20002), "theme", "dark"),
+    ];
+    records[2].expires_at = Some(expires_at);
+
+    for format in [TransferFormat::Json, TransferFormat::Csv] {
+        let mut buffer = Vec::new();
+        write_records(&mut buffer, &records, format).unwrap();
+        assert_eq!(read_records(buffer.as_slice(), format).unwrap(), records);
+    }
+
+    let csv = "scope,scope_id,name,value,expires_at\nuser,7,theme,dark,\n";
+    let records = read_records(csv.as_bytes(), TransferFormat::Csv).unwrap();
+    assert_eq!(records, [record(&Namespace::User(7), "theme", "dark")]);
+
+    let csv = "scope,scope_id,name,value,expires_at\nglobal,,theme,dark,\n";
+    assert!(matches!(read_records(csv.as_bytes(), TransferFormat::Csv), Err(DbError::Csv(_))));
+}
+
+#[test]
+fn records_map_to_namespaces() {
+    assert!(SettingRecord::new(&Namespace::Custom("app".to_string()), "a", "1").is_none());
+
+    let mut server = record(&Namespace::Server, "motd", "hi");
+    assert_eq!(server.namespace().unwrap(), Namespace::Server);
+
+    server.scope_id = Some(1);
+    assert!(matches!(server.namespace(), Err(DbError::InvalidScope(_))));
+
+    let mut user = record(&Namespace::User(1), "theme", "dark");
+    assert_eq!(user.scope, SettingScope::User);
+
+    user.scope_id = None;
+    assert!(matches!(user.namespace(), Err(DbError::InvalidScope(_))));
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn settings_migrate_between_databases() {
+    let mut test_db = require_mysql!();
+
+    let source = setup(&mut test_db, "KeyValueDBExport").await;
+    let target = setup(&mut test_db, "KeyValueDBImport").await;
+
+    source.set(&Namespace::Server, "motd", "welcome").await.unwrap();
+    source.set(&Namespace::Channel(20001), "slow_mode_seconds", "30").await.unwrap();
+    source.set(&Namespace::User(20002), "theme", "dark").await.unwrap();
+
+    let mut buffer = Vec::new();
+    let exported   = source.export_settings().await.unwrap();
+    write_records(&mut buffer, &exported, TransferFormat::Csv).unwrap();
+
+    let records = read_records(buffer.as_slice(), TransferFormat::Csv).unwrap();
+    assert_eq!(records, exported);
+
+    let report = target.import_settings(&records, ConflictPolicy::Overwrite, false).await.unwrap();
+    assert!(report.applied);
+    assert_eq!(report.changes.len(), records.len());
+
+    assert_eq!(target.get(&Namespace::Server, "motd").await.unwrap().as_deref(), Some("welcome"));
+    assert_eq!(target.get(&Namespace::Channel(20001), "slow_mode_seconds").await.unwrap().as_deref(), Some("30"));
+    assert_eq!(target.get(&Namespace::User(20002), "theme").await.unwrap().as_deref(), Some("dark"));
+
+    // Importing the same records again changes nothing.
+    let report = target.import_settings(&records, ConflictPolicy::Fail, false).await.unwrap();
+    assert_eq!(report.count(ImportAction::Unchanged), records.len());
+}
+
+#[tokio::test]
+#[ignore = "requires MySQL server given by DBPROJECT_TEST_MYSQL"]
+async fn import_applies_conflict_policy_and_dry_run() {
+    let mut test_db = require_mysql!();
+    let kv_db       = setup(&mut test_db, "KeyValueDBConflicts").await;
+
+    kv_db.set(&Namespace::User(20010), "theme", "light").await.unwrap();
+
+    let records = [
+        record(&Namespace::User(20010), "theme", "dark"),
+        record(&Namespace::User(20011), "theme", "dark"),
+    ];
+
+    // Dry run reports changes without writing them.
+    let report = kv_db.import_settings(&records, ConflictPolicy::Overwrite, true).await.unwrap();
+    assert!(!report.applied);
+    assert_eq!(report.changes[0].action, ImportAction::Update);
+    assert_eq!(report.changes[0].previous.as_deref(), Some("light"));
+    assert_eq!(report.changes[1].action, ImportAction::Insert);
+    assert_eq!(kv_db.get(&Namespace::User(20011), "theme").await.unwrap(), None);
+
+    // Conflict aborts whole import.
+    let result = kv_db.import_settings(&[records[1].clone(), records[0].clone()], ConflictPolicy::Fail, false).await;
+    assert!(matches!(result, Err(DbError::ImportConflict(_))));
+    assert_eq!(kv_db.get(&Namespace::User(20011), "theme").await.unwrap(), None);
+
+    // Skipped conflicts keep stored values.
+    let report = kv_db.import_settings(&records, ConflictPolicy::Skip, false).await.unwrap();
+    assert_eq!(report.count(ImportAction::Skip), 1);
+    assert_eq!(report.count(ImportAction::Insert), 1);
+    assert_eq!(kv_db.get(&Namespace::User(20010), "theme").await.unwrap().as_deref(), Some("light"));
+    assert_eq!(kv_db.get(&Namespace::User(20011), "theme").await.unwrap().as_deref(), Some("dark"));
+
+    // Duplicate records are rejected.
+    let result = kv_db.import_settings(&[records[0].clone(), records[0].clone()], ConflictPolicy::Skip, true).await;
+    assert!(matches!(result, Err(DbError::InvalidValue(_))));
+}